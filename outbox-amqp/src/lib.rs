@@ -0,0 +1,409 @@
+//! RabbitMQ / AMQP 0-9-1 transport for `outbox-core`, built on `lapin`.
+//!
+//! [`AmqpTransport`] publishes every event to an exchange on a channel in
+//! confirm mode and waits for the broker's ack, so `publish` only returns
+//! `Ok` once RabbitMQ has taken responsibility for the message. Messages are
+//! published `mandatory`: one that no queue is bound for comes back as an
+//! error instead of being dropped.
+//!
+//! The exchange and routing key are chosen per event type — the exchange
+//! given to [`AmqpTransport::new`] and the event type as routing key, unless
+//! [`with_route`](AmqpTransport::with_route) says otherwise. The transport
+//! declares nothing; exchanges, queues and bindings are the application's
+//! topology.
+//!
+//! The connection is opened with `lapin`'s automatic recovery. When a
+//! publish fails because the connection dropped, the transport waits for the
+//! connection and channel to be recovered and publishes once more.
+
+use async_trait::async_trait;
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions};
+use lapin::types::{AMQPValue, FieldTable, LongString, ShortString};
+use lapin::{BasicProperties, Channel, Confirmation, Connection, ConnectionProperties};
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
+use outbox_core::prelude::{Event, EventType, OutboxError, Transport};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use tracing::{error, warn};
+
+/// AMQP `delivery_mode` of messages that survive a broker restart.
+const PERSISTENT: u8 = 2;
+
+pub struct AmqpTransport {
+    // Kept so the connection lives as long as the transport.
+    _connection: Connection,
+    channel: Channel,
+    exchange: String,
+    routes: HashMap<String, Route>,
+}
+
+/// Where events of one type are published.
+#[derive(Debug, Clone)]
+struct Route {
+    exchange: String,
+    routing_key: String,
+}
+
+impl AmqpTransport {
+    /// Connects to the broker at `uri`, opens a channel in confirm mode and
+    /// creates a transport that publishes to `exchange` with the event type
+    /// as routing key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::InfrastructureError`] if the connection or the
+    /// channel cannot be opened, or the broker refuses confirm mode.
+    pub async fn new(uri: &str, exchange: &str) -> Result<Self, OutboxError> {
+        Self::with_properties(uri, exchange, ConnectionProperties::default()).await
+    }
+
+    /// Like [`new`](Self::new), with custom connection properties. Automatic
+    /// recovery is enabled on top of them.
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Self::new).
+    pub async fn with_properties(
+        uri: &str,
+        exchange: &str,
+        properties: ConnectionProperties,
+    ) -> Result<Self, OutboxError> {
+        let connection = Connection::connect(uri, properties.enable_auto_recover())
+            .await
+            .map_err(|e| {
+                error!("AMQP connection failed: {:?}", e);
+                OutboxError::InfrastructureError(format!("AMQP connection failed: {e}"))
+            })?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| OutboxError::InfrastructureError(format!("AMQP channel failed: {e}")))?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(|e| {
+                OutboxError::InfrastructureError(format!("AMQP confirm mode failed: {e}"))
+            })?;
+        Ok(Self {
+            _connection: connection,
+            channel,
+            exchange: exchange.to_owned(),
+            routes: HashMap::new(),
+        })
+    }
+
+    /// Publishes events of `event_type` to `exchange` with `routing_key`
+    /// instead of the default exchange and the event type.
+    #[must_use]
+    pub fn with_route(mut self, event_type: &str, exchange: &str, routing_key: &str) -> Self {
+        self.routes.insert(
+            event_type.to_owned(),
+            Route {
+                exchange: exchange.to_owned(),
+                routing_key: routing_key.to_owned(),
+            },
+        );
+        self
+    }
+
+    fn route_for(&self, event_type: &EventType) -> Route {
+        self.routes
+            .get(event_type.as_str())
+            .cloned()
+            .unwrap_or_else(|| Route {
+                exchange: self.exchange.clone(),
+                routing_key: event_type.as_str().to_owned(),
+            })
+    }
+
+    /// Publishes one message and waits for its confirmation. A publish that
+    /// fails because the connection dropped is retried once after recovery.
+    /// Only a plain ack is a success: a returned or nacked message fails, and
+    /// so does a channel that lost confirm mode, since nothing was confirmed.
+    async fn send<PT>(&self, event: &Event<PT>, headers: FieldTable) -> Result<(), OutboxError>
+    where
+        PT: Serialize,
+    {
+        let payload = serde_json::to_vec(&event.payload)
+            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
+        let route = self.route_for(&event.event_type);
+        let exchange = short_string(&route.exchange)?;
+        let routing_key = short_string(&route.routing_key)?;
+        let properties = message_properties(event, headers)?;
+
+        let confirmation = match self
+            .try_send(&exchange, &routing_key, &payload, properties.clone())
+            .await
+        {
+            Ok(confirmation) => confirmation,
+            Err(e) => {
+                warn!("AMQP publish failed, waiting for recovery: {:?}", e);
+                self.channel
+                    .wait_for_recovery(e)
+                    .await
+                    .map_err(|e| OutboxError::BrokerError(e.to_string()))?;
+                self.try_send(&exchange, &routing_key, &payload, properties)
+                    .await
+                    .map_err(|e| OutboxError::BrokerError(e.to_string()))?
+            }
+        };
+
+        match confirmation {
+            Confirmation::Ack(None) => Ok(()),
+            Confirmation::NotRequested => Err(OutboxError::BrokerError(format!(
+                "Publisher confirms are not enabled on the channel to {}",
+                route.exchange
+            ))),
+            Confirmation::Ack(Some(returned)) => Err(OutboxError::BrokerError(format!(
+                "Message returned by {}: {} {}",
+                route.exchange, returned.reply_code, returned.reply_text
+            ))),
+            Confirmation::Nack(_) => Err(OutboxError::BrokerError(format!(
+                "Message nacked by {}",
+                route.exchange
+            ))),
+        }
+    }
+
+    async fn try_send(
+        &self,
+        exchange: &ShortString,
+        routing_key: &ShortString,
+        payload: &[u8],
+        properties: BasicProperties,
+    ) -> lapin::Result<Confirmation> {
+        self.channel
+            .basic_publish(
+                exchange.clone(),
+                routing_key.clone(),
+                BasicPublishOptions {
+                    mandatory: true,
+                    ..BasicPublishOptions::default()
+                },
+                payload,
+                properties,
+            )
+            .await?
+            .await
+    }
+}
+
+/// Maps the event onto AMQP message properties: `message_id` is the event
+/// id, `type` the event type, and the id, type, creation time and
+/// idempotency token are added to `headers`.
+fn message_properties<PT>(
+    event: &Event<PT>,
+    mut headers: FieldTable,
+) -> Result<BasicProperties, OutboxError> {
+    let id = event.id.as_uuid().to_string();
+    headers.insert("event_id".into(), long_string(&id));
+    headers.insert("event_type".into(), long_string(event.event_type.as_str()));
+    headers.insert(
+        "created_at".into(),
+        long_string(&event.created_at.to_string()),
+    );
+    if let Some(token) = &event.idempotency_token {
+        headers.insert("idempotency_token".into(), long_string(token.as_str()));
+    }
+
+    Ok(BasicProperties::default()
+        .with_message_id(id.into())
+        .with_type(short_string(event.event_type.as_str())?)
+        .with_content_type("application/json".into())
+        .with_delivery_mode(PERSISTENT)
+        .with_timestamp(u64::try_from(event.created_at.unix_timestamp()).unwrap_or(0))
+        .with_headers(headers))
+}
+
+fn long_string(value: &str) -> AMQPValue {
+    AMQPValue::LongString(LongString::from(value))
+}
+
+/// Exchange names, routing keys and the `type` property are limited to 255
+/// bytes by the protocol.
+fn short_string(value: &str) -> Result<ShortString, OutboxError> {
+    ShortString::try_new(value).map_err(|e| {
+        OutboxError::InfrastructureError(format!("Invalid AMQP short string '{value}': {e}"))
+    })
+}
+
+#[async_trait]
+impl<PT> Transport<PT> for AmqpTransport
+where
+    PT: Debug + Clone + Send + Sync + Serialize + 'static,
+{
+    async fn publish(&self, event: Event<PT>) -> Result<(), OutboxError> {
+        self.send(&event, FieldTable::default()).await
+    }
+
+    /// `dlq_failure_count` is sent as an unsigned integer field; the other
+    /// `dlq_*` headers are strings.
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<PT>) -> Result<(), OutboxError> {
+        let mut headers = FieldTable::default();
+        headers.insert(
+            "dlq_failure_count".into(),
+            AMQPValue::LongUInt(letter.failure_count),
+        );
+        headers.insert(
+            "dlq_quarantined_at".into(),
+            long_string(&letter.quarantined_at.to_string()),
+        );
+        if let Some(last_error) = &letter.last_error {
+            headers.insert("dlq_last_error".into(), long_string(last_error));
+        }
+        if let Some(first_failed_at) = letter.first_failed_at {
+            headers.insert(
+                "dlq_first_failed_at".into(),
+                long_string(&first_failed_at.to_string()),
+            );
+        }
+
+        self.send(&letter.event, headers).await
+    }
+}
+
+/// The tests that talk to a broker are ignored by default. Point
+/// `OUTBOX_AMQP_URL` at a RabbitMQ server and run
+/// `cargo test -p outbox-amqp -- --ignored`.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use lapin::ExchangeKind;
+    use lapin::options::{
+        BasicGetOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+        QueueDeleteOptions,
+    };
+    use outbox_core::prelude::{IdempotencyToken, Payload};
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, Serialize)]
+    struct TestPayload {
+        order: u32,
+    }
+
+    fn url() -> String {
+        std::env::var("OUTBOX_AMQP_URL").unwrap_or_else(|_| "amqp://127.0.0.1:5672/%2f".to_string())
+    }
+
+    fn event(event_type: &str, token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new(event_type),
+            Payload::new(TestPayload { order: 7 }),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    fn header<'a>(properties: &'a BasicProperties, name: &str) -> Option<&'a AMQPValue> {
+        properties
+            .headers()
+            .as_ref()
+            .and_then(|headers| headers.inner().get(name))
+    }
+
+    #[rstest]
+    fn message_properties_map_id_type_and_token() {
+        let event = event("OrderCreated", Some("token-1"));
+
+        let properties = message_properties(&event, FieldTable::default()).unwrap();
+
+        let id = event.id.as_uuid().to_string();
+        assert_eq!(
+            properties.message_id().as_ref().map(ShortString::as_str),
+            Some(id.as_str())
+        );
+        assert_eq!(
+            properties.kind().as_ref().map(ShortString::as_str),
+            Some("OrderCreated")
+        );
+        assert_eq!(*properties.delivery_mode(), Some(PERSISTENT));
+        assert_eq!(header(&properties, "event_id"), Some(&long_string(&id)));
+        assert_eq!(
+            header(&properties, "idempotency_token"),
+            Some(&long_string("token-1"))
+        );
+    }
+
+    #[rstest]
+    fn message_properties_skip_missing_token() {
+        let properties =
+            message_properties(&event("OrderCreated", None), FieldTable::default()).unwrap();
+
+        assert!(header(&properties, "idempotency_token").is_none());
+    }
+
+    #[rstest]
+    fn overlong_names_are_rejected() {
+        assert!(short_string(&"x".repeat(256)).is_err());
+        assert!(short_string(&"x".repeat(255)).is_ok());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a RabbitMQ server at OUTBOX_AMQP_URL"]
+    async fn publish_is_confirmed_and_routed_by_event_type() {
+        let exchange = format!("outbox_test_{}", std::process::id());
+        let transport = AmqpTransport::new(&url(), &exchange).await.unwrap();
+        let channel = &transport.channel;
+        channel
+            .exchange_declare(
+                exchange.as_str().into(),
+                ExchangeKind::Direct,
+                ExchangeDeclareOptions {
+                    auto_delete: true,
+                    ..ExchangeDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .unwrap();
+        let queue = channel
+            .queue_declare(
+                "".into(),
+                QueueDeclareOptions {
+                    exclusive: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .unwrap();
+        channel
+            .queue_bind(
+                queue.name().clone(),
+                exchange.as_str().into(),
+                "OrderCreated".into(),
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .unwrap();
+        let created = event("OrderCreated", Some("token-1"));
+
+        transport.publish(created.clone()).await.unwrap();
+        let unroutable = transport.publish(event("OrderPaid", None)).await;
+
+        let message = channel
+            .basic_get(queue.name().clone(), BasicGetOptions { no_ack: true })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.delivery.data, b"{\"order\":7}");
+        assert_eq!(
+            message
+                .delivery
+                .properties
+                .message_id()
+                .as_ref()
+                .map(ShortString::as_str),
+            Some(created.id.as_uuid().to_string().as_str())
+        );
+        assert!(matches!(unroutable, Err(OutboxError::BrokerError(_))));
+        channel
+            .queue_delete(queue.name().clone(), QueueDeleteOptions::default())
+            .await
+            .unwrap();
+    }
+}
@@ -34,6 +34,8 @@
 
 use crate::config::OutboxConfig;
 #[cfg(feature = "dlq")]
+use crate::dlq::observer::DlqObserver;
+#[cfg(feature = "dlq")]
 use crate::dlq::storage::DlqHeap;
 use crate::error::OutboxError;
 use crate::manager::OutboxManager;
@@ -63,6 +65,8 @@ use tokio::sync::watch::Receiver;
 /// | [`config`](Self::config) | yes | fails `build()` if missing |
 /// | [`shutdown_rx`](Self::shutdown_rx) | yes | fails `build()` if missing |
 /// | [`dlq_heap`](Self::dlq_heap) | yes *(feature `dlq` only)* | fails `build()` if missing when feature is on |
+/// | [`dlq_observer`](Self::dlq_observer) | no *(feature `dlq` only)* | may be called several times |
+/// | [`dlq_destination`](Self::dlq_destination) | no *(feature `dlq` only)* | replaces the dead-letter table |
 pub struct OutboxManagerBuilder<S, P, PT>
 where
     PT: Debug + Clone + Serialize,
@@ -73,6 +77,10 @@ where
     shutdown_rx: Option<Receiver<bool>>,
     #[cfg(feature = "dlq")]
     dlq_heap: Option<Arc<dyn DlqHeap>>,
+    #[cfg(feature = "dlq")]
+    dlq_observers: Vec<Arc<dyn DlqObserver>>,
+    #[cfg(feature = "dlq")]
+    dlq_destination: Option<Arc<dyn Transport<PT>>>,
 }
 impl<S, P, PT> Default for OutboxManagerBuilder<S, P, PT>
 where
@@ -86,6 +94,10 @@ where
             shutdown_rx: None,
             #[cfg(feature = "dlq")]
             dlq_heap: None,
+            #[cfg(feature = "dlq")]
+            dlq_observers: Vec::new(),
+            #[cfg(feature = "dlq")]
+            dlq_destination: None,
         }
     }
 }
@@ -147,6 +159,25 @@ where
         self
     }
 
+    /// Adds an observer that is notified every time the DLQ reaper
+    /// quarantines events. Observers are called in registration order.
+    #[cfg(feature = "dlq")]
+    #[must_use]
+    pub fn dlq_observer(mut self, observer: Arc<dyn DlqObserver>) -> Self {
+        self.dlq_observers.push(observer);
+        self
+    }
+
+    /// Sends quarantined events to `destination` — for example a Kafka
+    /// `*.DLQ` topic — instead of the storage's dead-letter table. Events are
+    /// deleted from the outbox only after the publish succeeded.
+    #[cfg(feature = "dlq")]
+    #[must_use]
+    pub fn dlq_destination(mut self, destination: Arc<dyn Transport<PT>>) -> Self {
+        self.dlq_destination = Some(destination);
+        self
+    }
+
     /// Consumes the builder and returns a fully wired [`OutboxManager`].
     ///
     /// # Errors
@@ -171,7 +202,9 @@ where
             self.shutdown_rx.ok_or_else(|| {
                 OutboxError::ConfigError("Shutdown channel is missing".to_string())
             })?,
-        ));
+        )
+        .with_dlq_observers(self.dlq_observers)
+        .with_dlq_destination(self.dlq_destination));
         #[cfg(not(feature = "dlq"))]
         return Ok(OutboxManager::new(
             self.storage
@@ -189,9 +222,11 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::panic)]
 mod tests {
     use super::*;
     use crate::config::IdempotencyStrategy;
+    #[cfg(feature = "dlq")]
     use crate::dlq::storage::MockDlqHeap;
     use crate::publisher::MockTransport;
     use crate::storage::MockOutboxStorage;
@@ -244,6 +279,37 @@ mod tests {
         assert!(outbox_manager.is_ok());
     }
 
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    fn build_accepts_multiple_dlq_observers() {
+        let (_tx, rx) = watch::channel(false);
+        let result = Builder::new()
+            .storage(Arc::new(MockOutboxStorage::new()))
+            .publisher(Arc::new(MockTransport::new()))
+            .config(default_config())
+            .shutdown_rx(rx)
+            .dlq_heap(Arc::new(MockDlqHeap::new()))
+            .dlq_observer(Arc::new(|_: &[crate::dlq::model::DlqEntry]| {}))
+            .dlq_observer(Arc::new(crate::dlq::observer::MockDlqObserver::new()))
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    fn build_accepts_dlq_destination() {
+        let (_tx, rx) = watch::channel(false);
+        let result = Builder::new()
+            .storage(Arc::new(MockOutboxStorage::new()))
+            .publisher(Arc::new(MockTransport::new()))
+            .config(default_config())
+            .shutdown_rx(rx)
+            .dlq_heap(Arc::new(MockDlqHeap::new()))
+            .dlq_destination(Arc::new(MockTransport::new()))
+            .build();
+        assert!(result.is_ok());
+    }
+
     type Builder = OutboxManagerBuilder<
         MockOutboxStorage<SomeDomainEvent>,
         MockTransport<SomeDomainEvent>,
@@ -292,13 +358,11 @@ mod tests {
     fn new_matches_default() {
         let r1 = Builder::new().build();
         let r2 = Builder::default().build();
-        let m1 = match r1 {
-            Err(OutboxError::ConfigError(m)) => m,
-            _ => panic!("new().build() should fail with ConfigError"),
+        let Err(OutboxError::ConfigError(m1)) = r1 else {
+            panic!("new().build() should fail with ConfigError");
         };
-        let m2 = match r2 {
-            Err(OutboxError::ConfigError(m)) => m,
-            _ => panic!("default().build() should fail with ConfigError"),
+        let Err(OutboxError::ConfigError(m2)) = r2 else {
+            panic!("default().build() should fail with ConfigError");
         };
         assert_eq!(m1, m2);
     }
@@ -0,0 +1,157 @@
+//! Source of the current time for everything the outbox timestamps.
+//!
+//! Event creation, lock expiry, retention cutoffs and DLQ bookkeeping all ask
+//! a [`Clock`] for "now" instead of reading the wall clock directly. The
+//! default [`SystemClock`] reads it; [`ManualClock`] only moves when told to,
+//! so tests of retention, locks and revival cooldowns do not depend on how
+//! long they take to run.
+//!
+//! The clock is carried on [`OutboxConfig::clock`](crate::config::OutboxConfig::clock),
+//! so every component sharing a config shares its time. Interval timers
+//! (polling, garbage collection, the DLQ reaper) still tick on `tokio` time,
+//! which tests pause and advance with `tokio::time::pause`.
+
+use crate::error::OutboxError;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Tells the current time, in UTC.
+pub trait Clock: Debug + Send + Sync {
+    /// The current time.
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// Microseconds since the Unix epoch, saturating at the `i64` range.
+///
+/// Storage adapters without a timestamp type (SQLite, Redis) persist times
+/// this way, which keeps them ordered and comparable by the store itself.
+#[must_use]
+pub fn to_unix_micros(at: OffsetDateTime) -> i64 {
+    i64::try_from(at.unix_timestamp_nanos() / 1_000).unwrap_or(if at < OffsetDateTime::UNIX_EPOCH {
+        i64::MIN
+    } else {
+        i64::MAX
+    })
+}
+
+/// Reads back a time written by [`to_unix_micros`].
+///
+/// # Errors
+///
+/// [`OutboxError::DatabaseError`] if `micros` lies outside the range
+/// [`OffsetDateTime`] can represent.
+pub fn from_unix_micros(micros: i64) -> Result<OffsetDateTime, OutboxError> {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(micros) * 1_000)
+        .map_err(|e| OutboxError::DatabaseError(format!("invalid timestamp {micros}: {e}")))
+}
+
+/// [`Clock`] backed by the system's wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// [`Clock`] that stands still until [`advance`](Self::advance) or
+/// [`set`](Self::set) moves it.
+///
+/// Clones share the same time, so keep one in the test and put another into
+/// the [`OutboxConfig`](crate::config::OutboxConfig).
+///
+/// ```
+/// use outbox_core::prelude::*;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # #[derive(Debug, Clone, serde::Serialize)]
+/// # struct MyEvent;
+/// let clock = ManualClock::new(time::OffsetDateTime::UNIX_EPOCH);
+/// let config: OutboxConfig<MyEvent> =
+///     OutboxConfig::default().with_clock(Arc::new(clock.clone()));
+///
+/// clock.advance(Duration::from_hours(1));
+/// assert_eq!(config.clock.now().hour(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<OffsetDateTime>>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at `start`.
+    pub fn new(start: OffsetDateTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(PoisonError::into_inner);
+        *now += by;
+    }
+
+    /// Moves the clock to `to`, which may lie in the past.
+    pub fn set(&self, to: OffsetDateTime) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = to;
+    }
+}
+
+impl Default for ManualClock {
+    /// A clock stopped at the current wall-clock time.
+    fn default() -> Self {
+        Self::new(OffsetDateTime::now_utc())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn system_clock_reads_wall_clock() {
+        let before = OffsetDateTime::now_utc();
+        let now = SystemClock.now();
+        assert!(now >= before && now <= OffsetDateTime::now_utc());
+    }
+
+    #[rstest]
+    fn manual_clock_stands_still_until_advanced() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(clock.now(), OffsetDateTime::UNIX_EPOCH);
+
+        clock.advance(Duration::from_mins(90));
+        assert_eq!(
+            clock.now(),
+            OffsetDateTime::UNIX_EPOCH + time::Duration::minutes(90)
+        );
+    }
+
+    #[rstest]
+    fn manual_clock_clones_share_time() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let shared = clock.clone();
+
+        shared.set(OffsetDateTime::UNIX_EPOCH + time::Duration::days(3));
+        assert_eq!(clock.now(), shared.now());
+    }
+
+    #[rstest]
+    fn unix_micros_round_trip_at_microsecond_precision() {
+        let at = OffsetDateTime::UNIX_EPOCH + time::Duration::microseconds(1_234_567);
+        assert_eq!(from_unix_micros(to_unix_micros(at)).ok(), Some(at));
+        assert_eq!(to_unix_micros(OffsetDateTime::UNIX_EPOCH), 0);
+    }
+}
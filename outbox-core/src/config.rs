@@ -6,9 +6,12 @@
 //! intervals, lock timeout, and which [`IdempotencyStrategy`] to apply when
 //! new events are written.
 
+use crate::clock::{Clock, SystemClock};
 use crate::model::Event;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 
 /// Runtime configuration shared by the producer and worker sides.
 ///
@@ -16,8 +19,10 @@ use std::fmt::Debug;
 /// [`Custom`](IdempotencyStrategy::Custom) strategy variant holds a function
 /// pointer of type `fn(&Event<P>) -> String`.
 ///
-/// All fields are public so callers can construct the struct with a literal or
-/// start from [`default`](Self::default) and override selected fields.
+/// The struct is `#[non_exhaustive]` so new knobs can be added without a
+/// breaking change: start from [`default`](Self::default) and override
+/// selected fields with the `with_*` setters. The fields stay public for
+/// reading and for assignment on a mutable value.
 ///
 /// # Example
 ///
@@ -26,15 +31,14 @@ use std::fmt::Debug;
 ///
 /// # #[derive(Debug, Clone, serde::Serialize)]
 /// # struct MyEvent;
-/// let cfg: OutboxConfig<MyEvent> = OutboxConfig {
-///     batch_size: 200,
-///     poll_interval_secs: 2,
-///     ..OutboxConfig::default()
-/// };
+/// let cfg: OutboxConfig<MyEvent> = OutboxConfig::default()
+///     .with_batch_size(200)
+///     .with_poll_interval_secs(2);
 /// assert_eq!(cfg.batch_size, 200);
 /// assert_eq!(cfg.retention_days, 7); // inherited from default
 /// ```
 #[derive(Clone)]
+#[non_exhaustive]
 pub struct OutboxConfig<P>
 where
     P: Debug + Clone + Serialize,
@@ -58,6 +62,10 @@ where
     /// How idempotency tokens are produced for newly written events. See
     /// [`IdempotencyStrategy`] for the available variants.
     pub idempotency_strategy: IdempotencyStrategy<P>,
+    /// Where "now" comes from: event creation times, processing locks,
+    /// retention and DLQ cutoffs. Swap in a
+    /// [`ManualClock`](crate::clock::ManualClock) to control time in tests.
+    pub clock: Arc<dyn Clock>,
     /// Failure count at which an event becomes eligible for quarantine. Events
     /// whose failure counter reaches this value are returned by
     /// [`DlqHeap::drain_exceeded`](crate::dlq::storage::DlqHeap::drain_exceeded)
@@ -65,12 +73,69 @@ where
     ///
     /// Only consulted when the `dlq` feature is enabled.
     pub dlq_threshold: u32,
+    /// Per-event-type overrides of [`dlq_threshold`](Self::dlq_threshold),
+    /// keyed by event type name. Types without an entry use the global
+    /// threshold.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_type_thresholds: HashMap<String, u32>,
+    /// When set, events that have been failing for at least this many seconds
+    /// are quarantined regardless of their failure count. Measured from the
+    /// first recorded failure.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_max_failure_age_secs: Option<u64>,
     /// Interval between dead-letter reaper passes, in seconds. Each pass drains
     /// events that have crossed [`dlq_threshold`](Self::dlq_threshold) and hands
     /// them off for quarantine.
     ///
     /// Only consulted when the `dlq` feature is enabled.
     pub dlq_interval_secs: u64,
+    /// Maximum number of entries one reaper pass drains from the
+    /// [`DlqHeap`](crate::dlq::storage::DlqHeap). Anything beyond it stays
+    /// eligible and is drained on the next pass.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_batch_size: u32,
+    /// How long quarantined events are kept before the garbage collector
+    /// purges them from the dead-letter table, measured in days.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_retention_days: i64,
+    /// How long an entry drained from the [`DlqHeap`](crate::dlq::storage::DlqHeap)
+    /// may stay unacknowledged before the reaper hands it back to the heap,
+    /// in seconds. Covers quarantines that failed or reapers that crashed
+    /// mid-pass.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_claim_timeout_secs: u64,
+    /// When set, quarantined events are moved back into the active outbox
+    /// once they have sat in the dead-letter table for this many seconds,
+    /// giving outages that resolve themselves a chance to clear. `None`
+    /// disables automatic revival.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_revival_cooldown_secs: Option<u64>,
+    /// How many times one event may be revived from the dead-letter table.
+    /// An event quarantined again after its last revival stays dead until an
+    /// operator requeues or deletes it.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_max_revivals: u32,
+    /// How often, in seconds, the DLQ reaper reconciles the heap with the
+    /// outbox: failure counters for events that no longer exist in the outbox
+    /// are removed, and idle counters are expired (see
+    /// `dlq_idle_expiry_secs`).
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_reconcile_interval_secs: u64,
+    /// When set, failure counters that have not been touched by a failed
+    /// publish for this many seconds are dropped from the heap on the next
+    /// reconciliation. `None` keeps them until the event succeeds, is
+    /// quarantined or disappears from the outbox.
+    ///
+    /// Only consulted when the `dlq` feature is enabled.
+    pub dlq_idle_expiry_secs: Option<u64>,
 }
 
 impl<P> Default for OutboxConfig<P>
@@ -89,8 +154,18 @@ where
     /// | `poll_interval_secs` | 10 |
     /// | `lock_timeout_mins` | 5 |
     /// | `idempotency_strategy` | [`IdempotencyStrategy::None`] |
+    /// | `clock` | [`SystemClock`] |
     /// | `dlq_threshold` | 10 |
+    /// | `dlq_type_thresholds` | empty |
+    /// | `dlq_max_failure_age_secs` | `None` |
     /// | `dlq_interval_secs` | 300 |
+    /// | `dlq_batch_size` | 1000 |
+    /// | `dlq_retention_days` | 30 |
+    /// | `dlq_claim_timeout_secs` | 600 |
+    /// | `dlq_revival_cooldown_secs` | `None` |
+    /// | `dlq_max_revivals` | 3 |
+    /// | `dlq_reconcile_interval_secs` | 3600 |
+    /// | `dlq_idle_expiry_secs` | `None` |
     ///
     /// These values are part of the public contract — tuning them is a
     /// deliberate behaviour change.
@@ -102,12 +177,165 @@ where
             poll_interval_secs: 10,
             lock_timeout_mins: 5,
             idempotency_strategy: IdempotencyStrategy::None,
+            clock: Arc::new(SystemClock),
             dlq_threshold: 10,
+            dlq_type_thresholds: HashMap::new(),
+            dlq_max_failure_age_secs: None,
             dlq_interval_secs: 300,
+            dlq_batch_size: 1000,
+            dlq_retention_days: 30,
+            dlq_claim_timeout_secs: 600,
+            dlq_revival_cooldown_secs: None,
+            dlq_max_revivals: 3,
+            dlq_reconcile_interval_secs: 3600,
+            dlq_idle_expiry_secs: None,
         }
     }
 }
 
+impl<P> OutboxConfig<P>
+where
+    P: Debug + Clone + Serialize,
+{
+    /// Sets [`batch_size`](Self::batch_size).
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets [`retention_days`](Self::retention_days).
+    #[must_use]
+    pub fn with_retention_days(mut self, retention_days: i64) -> Self {
+        self.retention_days = retention_days;
+        self
+    }
+
+    /// Sets [`gc_interval_secs`](Self::gc_interval_secs).
+    #[must_use]
+    pub fn with_gc_interval_secs(mut self, gc_interval_secs: u64) -> Self {
+        self.gc_interval_secs = gc_interval_secs;
+        self
+    }
+
+    /// Sets [`poll_interval_secs`](Self::poll_interval_secs).
+    #[must_use]
+    pub fn with_poll_interval_secs(mut self, poll_interval_secs: u64) -> Self {
+        self.poll_interval_secs = poll_interval_secs;
+        self
+    }
+
+    /// Sets [`lock_timeout_mins`](Self::lock_timeout_mins).
+    #[must_use]
+    pub fn with_lock_timeout_mins(mut self, lock_timeout_mins: i64) -> Self {
+        self.lock_timeout_mins = lock_timeout_mins;
+        self
+    }
+
+    /// Sets [`idempotency_strategy`](Self::idempotency_strategy).
+    #[must_use]
+    pub fn with_idempotency_strategy(
+        mut self,
+        idempotency_strategy: IdempotencyStrategy<P>,
+    ) -> Self {
+        self.idempotency_strategy = idempotency_strategy;
+        self
+    }
+
+    /// Sets [`clock`](Self::clock).
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets [`dlq_threshold`](Self::dlq_threshold).
+    #[must_use]
+    pub fn with_dlq_threshold(mut self, dlq_threshold: u32) -> Self {
+        self.dlq_threshold = dlq_threshold;
+        self
+    }
+
+    /// Adds an entry to [`dlq_type_thresholds`](Self::dlq_type_thresholds),
+    /// replacing any earlier threshold for `event_type`.
+    #[must_use]
+    pub fn with_dlq_type_threshold(
+        mut self,
+        event_type: impl Into<String>,
+        threshold: u32,
+    ) -> Self {
+        self.dlq_type_thresholds
+            .insert(event_type.into(), threshold);
+        self
+    }
+
+    /// Sets [`dlq_max_failure_age_secs`](Self::dlq_max_failure_age_secs).
+    #[must_use]
+    pub fn with_dlq_max_failure_age_secs(mut self, dlq_max_failure_age_secs: Option<u64>) -> Self {
+        self.dlq_max_failure_age_secs = dlq_max_failure_age_secs;
+        self
+    }
+
+    /// Sets [`dlq_interval_secs`](Self::dlq_interval_secs).
+    #[must_use]
+    pub fn with_dlq_interval_secs(mut self, dlq_interval_secs: u64) -> Self {
+        self.dlq_interval_secs = dlq_interval_secs;
+        self
+    }
+
+    /// Sets [`dlq_batch_size`](Self::dlq_batch_size).
+    #[must_use]
+    pub fn with_dlq_batch_size(mut self, dlq_batch_size: u32) -> Self {
+        self.dlq_batch_size = dlq_batch_size;
+        self
+    }
+
+    /// Sets [`dlq_retention_days`](Self::dlq_retention_days).
+    #[must_use]
+    pub fn with_dlq_retention_days(mut self, dlq_retention_days: i64) -> Self {
+        self.dlq_retention_days = dlq_retention_days;
+        self
+    }
+
+    /// Sets [`dlq_claim_timeout_secs`](Self::dlq_claim_timeout_secs).
+    #[must_use]
+    pub fn with_dlq_claim_timeout_secs(mut self, dlq_claim_timeout_secs: u64) -> Self {
+        self.dlq_claim_timeout_secs = dlq_claim_timeout_secs;
+        self
+    }
+
+    /// Sets [`dlq_revival_cooldown_secs`](Self::dlq_revival_cooldown_secs).
+    #[must_use]
+    pub fn with_dlq_revival_cooldown_secs(
+        mut self,
+        dlq_revival_cooldown_secs: Option<u64>,
+    ) -> Self {
+        self.dlq_revival_cooldown_secs = dlq_revival_cooldown_secs;
+        self
+    }
+
+    /// Sets [`dlq_max_revivals`](Self::dlq_max_revivals).
+    #[must_use]
+    pub fn with_dlq_max_revivals(mut self, dlq_max_revivals: u32) -> Self {
+        self.dlq_max_revivals = dlq_max_revivals;
+        self
+    }
+
+    /// Sets [`dlq_reconcile_interval_secs`](Self::dlq_reconcile_interval_secs).
+    #[must_use]
+    pub fn with_dlq_reconcile_interval_secs(mut self, dlq_reconcile_interval_secs: u64) -> Self {
+        self.dlq_reconcile_interval_secs = dlq_reconcile_interval_secs;
+        self
+    }
+
+    /// Sets [`dlq_idle_expiry_secs`](Self::dlq_idle_expiry_secs).
+    #[must_use]
+    pub fn with_dlq_idle_expiry_secs(mut self, dlq_idle_expiry_secs: Option<u64>) -> Self {
+        self.dlq_idle_expiry_secs = dlq_idle_expiry_secs;
+        self
+    }
+}
+
 /// How an idempotency token is produced when a new event is written.
 ///
 /// The variant is evaluated inside
@@ -138,7 +366,7 @@ where
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used)]
+#[allow(clippy::unwrap_used, clippy::panic)]
 mod tests {
     use super::*;
     use rstest::rstest;
@@ -176,6 +404,37 @@ mod tests {
         assert_eq!(default_cfg().lock_timeout_mins, 5);
     }
 
+    #[rstest]
+    fn default_dlq_retention_days_is_30() {
+        assert_eq!(default_cfg().dlq_retention_days, 30);
+    }
+
+    #[rstest]
+    fn default_dlq_policy_has_no_overrides_or_age_limit() {
+        let cfg = default_cfg();
+        assert!(cfg.dlq_type_thresholds.is_empty());
+        assert!(cfg.dlq_max_failure_age_secs.is_none());
+    }
+
+    #[rstest]
+    fn default_dlq_claim_timeout_secs_is_600() {
+        assert_eq!(default_cfg().dlq_claim_timeout_secs, 600);
+    }
+
+    #[rstest]
+    fn default_dlq_revival_is_disabled_with_three_cycles() {
+        let cfg = default_cfg();
+        assert!(cfg.dlq_revival_cooldown_secs.is_none());
+        assert_eq!(cfg.dlq_max_revivals, 3);
+    }
+
+    #[rstest]
+    fn default_dlq_reconciles_hourly_without_idle_expiry() {
+        let cfg = default_cfg();
+        assert_eq!(cfg.dlq_reconcile_interval_secs, 3600);
+        assert!(cfg.dlq_idle_expiry_secs.is_none());
+    }
+
     #[rstest]
     fn default_idempotency_strategy_is_none() {
         assert!(matches!(
@@ -195,8 +454,8 @@ mod tests {
             poll_interval_secs: 1,
             lock_timeout_mins: 2,
             idempotency_strategy: IdempotencyStrategy::Uuid,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         };
         let cloned = cfg.clone();
         assert_eq!(cloned.batch_size, 42);
@@ -206,6 +465,8 @@ mod tests {
         assert_eq!(cloned.lock_timeout_mins, 2);
         assert_eq!(cloned.dlq_threshold, 10);
         assert_eq!(cloned.dlq_interval_secs, 1);
+        assert_eq!(cloned.dlq_retention_days, 30);
+        assert_eq!(cloned.dlq_claim_timeout_secs, 600);
         assert!(matches!(
             cloned.idempotency_strategy,
             IdempotencyStrategy::Uuid
@@ -224,8 +485,8 @@ mod tests {
             poll_interval_secs: 1,
             lock_timeout_mins: 1,
             idempotency_strategy: IdempotencyStrategy::Custom(derive),
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         };
         let cloned = cfg.clone();
         match cloned.idempotency_strategy {
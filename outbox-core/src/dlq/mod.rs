@@ -1,3 +1,9 @@
 pub mod model;
+#[cfg(feature = "dlq")]
+pub mod observer;
+#[cfg(feature = "dlq")]
 pub mod processor;
+#[cfg(feature = "dlq")]
 pub mod storage;
+#[cfg(feature = "dlq")]
+pub mod store;
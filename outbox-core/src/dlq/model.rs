@@ -1,16 +1,37 @@
-use crate::object::EventId;
+#[cfg(feature = "dlq")]
+use crate::config::OutboxConfig;
+#[cfg(feature = "dlq")]
+use crate::model::Event;
+use crate::object::{EventId, EventType};
+#[cfg(feature = "dlq")]
+use serde::Serialize;
+#[cfg(feature = "dlq")]
+use std::collections::HashMap;
+#[cfg(feature = "dlq")]
+use std::fmt::Debug;
+#[cfg(feature = "dlq")]
+use std::time::Duration;
+use time::OffsetDateTime;
 
 /// One entry tracked by [`DlqHeap`]: an event id together with its current
-/// aggregated failure count.
+/// aggregated failure count, the latest failure and when failures started.
+///
+/// Heaps may leave [`event_type`](Self::event_type) as `None` on entries
+/// returned by
+/// [`DlqHeap::drain_exceeded`](crate::dlq::storage::DlqHeap::drain_exceeded);
+/// [`OutboxStorage::quarantine_events`](crate::storage::OutboxStorage::quarantine_events)
+/// fills it in on the entries it reports as moved.
 ///
 /// The struct is `#[non_exhaustive]`: future revisions may add fields like
-/// `last_failed_at` or `last_error` without breaking downstream callers.
+/// `last_failed_at` without breaking downstream callers.
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct DlqEntry {
     pub id: EventId,
     pub failure_count: u32,
     pub last_error: Option<String>,
+    pub first_failed_at: Option<OffsetDateTime>,
+    pub event_type: Option<EventType>,
 }
 
 impl DlqEntry {
@@ -20,6 +41,374 @@ impl DlqEntry {
             id,
             failure_count,
             last_error,
+            first_failed_at: None,
+            event_type: None,
+        }
+    }
+
+    /// Sets the time of the first failure recorded for this entry.
+    #[must_use]
+    pub fn with_first_failed_at(mut self, first_failed_at: Option<OffsetDateTime>) -> Self {
+        self.first_failed_at = first_failed_at;
+        self
+    }
+
+    /// Sets the event type of the quarantined event.
+    #[must_use]
+    pub fn with_event_type(mut self, event_type: EventType) -> Self {
+        self.event_type = Some(event_type);
+        self
+    }
+}
+
+/// When a tracked event becomes eligible for quarantine.
+///
+/// An entry is exceeded once its failure count reaches the threshold for its
+/// event type — an entry of [`type_thresholds`](Self::type_thresholds) or the
+/// default [`threshold`](Self::threshold) — or, when
+/// [`max_failure_age`](Self::max_failure_age) is set, once it has been
+/// failing for at least that long, whichever comes first. At most
+/// [`batch_size`](Self::batch_size) exceeded entries are drained per call.
+///
+/// [`DlqProcessor`](crate::dlq::processor::DlqProcessor) builds one from the
+/// `dlq_*` fields of [`OutboxConfig`] with [`from_config`](Self::from_config)
+/// and hands it to
+/// [`DlqHeap::drain_exceeded`](crate::dlq::storage::DlqHeap::drain_exceeded).
+#[cfg(feature = "dlq")]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DlqPolicy {
+    pub threshold: u32,
+    pub type_thresholds: HashMap<String, u32>,
+    pub max_failure_age: Option<Duration>,
+    pub batch_size: u32,
+}
+
+#[cfg(feature = "dlq")]
+impl DlqPolicy {
+    /// Policy with one failure-count threshold for every event type, draining
+    /// up to 1000 entries per call.
+    #[must_use]
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            type_thresholds: HashMap::new(),
+            max_failure_age: None,
+            batch_size: 1000,
+        }
+    }
+
+    /// Reads `dlq_threshold`, `dlq_type_thresholds`,
+    /// `dlq_max_failure_age_secs` and `dlq_batch_size` from `config`.
+    #[must_use]
+    pub fn from_config<P>(config: &OutboxConfig<P>) -> Self
+    where
+        P: Debug + Clone + Serialize,
+    {
+        Self {
+            threshold: config.dlq_threshold,
+            type_thresholds: config.dlq_type_thresholds.clone(),
+            max_failure_age: config.dlq_max_failure_age_secs.map(Duration::from_secs),
+            batch_size: config.dlq_batch_size,
+        }
+    }
+
+    /// Overrides the threshold for one event type.
+    #[must_use]
+    pub fn with_type_threshold(mut self, event_type: impl Into<String>, threshold: u32) -> Self {
+        self.type_thresholds.insert(event_type.into(), threshold);
+        self
+    }
+
+    /// Also quarantines events that have been failing for at least `age`.
+    #[must_use]
+    pub fn with_max_failure_age(mut self, age: Duration) -> Self {
+        self.max_failure_age = Some(age);
+        self
+    }
+
+    /// Drains at most `batch_size` entries per call.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Failure-count threshold that applies to `event_type`.
+    pub fn threshold_for(&self, event_type: &str) -> u32 {
+        self.type_thresholds
+            .get(event_type)
+            .copied()
+            .unwrap_or(self.threshold)
+    }
+
+    /// Lowest failure-count threshold across all event types.
+    pub fn min_threshold(&self) -> u32 {
+        self.type_thresholds
+            .values()
+            .copied()
+            .fold(self.threshold, u32::min)
+    }
+
+    /// Failure times at or before the returned instant exceed
+    /// [`max_failure_age`](Self::max_failure_age) as of `now`. `None` when no
+    /// age is set or when it reaches back past the earliest representable
+    /// instant, in which case nothing can have failed for that long.
+    pub fn failing_since_cutoff(&self, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        let age = time::Duration::try_from(self.max_failure_age?).ok()?;
+        now.checked_sub(age)
+    }
+
+    /// Whether an entry with the given failure data is due for quarantine as
+    /// of `now`. An unknown event type falls back to the default threshold.
+    pub fn is_exceeded(
+        &self,
+        failure_count: u32,
+        event_type: Option<&str>,
+        first_failed_at: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+    ) -> bool {
+        if failure_count == 0 {
+            return false;
+        }
+        let threshold = event_type.map_or(self.threshold, |t| self.threshold_for(t));
+        if failure_count >= threshold {
+            return true;
         }
+        matches!(
+            (first_failed_at, self.failing_since_cutoff(now)),
+            (Some(at), Some(cutoff)) if at <= cutoff
+        )
+    }
+}
+
+/// Selects dead letters by id, event type and/or quarantine time range.
+///
+/// Every criterion is optional and they combine with AND. There is no
+/// `Default`: a filter without criteria matches every dead letter, so it has
+/// to be asked for by name with [`all`](Self::all) and narrowed from there.
+/// The time range is half-open: `quarantined_after` is inclusive,
+/// `quarantined_before` is exclusive.
+#[cfg(feature = "dlq")]
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct DlqFilter {
+    pub ids: Option<Vec<EventId>>,
+    pub event_type: Option<String>,
+    pub quarantined_after: Option<OffsetDateTime>,
+    pub quarantined_before: Option<OffsetDateTime>,
+}
+
+#[cfg(feature = "dlq")]
+impl DlqFilter {
+    /// Filter that matches every dead letter.
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            ids: None,
+            event_type: None,
+            quarantined_after: None,
+            quarantined_before: None,
+        }
+    }
+
+    /// Restricts the filter to the given dead-letter ids.
+    #[must_use]
+    pub fn with_ids(mut self, ids: Vec<EventId>) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    /// Restricts the filter to one event type.
+    #[must_use]
+    pub fn with_event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.event_type = Some(event_type.into());
+        self
+    }
+
+    /// Restricts the filter to dead letters quarantined in `[from, to)`.
+    #[must_use]
+    pub fn quarantined_between(mut self, from: OffsetDateTime, to: OffsetDateTime) -> Self {
+        self.quarantined_after = Some(from);
+        self.quarantined_before = Some(to);
+        self
+    }
+}
+
+/// Offset/limit window for paging through dead letters.
+///
+/// Backends return dead letters newest-quarantined first, so stepping with
+/// [`next`](Self::next) walks back in time.
+#[cfg(feature = "dlq")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DlqPage {
+    pub offset: u64,
+    pub limit: u32,
+}
+
+#[cfg(feature = "dlq")]
+impl DlqPage {
+    /// First page holding at most `limit` dead letters.
+    #[must_use]
+    pub fn first(limit: u32) -> Self {
+        Self { offset: 0, limit }
+    }
+
+    /// The page right after this one, with the same limit.
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self {
+            offset: self.offset + u64::from(self.limit),
+            limit: self.limit,
+        }
+    }
+}
+
+/// A quarantined event together with the failure data it was quarantined
+/// with.
+///
+/// [`event`](Self::event) carries the status the row had when it was moved
+/// out of the active outbox. [`revival_count`](Self::revival_count) is how
+/// many times the event has already been revived from the dead-letter table
+/// automatically.
+#[cfg(feature = "dlq")]
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct DeadLetter<P> {
+    pub event: Event<P>,
+    pub failure_count: u32,
+    pub last_error: Option<String>,
+    pub first_failed_at: Option<OffsetDateTime>,
+    pub quarantined_at: OffsetDateTime,
+    pub revival_count: u32,
+}
+
+#[cfg(feature = "dlq")]
+impl<P> DeadLetter<P> {
+    #[must_use]
+    pub fn new(
+        event: Event<P>,
+        failure_count: u32,
+        last_error: Option<String>,
+        quarantined_at: OffsetDateTime,
+    ) -> Self {
+        Self {
+            event,
+            failure_count,
+            last_error,
+            first_failed_at: None,
+            quarantined_at,
+            revival_count: 0,
+        }
+    }
+
+    /// Sets the time of the first failure recorded before quarantine.
+    #[must_use]
+    pub fn with_first_failed_at(mut self, first_failed_at: Option<OffsetDateTime>) -> Self {
+        self.first_failed_at = first_failed_at;
+        self
+    }
+
+    /// Sets how many times the event has been revived before.
+    #[must_use]
+    pub fn with_revival_count(mut self, revival_count: u32) -> Self {
+        self.revival_count = revival_count;
+        self
+    }
+}
+
+#[cfg(all(test, feature = "dlq"))]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn entry_new_has_no_first_failure_time() {
+        let entry = DlqEntry::new(EventId::default(), 3, Some("Broker error: x".into()));
+        assert!(entry.first_failed_at.is_none());
+        let at = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(
+            entry.with_first_failed_at(Some(at)).first_failed_at,
+            Some(at)
+        );
+    }
+
+    #[rstest]
+    #[case::below_default(2, None, false)]
+    #[case::at_default(5, None, true)]
+    #[case::type_override_lower(3, Some("Audit"), true)]
+    #[case::type_override_higher(30, Some("Billing"), false)]
+    #[case::unknown_type_uses_default(5, Some("Other"), true)]
+    #[case::never_failed(0, Some("Audit"), false)]
+    fn policy_applies_per_type_thresholds(
+        #[case] failure_count: u32,
+        #[case] event_type: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let policy = DlqPolicy::new(5)
+            .with_type_threshold("Audit", 3)
+            .with_type_threshold("Billing", 50);
+        let now = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(
+            policy.is_exceeded(failure_count, event_type, Some(now), now),
+            expected
+        );
+        assert_eq!(policy.min_threshold(), 3);
+    }
+
+    #[rstest]
+    fn policy_quarantines_entries_failing_longer_than_max_age() {
+        let policy = DlqPolicy::new(100).with_max_failure_age(Duration::from_hours(24));
+        let now = OffsetDateTime::UNIX_EPOCH + time::Duration::days(10);
+        let old = now - time::Duration::hours(25);
+        let recent = now - time::Duration::hours(1);
+        assert!(policy.is_exceeded(1, None, Some(old), now));
+        assert!(!policy.is_exceeded(1, None, Some(recent), now));
+        assert!(!policy.is_exceeded(1, None, None, now));
+        assert!(!DlqPolicy::new(100).is_exceeded(1, None, Some(old), now));
+    }
+
+    #[rstest]
+    fn policy_with_unrepresentable_max_age_has_no_cutoff() {
+        let policy = DlqPolicy::new(100).with_max_failure_age(Duration::MAX);
+        let now = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(policy.failing_since_cutoff(now), None);
+        assert!(!policy.is_exceeded(1, None, Some(OffsetDateTime::UNIX_EPOCH), now));
+    }
+
+    #[rstest]
+    fn page_next_advances_offset_by_limit() {
+        let page = DlqPage::first(25).next().next();
+        assert_eq!(
+            page,
+            DlqPage {
+                offset: 50,
+                limit: 25
+            }
+        );
+    }
+
+    #[rstest]
+    fn all_filter_matches_everything() {
+        let filter = DlqFilter::all();
+        assert!(filter.ids.is_none());
+        assert!(filter.event_type.is_none());
+        assert!(filter.quarantined_after.is_none());
+        assert!(filter.quarantined_before.is_none());
+    }
+
+    #[rstest]
+    fn filter_builders_set_criteria() {
+        let id = EventId::default();
+        let from = OffsetDateTime::UNIX_EPOCH;
+        let to = from + time::Duration::hours(1);
+        let filter = DlqFilter::all()
+            .with_ids(vec![id])
+            .with_event_type("OrderPaid")
+            .quarantined_between(from, to);
+        assert_eq!(filter.ids, Some(vec![id]));
+        assert_eq!(filter.event_type.as_deref(), Some("OrderPaid"));
+        assert_eq!(filter.quarantined_after, Some(from));
+        assert_eq!(filter.quarantined_before, Some(to));
     }
 }
@@ -0,0 +1,48 @@
+//! Hooks invoked after events have been quarantined.
+//!
+//! [`DlqProcessor`](crate::dlq::processor::DlqProcessor) calls every
+//! registered [`DlqObserver`] once per reaper pass that moved at least one
+//! event, so integrators can page on-call, post to chat, or publish a
+//! "dead-lettered" notification through a secondary
+//! [`Transport`](crate::publisher::Transport).
+
+use crate::dlq::model::DlqEntry;
+use crate::error::OutboxError;
+use async_trait::async_trait;
+
+/// Receives the entries moved into the dead-letter table.
+///
+/// Register one with
+/// [`OutboxManagerBuilder::dlq_observer`](crate::builder::OutboxManagerBuilder::dlq_observer).
+/// Any `Fn(&[DlqEntry]) + Send + Sync` closure is an observer too, which
+/// covers simple logging or alerting without a dedicated type.
+///
+/// Observers run on the reaper task, after the quarantine transaction has
+/// committed; a slow observer delays the next reaper pass but never the
+/// worker loop.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait DlqObserver: Send + Sync {
+    /// Called with the entries quarantined in one reaper pass. Never called
+    /// with an empty slice. Each entry carries its
+    /// [`event_type`](DlqEntry::event_type) when the storage backend reports
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the notification could not be
+    /// delivered. The processor logs the error and moves on — the events stay
+    /// quarantined either way.
+    async fn on_quarantined(&self, entries: &[DlqEntry]) -> Result<(), OutboxError>;
+}
+
+#[async_trait]
+impl<F> DlqObserver for F
+where
+    F: Fn(&[DlqEntry]) + Send + Sync,
+{
+    async fn on_quarantined(&self, entries: &[DlqEntry]) -> Result<(), OutboxError> {
+        self(entries);
+        Ok(())
+    }
+}
@@ -1,27 +1,47 @@
 //! Background reaper that quarantines chronically failing events.
 //!
 //! [`DlqProcessor`] is the worker side of the dead-letter-queue subsystem.
-//! It wakes up on `config.dlq_interval_secs` and is meant to drain events
-//! whose failure count has crossed `config.dlq_threshold` from the
-//! [`DlqHeap`] backend. The current implementation owns the loop and the
-//! shutdown plumbing; the per-tick draining/quarantine logic is being filled
-//! out incrementally.
+//! Every `config.dlq_interval_secs` it runs one reaper pass:
+//!
+//! 1. hands claims left unacknowledged for longer than
+//!    `config.dlq_claim_timeout_secs` back to the [`DlqHeap`];
+//! 2. drains the entries that crossed the [`DlqPolicy`] built from the
+//!    `dlq_*` fields of the config;
+//! 3. quarantines them into the dead-letter table, or publishes them to a
+//!    [`Transport`] when a destination is configured;
+//! 4. acknowledges the settled entries, so the heap forgets them, and
+//!    notifies the [`DlqObserver`]s;
+//! 5. revives dead letters whose cooldown passed, when revival is enabled.
+//!
+//! Every `config.dlq_reconcile_interval_secs` it also reconciles the heap
+//! with the outbox, dropping counters of events that are gone or idle. A
+//! failing step is logged and retried on a later pass; entries that were
+//! drained but not acknowledged are picked up again by step 1.
 //!
 //! The processor is feature-gated behind `dlq` and is only spawned when that
 //! feature is enabled — see
 //! [`OutboxManager::run`](crate::manager::OutboxManager::run).
 
 use crate::config::OutboxConfig;
+use crate::dlq::model::{DeadLetter, DlqEntry, DlqPolicy};
+use crate::dlq::observer::DlqObserver;
 use crate::dlq::storage::DlqHeap;
 use crate::error::OutboxError;
+use crate::object::EventId;
 use crate::prelude::OutboxStorage;
+use crate::publisher::Transport;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::watch::Receiver;
 use tracing::{debug, error, info};
 
+/// Page size hint for [`DlqHeap::tracked_ids`] during reconciliation, which
+/// also bounds each [`OutboxStorage::missing_events`] call.
+const RECONCILE_PAGE: usize = 1000;
+
 /// Long-running task that drains overdue events from a [`DlqHeap`] and
 /// hands them to [`OutboxStorage::quarantine_events`] for atomic move into
 /// the dead-letter table.
@@ -32,11 +52,16 @@ use tracing::{debug, error, info};
 ///   tick to fetch events that have crossed the configured threshold).
 /// - an [`OutboxStorage`] used to perform the atomic move from the active
 ///   table into the quarantine table.
-/// - an [`OutboxConfig`] from which the tick interval and threshold are
-///   read.
+/// - an [`OutboxConfig`] from which the tick interval and the
+///   [`DlqPolicy`] are read.
 /// - a [`Receiver<bool>`](tokio::sync::watch::Receiver) shutdown channel
 ///   that lets the loop exit cleanly.
 ///
+/// Optionally, any number of [`DlqObserver`]s are notified of every batch
+/// that was quarantined (see [`with_observers`](Self::with_observers)), and a
+/// [`Transport`] can replace the dead-letter table as the destination (see
+/// [`with_destination`](Self::with_destination)).
+///
 /// Construct one with [`new`](Self::new) and drive it with [`run`](Self::run).
 pub struct DlqProcessor<S, PT>
 where
@@ -46,7 +71,10 @@ where
     heap: Arc<dyn DlqHeap>,
     storage: Arc<S>,
     config: Arc<OutboxConfig<PT>>,
+    policy: DlqPolicy,
     shutdown_rx: Receiver<bool>,
+    observers: Vec<Arc<dyn DlqObserver>>,
+    destination: Option<Arc<dyn Transport<PT>>>,
 }
 
 impl<S, PT> DlqProcessor<S, PT>
@@ -67,41 +95,88 @@ where
         Self {
             heap,
             storage,
+            policy: DlqPolicy::from_config(&config),
             config,
             shutdown_rx,
+            observers: Vec::new(),
+            destination: None,
         }
     }
 
+    /// Registers observers that are called with every non-empty batch of
+    /// quarantined entries.
+    #[must_use]
+    pub fn with_observers(mut self, observers: Vec<Arc<dyn DlqObserver>>) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    /// Sends drained events to `destination` instead of the dead-letter
+    /// table.
+    ///
+    /// Each drained event is loaded with [`OutboxStorage::fetch_events`],
+    /// handed to [`Transport::publish_dead_letter`] together with its failure
+    /// data, and deleted from the outbox with [`OutboxStorage::delete_events`]
+    /// only once that publish succeeded. Delivery is at least once: if the
+    /// delete fails, the claim is reclaimed and the event published again.
+    #[must_use]
+    pub fn with_destination(mut self, destination: Arc<dyn Transport<PT>>) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
     /// Runs the dead-letter reaper loop until shutdown is observed.
     ///
-    /// Each iteration races two arms in a `tokio::select!`:
+    /// Each iteration races three arms in a `tokio::select!`:
     ///
     /// - the shutdown receiver — the loop exits when the watched value flips
     ///   to `true`, and also when the sender side is dropped (treated as an
     ///   implicit shutdown via [`Receiver::has_changed`]).
     /// - a periodic tick on `config.dlq_interval_secs`. On each tick the
-    ///   processor drains entries above
-    ///   `config.dlq_threshold` from the [`DlqHeap`] and forwards them to
+    ///   processor first hands claims older than
+    ///   `config.dlq_claim_timeout_secs` back to the heap with
+    ///   [`DlqHeap::reclaim_stale`]. It then drains entries exceeding the
+    ///   [`DlqPolicy`] (per-type thresholds and maximum failure age
+    ///   included) from the [`DlqHeap`] and forwards them to
     ///   [`OutboxStorage::quarantine_events`] for atomic move into the DLQ
-    ///   table. Errors from either step are logged and the loop keeps
-    ///   running — a transient drain/quarantine failure does not bring the
-    ///   reaper down. Drained entries that fail to be quarantined are lost
-    ///   from the heap but will re-accumulate on subsequent failures.
+    ///   table — or, with a destination configured, publishes them to that
+    ///   [`Transport`] — and acknowledges them with
+    ///   [`DlqHeap::ack_drained`]. The entries the storage reports as moved
+    ///   are then passed to every registered [`DlqObserver`]. Drained
+    ///   entries that fail to be quarantined stay claimed and are reclaimed
+    ///   on a later pass. When `config.dlq_revival_cooldown_secs` is set,
+    ///   the tick ends with [`OutboxStorage::revive_dead_letters`], which
+    ///   moves dead letters past their cooldown back into the outbox (at
+    ///   most `config.dlq_max_revivals` times per event); the heap is reset
+    ///   for every revived id so it starts over with a clean counter.
+    /// - a second tick on `config.dlq_reconcile_interval_secs`, first fired
+    ///   one period after start, that reconciles the heap with the outbox:
+    ///   ids from each page of [`DlqHeap::tracked_ids`] that
+    ///   [`OutboxStorage::missing_events`] reports as gone are dropped with
+    ///   [`DlqHeap::forget`], and, when `config.dlq_idle_expiry_secs` is
+    ///   set, counters idle for longer are dropped with
+    ///   [`DlqHeap::expire_idle`].
     ///
     /// This method consumes the processor and is meant to be spawned on a
     /// dedicated Tokio task. Only compiled with the `dlq` feature.
     ///
     /// # Errors
     ///
-    /// Always returns `Ok(())` in the current implementation — every shutdown
-    /// path is graceful. The fallible signature is preserved so future
-    /// drain/quarantine logic can surface terminal failures without breaking
-    /// the API.
+    /// Never returns an error. A failing heap, storage, destination or
+    /// observer call is logged and the loop keeps running, so a transient
+    /// failure does not bring the reaper down; the work it interrupted is
+    /// retried on a later tick. The loop returns `Ok(())` once shutdown is
+    /// observed.
     #[cfg(feature = "dlq")]
     pub async fn run(self) -> Result<(), OutboxError> {
         let mut rx_dlq = self.shutdown_rx.clone();
         let mut interval =
             tokio::time::interval(Duration::from_secs(self.config.dlq_interval_secs));
+        let reconcile_period = Duration::from_secs(self.config.dlq_reconcile_interval_secs);
+        let mut reconcile_interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + reconcile_period,
+            reconcile_period,
+        );
 
         info!("Starting DLQ processor");
 
@@ -116,20 +191,29 @@ where
                         }
                 }
                 _ = interval.tick() => {
-                    match self.heap.drain_exceeded(self.config.dlq_threshold).await {
+                    self.reclaim_stale().await;
+                    match self.heap.drain_exceeded(&self.policy).await {
                         Ok(entries) if entries.is_empty() => {}
                         Ok(entries) => {
                             debug!("DLQ reaper draining {} entries", entries.len());
-                            if let Err(e) = self.storage.quarantine_events(&entries).await {
-                                error!(
+                            match self.quarantine(&entries).await {
+                                Ok((moved, settled)) => {
+                                    self.ack_drained(&settled).await;
+                                    self.notify_observers(&moved).await;
+                                }
+                                Err(e) => error!(
                                     "Failed to quarantine {} events: {}",
                                     entries.len(),
                                     e
-                                );
+                                ),
                             }
                         }
                         Err(e) => error!("DLQ drain_exceeded failed: {}", e),
                     }
+                    self.revive_dead_letters().await;
+                }
+                _ = reconcile_interval.tick() => {
+                    self.reconcile().await;
                 }
             }
         }
@@ -137,19 +221,169 @@ where
         info!("Dlq processor stopped");
         Ok(())
     }
+
+    async fn reclaim_stale(&self) {
+        let timeout = Duration::from_secs(self.config.dlq_claim_timeout_secs);
+        match self.heap.reclaim_stale(timeout).await {
+            Ok(0) => {}
+            Ok(n) => info!("DLQ reaper reclaimed {} stale claims", n),
+            Err(e) => error!("DLQ reclaim_stale failed: {}", e),
+        }
+    }
+
+    async fn reconcile(&self) {
+        let mut cursor = 0;
+        loop {
+            match self.heap.tracked_ids(cursor, RECONCILE_PAGE).await {
+                Ok((next, ids)) => {
+                    self.forget_missing(&ids).await;
+                    if next == 0 {
+                        break;
+                    }
+                    cursor = next;
+                }
+                Err(e) => {
+                    error!("DLQ tracked_ids failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        let Some(max_idle) = self.config.dlq_idle_expiry_secs else {
+            return;
+        };
+        match self.heap.expire_idle(Duration::from_secs(max_idle)).await {
+            Ok(0) => {}
+            Ok(n) => info!("DLQ reaper expired {} idle failure counters", n),
+            Err(e) => error!("DLQ expire_idle failed: {}", e),
+        }
+    }
+
+    async fn forget_missing(&self, ids: &[EventId]) {
+        if ids.is_empty() {
+            return;
+        }
+        match self.storage.missing_events(ids).await {
+            Ok(missing) if missing.is_empty() => {}
+            Ok(missing) => match self.heap.forget(&missing).await {
+                Ok(n) => info!("DLQ reaper removed {} orphaned failure counters", n),
+                Err(e) => error!(
+                    "Failed to forget {} orphaned DLQ counters: {}",
+                    missing.len(),
+                    e
+                ),
+            },
+            Err(e) => error!("DLQ missing_events failed: {}", e),
+        }
+    }
+
+    async fn revive_dead_letters(&self) {
+        let Some(cooldown) = self.config.dlq_revival_cooldown_secs else {
+            return;
+        };
+        match self
+            .storage
+            .revive_dead_letters(Duration::from_secs(cooldown), self.config.dlq_max_revivals)
+            .await
+        {
+            Ok(ids) if ids.is_empty() => {}
+            Ok(ids) => {
+                info!("DLQ reaper revived {} dead letters", ids.len());
+                for id in ids {
+                    if let Err(e) = self.heap.record_success(id).await {
+                        error!("Failed to reset DLQ heap for revived event {:?}: {}", id, e);
+                    }
+                }
+            }
+            Err(e) => error!("DLQ revive_dead_letters failed: {}", e),
+        }
+    }
+
+    /// Moves `entries` to the configured destination. Returns the entries
+    /// that were moved, with their event type, and the ids whose claim can be
+    /// acknowledged — moved ones plus ids no longer in the outbox.
+    async fn quarantine(
+        &self,
+        entries: &[DlqEntry],
+    ) -> Result<(Vec<DlqEntry>, Vec<EventId>), OutboxError> {
+        let Some(destination) = &self.destination else {
+            let moved = self.storage.quarantine_events(entries).await?;
+            return Ok((moved, entries.iter().map(|e| e.id).collect()));
+        };
+
+        let ids: Vec<EventId> = entries.iter().map(|e| e.id).collect();
+        let mut events: HashMap<EventId, _> = self
+            .storage
+            .fetch_events(&ids)
+            .await?
+            .into_iter()
+            .map(|event| (event.id, event))
+            .collect();
+
+        let now = self.config.clock.now();
+        let mut moved = Vec::new();
+        let mut settled = Vec::new();
+        for entry in entries {
+            let Some(event) = events.remove(&entry.id) else {
+                settled.push(entry.id);
+                continue;
+            };
+            let event_type = event.event_type.clone();
+            let letter = DeadLetter::new(event, entry.failure_count, entry.last_error.clone(), now)
+                .with_first_failed_at(entry.first_failed_at);
+            match destination.publish_dead_letter(letter).await {
+                Ok(()) => moved.push(entry.clone().with_event_type(event_type)),
+                Err(e) => error!("Failed to publish dead letter {:?}: {}", entry.id, e),
+            }
+        }
+
+        let published: Vec<EventId> = moved.iter().map(|e| e.id).collect();
+        if !published.is_empty() {
+            self.storage.delete_events(&published).await?;
+        }
+        settled.extend(published);
+        Ok((moved, settled))
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) {
+        if ids.is_empty() {
+            return;
+        }
+        if let Err(e) = self.heap.ack_drained(ids).await {
+            error!("Failed to ack {} drained DLQ entries: {}", ids.len(), e);
+        }
+    }
+
+    async fn notify_observers(&self, moved: &[DlqEntry]) {
+        if moved.is_empty() {
+            return;
+        }
+        for observer in &self.observers {
+            if let Err(e) = observer.on_quarantined(moved).await {
+                error!(
+                    "DLQ observer failed for {} quarantined events: {}",
+                    moved.len(),
+                    e
+                );
+            }
+        }
+    }
 }
 
 #[cfg(all(test, feature = "dlq"))]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use crate::config::IdempotencyStrategy;
     use crate::dlq::model::DlqEntry;
+    use crate::dlq::observer::MockDlqObserver;
     use crate::dlq::storage::MockDlqHeap;
-    use crate::object::EventId;
+    use crate::model::Event;
+    use crate::object::{EventId, EventType, Payload};
+    use crate::publisher::MockTransport;
     use crate::storage::MockOutboxStorage;
     use rstest::rstest;
     use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::sync::watch;
 
@@ -158,14 +392,9 @@ mod tests {
 
     fn config() -> Arc<OutboxConfig<TestPayload>> {
         Arc::new(OutboxConfig {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 3600,
+            ..OutboxConfig::default()
         })
     }
 
@@ -175,6 +404,7 @@ mod tests {
 
     fn quiet_heap() -> Arc<MockDlqHeap> {
         let mut h = MockDlqHeap::new();
+        h.expect_reclaim_stale().returning(|_| Ok(0));
         h.expect_drain_exceeded().returning(|_| Ok(vec![]));
         Arc::new(h)
     }
@@ -239,10 +469,12 @@ mod tests {
     #[tokio::test(start_paused = true)]
     async fn run_drains_heap_and_forwards_to_quarantine_events() {
         let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_ack_drained().returning(|_| Ok(()));
         let entry = DlqEntry::new(EventId::load(uuid::Uuid::now_v7()), 12, None);
         let entry_for_heap = entry.clone();
         heap.expect_drain_exceeded()
-            .withf(|t| *t == 10)
+            .withf(|policy| *policy == DlqPolicy::new(10))
             .returning(move |_| Ok(vec![entry_for_heap.clone()]));
 
         let mut storage = MockOutboxStorage::<TestPayload>::new();
@@ -250,18 +482,13 @@ mod tests {
         storage
             .expect_quarantine_events()
             .withf(move |entries| entries.len() == 1 && entries[0].id == entry_for_storage.id)
-            .returning(|_| Ok(()));
+            .returning(|entries| Ok(entries.to_vec()));
 
         let (tx, rx) = watch::channel(false);
         let cfg = Arc::new(OutboxConfig::<TestPayload> {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 60,
+            ..OutboxConfig::default()
         });
 
         let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), cfg, rx);
@@ -275,10 +502,514 @@ mod tests {
         let _ = tokio::time::timeout(Duration::from_secs(1), handle).await;
     }
 
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_drains_with_policy_built_from_config() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded()
+            .withf(|policy| {
+                *policy
+                    == DlqPolicy::new(10)
+                        .with_type_threshold("Audit", 3)
+                        .with_max_failure_age(Duration::from_hours(24))
+            })
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let (tx, rx) = watch::channel(false);
+        let cfg = Arc::new(OutboxConfig::<TestPayload> {
+            dlq_type_thresholds: HashMap::from([("Audit".to_owned(), 3)]),
+            dlq_max_failure_age_secs: Some(86_400),
+            ..(*config()).clone()
+        });
+        let processor = DlqProcessor::new(Arc::new(heap), empty_storage(), cfg, rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    fn one_entry_heap(entry: DlqEntry) -> MockDlqHeap {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_ack_drained().returning(|_| Ok(()));
+        heap.expect_drain_exceeded()
+            .returning(move |_| Ok(vec![entry.clone()]));
+        heap
+    }
+
+    async fn run_one_tick<S>(processor: DlqProcessor<S, TestPayload>, tx: watch::Sender<bool>)
+    where
+        S: OutboxStorage<TestPayload> + Send + Sync + 'static,
+    {
+        let handle = tokio::spawn(async move { processor.run().await });
+
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_millis(1)).await;
+        tokio::task::yield_now().await;
+
+        tx.send(true).unwrap();
+        // Mock expectations are verified when the processor is dropped at the
+        // end of the task, so a violated expectation surfaces as a join error.
+        let result = tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("run did not stop in time")
+            .unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_notifies_observers_with_moved_entries() {
+        let entry = DlqEntry::new(EventId::default(), 12, Some("Broker error: x".into()));
+        let id = entry.id;
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_quarantine_events().returning(|entries| {
+            Ok(entries
+                .iter()
+                .map(|e| e.clone().with_event_type(EventType::new("OrderPaid")))
+                .collect())
+        });
+
+        let mut observer = MockDlqObserver::new();
+        observer
+            .expect_on_quarantined()
+            .withf(move |entries| {
+                entries.len() == 1
+                    && entries[0].id == id
+                    && entries[0].event_type.as_ref().map(EventType::as_str) == Some("OrderPaid")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let seen = Arc::new(AtomicUsize::new(0));
+        let seen_clone = seen.clone();
+        let closure = move |entries: &[DlqEntry]| {
+            seen_clone.fetch_add(entries.len(), Ordering::SeqCst);
+        };
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(
+            Arc::new(one_entry_heap(entry)),
+            Arc::new(storage),
+            config(),
+            rx,
+        )
+        .with_observers(vec![Arc::new(observer), Arc::new(closure)]);
+        run_one_tick(processor, tx).await;
+
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_skips_observers_when_nothing_was_moved() {
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_quarantine_events().returning(|_| Ok(vec![]));
+
+        let mut observer = MockDlqObserver::new();
+        observer.expect_on_quarantined().times(0);
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(
+            Arc::new(one_entry_heap(DlqEntry::new(EventId::default(), 12, None))),
+            Arc::new(storage),
+            config(),
+            rx,
+        )
+        .with_observers(vec![Arc::new(observer)]);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_calls_every_observer_even_if_one_fails() {
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_quarantine_events()
+            .returning(|entries| Ok(entries.to_vec()));
+
+        let mut failing = MockDlqObserver::new();
+        failing
+            .expect_on_quarantined()
+            .times(1)
+            .returning(|_| Err(OutboxError::BrokerError("chat down".into())));
+        let mut healthy = MockDlqObserver::new();
+        healthy
+            .expect_on_quarantined()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(
+            Arc::new(one_entry_heap(DlqEntry::new(EventId::default(), 12, None))),
+            Arc::new(storage),
+            config(),
+            rx,
+        )
+        .with_observers(vec![Arc::new(failing), Arc::new(healthy)]);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_acks_drained_entries_after_quarantine_succeeds() {
+        let entry = DlqEntry::new(EventId::default(), 12, None);
+        let id = entry.id;
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded()
+            .returning(move |_| Ok(vec![entry.clone()]));
+        heap.expect_ack_drained()
+            .withf(move |ids| ids == [id])
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_quarantine_events()
+            .returning(|entries| Ok(entries.to_vec()));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_leaves_entries_claimed_when_quarantine_fails() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded()
+            .returning(|_| Ok(vec![DlqEntry::new(EventId::default(), 12, None)]));
+        heap.expect_ack_drained().times(0);
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_quarantine_events()
+            .returning(|_| Err(OutboxError::DatabaseError("boom".into())));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_reclaims_stale_claims_with_configured_timeout() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale()
+            .withf(|timeout| *timeout == Duration::from_secs(45))
+            .times(1)
+            .returning(|_| Ok(3));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_quarantine_events().times(0);
+
+        let (tx, rx) = watch::channel(false);
+        let cfg = Arc::new(OutboxConfig::<TestPayload> {
+            dlq_claim_timeout_secs: 45,
+            ..(*config()).clone()
+        });
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), cfg, rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_keeps_draining_when_reclaim_fails() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale()
+            .returning(|_| Err(OutboxError::InfrastructureError("down".into())));
+        heap.expect_drain_exceeded()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), empty_storage(), config(), rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_revives_dead_letters_and_resets_heap_when_enabled() {
+        let id = EventId::default();
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+        heap.expect_record_success()
+            .withf(move |got| *got == id)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_revive_dead_letters()
+            .withf(|cooldown, max| *cooldown == Duration::from_hours(6) && *max == 2)
+            .times(1)
+            .returning(move |_, _| Ok(vec![id]));
+
+        let (tx, rx) = watch::channel(false);
+        let cfg = Arc::new(OutboxConfig::<TestPayload> {
+            dlq_revival_cooldown_secs: Some(6 * 3600),
+            dlq_max_revivals: 2,
+            ..(*config()).clone()
+        });
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), cfg, rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_does_not_revive_when_cooldown_is_unset() {
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_revive_dead_letters().times(0);
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(quiet_heap(), Arc::new(storage), config(), rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    async fn run_reconcile_pass<S>(processor: DlqProcessor<S, TestPayload>, tx: watch::Sender<bool>)
+    where
+        S: OutboxStorage<TestPayload> + Send + Sync + 'static,
+    {
+        let handle = tokio::spawn(async move { processor.run().await });
+
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_hours(1)).await;
+        tokio::task::yield_now().await;
+
+        tx.send(true).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("run did not stop in time")
+            .unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_forgets_counters_of_events_missing_from_outbox() {
+        let live = EventId::default();
+        let gone = EventId::default();
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+        heap.expect_tracked_ids()
+            .times(1)
+            .returning(move |_, _| Ok((0, vec![live, gone])));
+        heap.expect_forget()
+            .withf(move |ids| ids == [gone])
+            .times(1)
+            .returning(|ids| Ok(ids.len() as u64));
+        heap.expect_expire_idle().times(0);
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_missing_events()
+            .withf(move |ids| ids == [live, gone])
+            .times(1)
+            .returning(move |_| Ok(vec![gone]));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx);
+        run_reconcile_pass(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_reconciles_every_page_of_tracked_ids() {
+        let first = EventId::default();
+        let second = EventId::default();
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+        heap.expect_tracked_ids()
+            .withf(|cursor, _| *cursor == 0)
+            .times(1)
+            .returning(move |_, _| Ok((7, vec![first])));
+        heap.expect_tracked_ids()
+            .withf(|cursor, _| *cursor == 7)
+            .times(1)
+            .returning(move |_, _| Ok((0, vec![second])));
+        heap.expect_forget()
+            .withf(move |ids| ids == [second])
+            .times(1)
+            .returning(|ids| Ok(ids.len() as u64));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_missing_events()
+            .withf(move |ids| ids == [first])
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        storage
+            .expect_missing_events()
+            .withf(move |ids| ids == [second])
+            .times(1)
+            .returning(move |_| Ok(vec![second]));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx);
+        run_reconcile_pass(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_expires_idle_counters_when_enabled() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+        heap.expect_tracked_ids()
+            .times(1)
+            .returning(|_, _| Ok((0, vec![])));
+        heap.expect_forget().times(0);
+        heap.expect_expire_idle()
+            .withf(|max_idle| *max_idle == Duration::from_hours(24))
+            .times(1)
+            .returning(|_| Ok(3));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_missing_events().times(0);
+
+        let (tx, rx) = watch::channel(false);
+        let cfg = Arc::new(OutboxConfig::<TestPayload> {
+            dlq_idle_expiry_secs: Some(24 * 3600),
+            ..(*config()).clone()
+        });
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), cfg, rx);
+        run_reconcile_pass(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_does_not_reconcile_before_first_period() {
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
+        heap.expect_tracked_ids().times(0);
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), empty_storage(), config(), rx);
+        run_one_tick(processor, tx).await;
+    }
+
+    fn stored_event(id: EventId) -> Event<TestPayload> {
+        let mut event = Event::new(EventType::new("OrderPaid"), Payload::new(TestPayload), None);
+        event.id = id;
+        event
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_publishes_to_destination_then_deletes_and_acks() {
+        let entry = DlqEntry::new(EventId::default(), 12, Some("Broker error: x".into()));
+        let id = entry.id;
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded()
+            .returning(move |_| Ok(vec![entry.clone()]));
+        heap.expect_ack_drained()
+            .withf(move |ids| ids == [id])
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_quarantine_events().times(0);
+        storage
+            .expect_fetch_events()
+            .withf(move |ids| ids == [id])
+            .returning(move |_| Ok(vec![stored_event(id)]));
+        storage
+            .expect_delete_events()
+            .withf(move |ids| ids == [id])
+            .times(1)
+            .returning(|_| Ok(1));
+
+        let mut destination = MockTransport::<TestPayload>::new();
+        destination
+            .expect_publish_dead_letter()
+            .withf(move |letter| {
+                letter.event.id == id
+                    && letter.failure_count == 12
+                    && letter.last_error.as_deref() == Some("Broker error: x")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut observer = MockDlqObserver::new();
+        observer
+            .expect_on_quarantined()
+            .withf(|entries| {
+                entries.len() == 1
+                    && entries[0].event_type.as_ref().map(EventType::as_str) == Some("OrderPaid")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx)
+            .with_observers(vec![Arc::new(observer)])
+            .with_destination(Arc::new(destination));
+        run_one_tick(processor, tx).await;
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn run_keeps_events_whose_dead_letter_publish_failed() {
+        let failing = DlqEntry::new(EventId::default(), 12, None);
+        let published = DlqEntry::new(EventId::default(), 12, None);
+        let gone = DlqEntry::new(EventId::default(), 12, None);
+        let (failing_id, published_id, gone_id) = (failing.id, published.id, gone.id);
+
+        let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_drain_exceeded()
+            .returning(move |_| Ok(vec![failing.clone(), published.clone(), gone.clone()]));
+        heap.expect_ack_drained()
+            .withf(move |ids| ids == [gone_id, published_id])
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_fetch_events()
+            .returning(move |_| Ok(vec![stored_event(failing_id), stored_event(published_id)]));
+        storage
+            .expect_delete_events()
+            .withf(move |ids| ids == [published_id])
+            .times(1)
+            .returning(|_| Ok(1));
+
+        let mut destination = MockTransport::<TestPayload>::new();
+        destination
+            .expect_publish_dead_letter()
+            .times(2)
+            .returning(move |letter| {
+                if letter.event.id == failing_id {
+                    Err(OutboxError::BrokerError("dlq topic down".into()))
+                } else {
+                    Ok(())
+                }
+            });
+
+        let (tx, rx) = watch::channel(false);
+        let processor = DlqProcessor::new(Arc::new(heap), Arc::new(storage), config(), rx)
+            .with_destination(Arc::new(destination));
+        run_one_tick(processor, tx).await;
+    }
+
     #[rstest]
     #[tokio::test(start_paused = true)]
     async fn run_skips_quarantine_when_drain_returns_empty_batch() {
         let mut heap = MockDlqHeap::new();
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+        heap.expect_ack_drained().returning(|_| Ok(()));
         heap.expect_drain_exceeded().returning(|_| Ok(vec![]));
 
         let mut storage = MockOutboxStorage::<TestPayload>::new();
@@ -308,6 +1039,10 @@ mod tests {
         let drain_calls_clone = drain_calls.clone();
 
         let mut heap = MockDlqHeap::new();
+
+        heap.expect_reclaim_stale().returning(|_| Ok(0));
+
+        heap.expect_ack_drained().returning(|_| Ok(()));
         heap.expect_drain_exceeded().returning(move |_| {
             drain_calls_clone.fetch_add(1, Ordering::SeqCst);
             Ok(vec![DlqEntry::new(
@@ -332,7 +1067,7 @@ mod tests {
         let handle = tokio::spawn(async move { processor.run().await });
 
         tokio::task::yield_now().await;
-        tokio::time::advance(Duration::from_secs(60)).await;
+        tokio::time::advance(Duration::from_mins(1)).await;
         tokio::task::yield_now().await;
 
         tx.send(true).unwrap();
@@ -3,14 +3,26 @@
 //! A [`DlqHeap`] implementation tracks how many times each event has failed to
 //! publish. The worker feeds it per-event outcomes on every processing pass;
 //! the [`DlqProcessor`](crate::dlq::processor::DlqProcessor) periodically
-//! drains entries whose failure count has crossed a configured threshold and
+//! drains entries that have crossed the configured [`DlqPolicy`] and
 //! hands them to [`OutboxStorage::quarantine_events`](crate::storage::OutboxStorage::quarantine_events)
 //! for atomic move into a separate quarantine table.
+//!
+//! Draining is a claim/ack protocol: [`DlqHeap::drain_exceeded`] claims the
+//! entries, [`DlqHeap::ack_drained`] forgets them once the quarantine has
+//! committed, and [`DlqHeap::reclaim_stale`] hands claims that were never
+//! acknowledged (a failed quarantine, a crashed reaper) back to the heap.
+//!
+//! Counters that outlive their event are cleaned up by a periodic
+//! reconciliation: [`DlqHeap::tracked_ids`] lists the counted ids, the ones
+//! missing from the outbox are dropped with [`DlqHeap::forget`], and
+//! [`DlqHeap::expire_idle`] drops counters that have not been touched for a
+//! configured window.
 
-use crate::dlq::model::DlqEntry;
+use crate::dlq::model::{DlqEntry, DlqPolicy};
 use crate::error::OutboxError;
-use crate::object::EventId;
+use crate::object::{EventId, EventType};
 use async_trait::async_trait;
+use std::time::Duration;
 
 /// Tracks per-event publish failure counts for the dead-letter queue.
 ///
@@ -23,15 +35,24 @@ use async_trait::async_trait;
 /// intentionally narrow so in-memory implementations are trivial to write
 /// for tests.
 ///
-/// All three methods are expected to be concurrency-safe — the worker may be
+/// All methods are expected to be concurrency-safe — the worker may be
 /// driving multiple batches through the heap at once.
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait DlqHeap: Send + Sync {
-    /// Increments the failure counter for `id`.
+    /// Increments the failure counter for `id` and remembers `error` as its
+    /// latest failure.
+    ///
+    /// Called by the worker loop after a publish attempt returns `Err`, with
+    /// the event's type and the error the transport returned. Heaps that do
+    /// not share a store with the outbox keep `event_type` so
+    /// [`drain_exceeded`](Self::drain_exceeded) can apply per-type
+    /// thresholds. Implementations persist the error's `Display` string —
+    /// which starts with its category, e.g. `"Broker error: ..."` — as
+    /// [`DlqEntry::last_error`], overwriting the previous one, and keep the
+    /// time of the first failure as [`DlqEntry::first_failed_at`].
     ///
-    /// Called by the worker loop after a publish attempt returns `Err`. The
-    /// method is fire-and-forget: implementations are free to emit per-call
+    /// The method is fire-and-forget: implementations are free to emit per-call
     /// metrics or logs internally, but no aggregated state is surfaced to the
     /// caller — quarantine decisions are taken by
     /// [`DlqProcessor`](crate::dlq::processor::DlqProcessor) based on
@@ -40,25 +61,125 @@ pub trait DlqHeap: Send + Sync {
     /// # Errors
     ///
     /// Returns an [`OutboxError`] if the backing store call fails.
-    async fn record_failure(&self, id: EventId) -> Result<(), OutboxError>;
+    async fn record_failure(
+        &self,
+        id: EventId,
+        event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError>;
 
-    /// Clears the failure counter for `id` after a successful publish.
+    /// Clears the failure counter, last error and first-failure time for `id`
+    /// after a successful publish.
     ///
     /// # Errors
     ///
     /// Returns an [`OutboxError`] if the backing store call fails.
     async fn record_success(&self, id: EventId) -> Result<(), OutboxError>;
 
-    /// Removes and returns every [`DlqEntry`] whose failure count has reached
-    /// or exceeded `threshold`.
+    /// Claims and returns the [`DlqEntry`]s that `policy` considers
+    /// exceeded — its failure count reached the threshold for its event type,
+    /// or it has been failing for longer than the policy's maximum age (see
+    /// [`DlqPolicy::is_exceeded`]). At most
+    /// [`batch_size`](DlqPolicy::batch_size) entries are returned; the rest
+    /// stay unclaimed for the next call.
     ///
     /// Intended to be called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor)
-    /// on its timer. The implementation is expected to remove the returned
-    /// entries from its tracking table atomically so the same id is not
-    /// returned twice on overlapping calls.
+    /// on its timer. The implementation is expected to claim the returned
+    /// entries atomically so the same id is not returned twice on overlapping
+    /// calls. Implementations that support [`ack_drained`](Self::ack_drained)
+    /// keep a claimed entry's failure history until it is acknowledged, so a
+    /// failed quarantine does not lose it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError>;
+
+    /// Forgets the claimed entries for `ids` once they have been quarantined.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) after
+    /// [`OutboxStorage::quarantine_events`](crate::storage::OutboxStorage::quarantine_events)
+    /// succeeded for the batch returned by
+    /// [`drain_exceeded`](Self::drain_exceeded). The default implementation
+    /// does nothing, for heaps whose drain already removes the entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        let _ = ids;
+        Ok(())
+    }
+
+    /// Returns claims older than `timeout` to the heap with their failure
+    /// count and last error intact, and reports how many were reclaimed.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) at the
+    /// start of every pass so entries whose quarantine failed, or whose reaper
+    /// crashed between drain and ack, are drained again. The default
+    /// implementation does nothing and returns `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let _ = timeout;
+        Ok(0)
+    }
+
+    /// Returns one page of the ids that currently have a failure counter,
+    /// excluding claimed entries, together with the cursor of the next page.
+    ///
+    /// Iteration starts with `cursor` `0` and ends when the returned cursor
+    /// is `0` again, as with Redis `SCAN`. `count` is a hint for the page
+    /// size, and an id may show up on more than one page.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) on
+    /// every reconciliation pass; each page is checked against
+    /// [`OutboxStorage::missing_events`](crate::storage::OutboxStorage::missing_events)
+    /// before the next one is fetched. The default implementation returns an
+    /// empty last page, for heaps whose counters cannot outlive their event
+    /// (e.g. a counter column on the outbox row itself).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn tracked_ids(
+        &self,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, Vec<EventId>), OutboxError> {
+        let _ = (cursor, count);
+        Ok((0, Vec::new()))
+    }
+
+    /// Removes the failure counters, last errors and claims for `ids` and
+    /// reports how many counters were removed.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) with
+    /// the ids whose event no longer exists in the outbox. The default
+    /// implementation does nothing and returns `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn forget(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let _ = ids;
+        Ok(0)
+    }
+
+    /// Removes failure counters whose last failure is older than `max_idle`
+    /// and reports how many were removed. Claimed entries are left alone.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) on
+    /// every reconciliation pass when `config.dlq_idle_expiry_secs` is set.
+    /// The default implementation does nothing and returns `0`.
     ///
     /// # Errors
     ///
     /// Returns an [`OutboxError`] if the backing store call fails.
-    async fn drain_exceeded(&self, threshold: u32) -> Result<Vec<DlqEntry>, OutboxError>;
+    async fn expire_idle(&self, max_idle: Duration) -> Result<u64, OutboxError> {
+        let _ = max_idle;
+        Ok(0)
+    }
 }
@@ -0,0 +1,229 @@
+//! Management access to events already moved into the dead-letter table.
+//!
+//! [`DlqStore`] lets operators inspect, replay and discard quarantined events
+//! without touching the underlying table directly. It is implemented by
+//! storage backends that own the quarantine table written by
+//! [`OutboxStorage::quarantine_events`](crate::storage::OutboxStorage::quarantine_events).
+//! [`DlqAdmin`] pairs a store with the [`DlqHeap`] so that replayed events
+//! start over with a clean failure counter.
+
+use crate::dlq::model::{DeadLetter, DlqFilter, DlqPage};
+use crate::dlq::storage::DlqHeap;
+use crate::error::OutboxError;
+use crate::object::{EventId, EventType};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Backend access to quarantined events.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait DlqStore<P>: Send + Sync
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    /// Returns one page of dead letters matching `filter`, newest
+    /// quarantined first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn list(
+        &self,
+        filter: &DlqFilter,
+        page: DlqPage,
+    ) -> Result<Vec<DeadLetter<P>>, OutboxError>;
+
+    /// Returns the dead letter with `id`, or `None` if it is not quarantined.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn get(&self, id: EventId) -> Result<Option<DeadLetter<P>>, OutboxError>;
+
+    /// Returns the number of dead letters per event type, largest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn count_by_event_type(&self) -> Result<Vec<(EventType, u64)>, OutboxError>;
+
+    /// Permanently removes the dead letters with the given ids. Returns the
+    /// number of rows deleted; unknown ids are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn delete(&self, ids: &[EventId]) -> Result<u64, OutboxError>;
+
+    /// Moves every dead letter matching `filter` back into the active outbox
+    /// as a [`Pending`](crate::model::EventStatus::Pending) event, keeping
+    /// its id, idempotency token and payload. Returns the ids that were
+    /// requeued.
+    ///
+    /// The move must be atomic per dead letter: it is either back in the
+    /// outbox or still quarantined, never both or neither. Backends may move
+    /// the matches in several batches, so a failing call can leave some of
+    /// them requeued. A dead letter whose idempotency token is held by an
+    /// event in the outbox again stays quarantined and is not returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn requeue(&self, filter: &DlqFilter) -> Result<Vec<EventId>, OutboxError>;
+
+    /// Moves a single dead letter back into the active outbox with its
+    /// payload replaced by `payload`. Returns `false` if no dead letter with
+    /// `id` exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the backing store call fails.
+    async fn requeue_with_payload(&self, id: EventId, payload: P) -> Result<bool, OutboxError>;
+}
+
+/// Requeues dead letters and resets their failure counters.
+///
+/// The store moves the rows back into the outbox; the heap is then told that
+/// each requeued id succeeded, so a replayed event is not quarantined again
+/// on its first failure because of counts left over from before.
+pub struct DlqAdmin<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    store: Arc<dyn DlqStore<P>>,
+    heap: Arc<dyn DlqHeap>,
+    _marker: PhantomData<P>,
+}
+
+impl<P> DlqAdmin<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new(store: Arc<dyn DlqStore<P>>, heap: Arc<dyn DlqHeap>) -> Self {
+        Self {
+            store,
+            heap,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Requeues every dead letter matching `filter`. See
+    /// [`DlqStore::requeue`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the store or heap call fails. A heap
+    /// failure leaves the events requeued but with stale counters.
+    pub async fn requeue(&self, filter: &DlqFilter) -> Result<Vec<EventId>, OutboxError> {
+        let ids = self.store.requeue(filter).await?;
+        for id in &ids {
+            self.heap.record_success(*id).await?;
+        }
+        debug!("DLQ admin: requeued {} dead letters", ids.len());
+        Ok(ids)
+    }
+
+    /// Requeues one dead letter with a patched payload. See
+    /// [`DlqStore::requeue_with_payload`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the store or heap call fails.
+    pub async fn requeue_with_payload(&self, id: EventId, payload: P) -> Result<bool, OutboxError> {
+        let requeued = self.store.requeue_with_payload(id, payload).await?;
+        if requeued {
+            self.heap.record_success(id).await?;
+        }
+        Ok(requeued)
+    }
+}
+
+#[cfg(all(test, feature = "dlq"))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::dlq::storage::MockDlqHeap;
+    use mockall::predicate::eq;
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload(u32);
+
+    fn admin(store: MockDlqStore<TestPayload>, heap: MockDlqHeap) -> DlqAdmin<TestPayload> {
+        DlqAdmin::new(Arc::new(store), Arc::new(heap))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn requeue_resets_heap_for_every_requeued_id() {
+        let ids = vec![EventId::default(), EventId::default()];
+        let returned = ids.clone();
+
+        let mut store = MockDlqStore::new();
+        store
+            .expect_requeue()
+            .withf(|f| f.event_type.as_deref() == Some("OrderPaid"))
+            .times(1)
+            .returning(move |_| Ok(returned.clone()));
+        let mut heap = MockDlqHeap::new();
+        for id in &ids {
+            heap.expect_record_success()
+                .with(eq(*id))
+                .times(1)
+                .returning(|_| Ok(()));
+        }
+
+        let result = admin(store, heap)
+            .requeue(&DlqFilter::all().with_event_type("OrderPaid"))
+            .await
+            .unwrap();
+        assert_eq!(result, ids);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn requeue_store_error_skips_heap() {
+        let mut store = MockDlqStore::new();
+        store
+            .expect_requeue()
+            .returning(|_| Err(OutboxError::DatabaseError("down".into())));
+        let mut heap = MockDlqHeap::new();
+        heap.expect_record_success().never();
+
+        let result = admin(store, heap).requeue(&DlqFilter::all()).await;
+        assert!(matches!(result, Err(OutboxError::DatabaseError(_))));
+    }
+
+    #[rstest]
+    #[case::found(true, 1)]
+    #[case::missing(false, 0)]
+    #[tokio::test]
+    async fn requeue_with_payload_resets_heap_only_when_found(
+        #[case] found: bool,
+        #[case] resets: usize,
+    ) {
+        let id = EventId::default();
+        let mut store = MockDlqStore::new();
+        store
+            .expect_requeue_with_payload()
+            .with(eq(id), eq(TestPayload(7)))
+            .times(1)
+            .returning(move |_, _| Ok(found));
+        let mut heap = MockDlqHeap::new();
+        heap.expect_record_success()
+            .with(eq(id))
+            .times(resets)
+            .returning(|_| Ok(()));
+
+        let result = admin(store, heap)
+            .requeue_with_payload(id, TestPayload(7))
+            .await
+            .unwrap();
+        assert_eq!(result, found);
+    }
+}
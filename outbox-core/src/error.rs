@@ -27,7 +27,14 @@ pub enum OutboxError {
     /// The event's idempotency token has already been reserved by a prior
     /// call. Returned by [`OutboxService::add_event`](crate::service::OutboxService::add_event)
     /// when [`IdempotencyStorageProvider::try_reserve`](crate::idempotency::storage::IdempotencyStorageProvider::try_reserve)
-    /// reports the token as taken. Not retryable.
+    /// reports the token as taken, and by writers whose storage rejects a
+    /// second row with the same idempotency token. Not retryable.
+    ///
+    /// Writers map their storage's unique-token violation to this variant
+    /// rather than to [`DatabaseError`](Self::DatabaseError): a token can
+    /// reach the insert without a reservation — no provider is configured,
+    /// or its reservation expired — and callers should see the same error
+    /// either way.
     #[error("Duplicate error")]
     DuplicateEvent,
     /// Failure from the primary event store (for example SQL errors, pool
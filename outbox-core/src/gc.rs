@@ -3,7 +3,9 @@
 //! [`OutboxManager`](crate::manager::OutboxManager) spawns a background task
 //! that wakes up on `config.gc_interval_secs` and asks a [`GarbageCollector`]
 //! to run. The collector is a thin proxy over
-//! [`OutboxStorage::delete_garbage`] — the actual retention logic lives in
+//! [`OutboxStorage::delete_garbage`] (plus
+//! [`OutboxStorage::purge_dead_letters`] for the dead-letter table when the
+//! `dlq` feature is on) — the actual retention logic lives in
 //! the storage implementation because what qualifies as "garbage" (age cut
 //! off, status filter, batch size) is backend-specific.
 
@@ -28,7 +30,7 @@ pub(crate) struct GarbageCollector<S, P> {
 
 impl<S, P> GarbageCollector<S, P>
 where
-    S: OutboxStorage<P> + 'static,
+    S: OutboxStorage<P> + Send + Sync + 'static,
     P: Debug + Clone + Serialize + Send + Sync,
 {
     /// Creates a collector that will call into the supplied storage.
@@ -40,16 +42,26 @@ where
     }
 
     /// Runs one cleanup pass by delegating to
-    /// [`OutboxStorage::delete_garbage`].
+    /// [`OutboxStorage::delete_garbage`] and, with the `dlq` feature, to
+    /// [`OutboxStorage::purge_dead_letters`].
     ///
     /// The collector itself is stateless — each call hits the storage layer,
-    /// which decides which rows are expired and removes them.
+    /// which decides which rows are expired and removes them. The dead-letter
+    /// purge runs even if the outbox cleanup failed.
     ///
     /// # Errors
     ///
     /// Propagates any [`OutboxError`] returned by the storage implementation.
+    /// If both steps fail, the `delete_garbage` error is returned.
     pub async fn collect_garbage(&self) -> Result<(), OutboxError> {
-        self.storage.delete_garbage().await
+        let result = self.storage.delete_garbage().await;
+        #[cfg(feature = "dlq")]
+        {
+            let purged = self.storage.purge_dead_letters().await;
+            result.and(purged)
+        }
+        #[cfg(not(feature = "dlq"))]
+        result
     }
 
     /// Runs the garbage-collection loop until shutdown is observed.
@@ -122,10 +134,19 @@ mod tests {
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
     struct TestPayload;
 
+    /// With `dlq` on, every collection pass also purges dead letters.
+    fn allow_purge(storage: &mut MockOutboxStorage<TestPayload>) {
+        #[cfg(feature = "dlq")]
+        storage.expect_purge_dead_letters().returning(|| Ok(()));
+        #[cfg(not(feature = "dlq"))]
+        let _ = storage;
+    }
+
     #[rstest]
     #[tokio::test]
     async fn collect_garbage_ok_proxies_to_delete_garbage() {
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage
             .expect_delete_garbage()
             .times(1)
@@ -139,6 +160,7 @@ mod tests {
     #[tokio::test]
     async fn collect_garbage_propagates_storage_error() {
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage
             .expect_delete_garbage()
             .times(1)
@@ -153,6 +175,7 @@ mod tests {
     #[tokio::test]
     async fn collect_garbage_invokes_storage_each_call_with_no_caching() {
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         // Stateless: two invocations must trigger two storage calls.
         storage
             .expect_delete_garbage()
@@ -164,12 +187,68 @@ mod tests {
         assert!(gc.collect_garbage().await.is_ok());
     }
 
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    #[tokio::test]
+    async fn collect_garbage_purges_dead_letters_after_delete_garbage() {
+        let mut seq = mockall::Sequence::new();
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_delete_garbage()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        storage
+            .expect_purge_dead_letters()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+
+        let gc = GarbageCollector::new(Arc::new(storage));
+        assert!(gc.collect_garbage().await.is_ok());
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    #[tokio::test]
+    async fn collect_garbage_purges_dead_letters_even_if_delete_garbage_fails() {
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage
+            .expect_delete_garbage()
+            .times(1)
+            .returning(|| Err(OutboxError::DatabaseError("gc failed".into())));
+        storage
+            .expect_purge_dead_letters()
+            .times(1)
+            .returning(|| Err(OutboxError::InfrastructureError("purge failed".into())));
+
+        let gc = GarbageCollector::new(Arc::new(storage));
+        let result = gc.collect_garbage().await;
+        assert!(matches!(result, Err(OutboxError::DatabaseError(_))));
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    #[tokio::test]
+    async fn collect_garbage_propagates_purge_error() {
+        let mut storage = MockOutboxStorage::<TestPayload>::new();
+        storage.expect_delete_garbage().returning(|| Ok(()));
+        storage
+            .expect_purge_dead_letters()
+            .returning(|| Err(OutboxError::DatabaseError("purge failed".into())));
+
+        let gc = GarbageCollector::new(Arc::new(storage));
+        let result = gc.collect_garbage().await;
+        assert!(matches!(result, Err(OutboxError::DatabaseError(_))));
+    }
+
     // ------------------------------- run loop -----------------------------
 
     #[rstest]
     #[tokio::test]
     async fn run_exits_when_shutdown_flag_is_set_to_true() {
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         // The first interval tick is immediate, so collect_garbage may be
         // called once before the shutdown wins the select; allow any count.
         storage.expect_delete_garbage().returning(|| Ok(()));
@@ -180,7 +259,7 @@ mod tests {
         let handle = tokio::spawn(async move {
             // A long interval keeps the tick branch out of the way after the
             // initial fire, so the test exits via the shutdown branch.
-            gc.run(Duration::from_secs(3600), &mut rx).await
+            gc.run(Duration::from_hours(1), &mut rx).await
         });
 
         // Let the worker enter the select before flipping the flag.
@@ -198,12 +277,13 @@ mod tests {
     #[tokio::test]
     async fn run_exits_when_sender_is_dropped() {
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage.expect_delete_garbage().returning(|| Ok(()));
 
         let gc = GarbageCollector::new(Arc::new(storage));
         let (tx, mut rx) = watch::channel(false);
 
-        let handle = tokio::spawn(async move { gc.run(Duration::from_secs(3600), &mut rx).await });
+        let handle = tokio::spawn(async move { gc.run(Duration::from_hours(1), &mut rx).await });
 
         tokio::task::yield_now().await;
         // Dropping the sender is treated as an implicit shutdown — the
@@ -223,12 +303,13 @@ mod tests {
         // Sending the same default value (false) must not be confused with a
         // shutdown signal — the loop should keep running.
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage.expect_delete_garbage().returning(|| Ok(()));
 
         let gc = GarbageCollector::new(Arc::new(storage));
         let (tx, mut rx) = watch::channel(false);
 
-        let handle = tokio::spawn(async move { gc.run(Duration::from_secs(3600), &mut rx).await });
+        let handle = tokio::spawn(async move { gc.run(Duration::from_hours(1), &mut rx).await });
 
         tokio::task::yield_now().await;
         // Flip to a value that is still falsy. The loop must observe the
@@ -251,6 +332,7 @@ mod tests {
         let counter_clone = counter.clone();
 
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage.expect_delete_garbage().returning(move || {
             counter_clone.fetch_add(1, Ordering::SeqCst);
             Ok(())
@@ -259,13 +341,13 @@ mod tests {
         let gc = GarbageCollector::new(Arc::new(storage));
         let (tx, mut rx) = watch::channel(false);
 
-        let handle = tokio::spawn(async move { gc.run(Duration::from_secs(60), &mut rx).await });
+        let handle = tokio::spawn(async move { gc.run(Duration::from_mins(1), &mut rx).await });
 
         // Initial immediate tick — let it fire.
         tokio::task::yield_now().await;
         // Two more interval boundaries.
-        tokio::time::advance(Duration::from_secs(60)).await;
-        tokio::time::advance(Duration::from_secs(60)).await;
+        tokio::time::advance(Duration::from_mins(1)).await;
+        tokio::time::advance(Duration::from_mins(1)).await;
         tokio::task::yield_now().await;
 
         tx.send(true).unwrap();
@@ -284,6 +366,7 @@ mod tests {
         let counter_clone = counter.clone();
 
         let mut storage = MockOutboxStorage::<TestPayload>::new();
+        allow_purge(&mut storage);
         storage.expect_delete_garbage().returning(move || {
             counter_clone.fetch_add(1, Ordering::SeqCst);
             Err(OutboxError::DatabaseError("transient".into()))
@@ -292,12 +375,12 @@ mod tests {
         let gc = GarbageCollector::new(Arc::new(storage));
         let (tx, mut rx) = watch::channel(false);
 
-        let handle = tokio::spawn(async move { gc.run(Duration::from_secs(60), &mut rx).await });
+        let handle = tokio::spawn(async move { gc.run(Duration::from_mins(1), &mut rx).await });
 
         // Drive at least two ticks so we observe the loop surviving an error
         // and proceeding to the next iteration.
         tokio::task::yield_now().await;
-        tokio::time::advance(Duration::from_secs(60)).await;
+        tokio::time::advance(Duration::from_mins(1)).await;
         tokio::task::yield_now().await;
 
         tx.send(true).unwrap();
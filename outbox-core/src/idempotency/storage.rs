@@ -33,6 +33,19 @@ pub trait IdempotencyStorageProvider {
     /// reservation backend itself fails (connection lost, timeout, etc.).
     /// The error is propagated to the caller without inserting the event.
     async fn try_reserve(&self, token: &IdempotencyToken) -> Result<bool, OutboxError>;
+
+    /// Releases a reservation made by a successful
+    /// [`try_reserve`](Self::try_reserve), so the token can be reserved again.
+    ///
+    /// Called by
+    /// [`OutboxService::add_events`](crate::service::OutboxService::add_events)
+    /// for the tokens it already reserved when a later one in the same batch
+    /// is rejected. Releasing a token that is not reserved is not an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] when the reservation backend itself fails.
+    async fn release(&self, token: &IdempotencyToken) -> Result<(), OutboxError>;
 }
 
 /// No-op [`IdempotencyStorageProvider`] that accepts every token.
@@ -51,6 +64,11 @@ impl IdempotencyStorageProvider for NoIdempotency {
     async fn try_reserve(&self, _token: &IdempotencyToken) -> Result<bool, OutboxError> {
         Ok(true)
     }
+
+    /// Always returns `Ok(())` — nothing was reserved.
+    async fn release(&self, _token: &IdempotencyToken) -> Result<(), OutboxError> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
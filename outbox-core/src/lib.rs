@@ -38,6 +38,7 @@
 //! ```
 
 mod builder;
+mod clock;
 mod config;
 mod dlq;
 mod error;
@@ -60,7 +61,7 @@ mod storage;
 pub mod prelude {
     pub use crate::idempotency::storage::IdempotencyStorageProvider;
     pub use crate::publisher::Transport;
-    pub use crate::storage::{OutboxStorage, OutboxWriter};
+    pub use crate::storage::{OutboxStorage, OutboxTxWriter, OutboxWriter};
 
     pub use crate::config::{IdempotencyStrategy, OutboxConfig};
     pub use crate::manager::OutboxManager;
@@ -71,10 +72,15 @@ pub mod prelude {
     pub use crate::object::{EventId, EventType, IdempotencyToken, Payload};
 
     pub use crate::builder::OutboxManagerBuilder;
+    pub use crate::clock::{Clock, ManualClock, SystemClock, from_unix_micros, to_unix_micros};
     pub use crate::error::OutboxError;
 
     #[cfg(feature = "dlq")]
-    pub use crate::dlq::model::DlqEntry;
+    pub use crate::dlq::model::{DeadLetter, DlqEntry, DlqFilter, DlqPage, DlqPolicy};
+    #[cfg(feature = "dlq")]
+    pub use crate::dlq::observer::DlqObserver;
     #[cfg(feature = "dlq")]
     pub use crate::dlq::storage::DlqHeap;
+    #[cfg(feature = "dlq")]
+    pub use crate::dlq::store::{DlqAdmin, DlqStore};
 }
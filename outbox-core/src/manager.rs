@@ -7,6 +7,7 @@
 //! the side. Construct one via [`OutboxManagerBuilder`](crate::builder::OutboxManagerBuilder).
 
 use crate::config::OutboxConfig;
+#[cfg(feature = "dlq")]
 use crate::dlq::processor::DlqProcessor;
 use crate::error::OutboxError;
 use crate::gc::GarbageCollector;
@@ -42,6 +43,10 @@ where
     shutdown_rx: Receiver<bool>,
     #[cfg(feature = "dlq")]
     dlq_heap: Arc<dyn crate::dlq::storage::DlqHeap>,
+    #[cfg(feature = "dlq")]
+    dlq_observers: Vec<Arc<dyn crate::dlq::observer::DlqObserver>>,
+    #[cfg(feature = "dlq")]
+    dlq_destination: Option<Arc<dyn Transport<PT>>>,
 }
 
 impl<S, P, PT> OutboxManager<S, P, PT>
@@ -73,9 +78,32 @@ where
             config,
             shutdown_rx,
             dlq_heap,
+            dlq_observers: Vec::new(),
+            dlq_destination: None,
         }
     }
 
+    /// Sets the observers notified by the DLQ reaper after each quarantine.
+    #[cfg(feature = "dlq")]
+    pub(crate) fn with_dlq_observers(
+        mut self,
+        observers: Vec<Arc<dyn crate::dlq::observer::DlqObserver>>,
+    ) -> Self {
+        self.dlq_observers = observers;
+        self
+    }
+
+    /// Sets the transport the DLQ reaper publishes quarantined events to in
+    /// place of the dead-letter table.
+    #[cfg(feature = "dlq")]
+    pub(crate) fn with_dlq_destination(
+        mut self,
+        destination: Option<Arc<dyn Transport<PT>>>,
+    ) -> Self {
+        self.dlq_destination = destination;
+        self
+    }
+
     /// Direct constructor used by
     /// [`OutboxManagerBuilder`](crate::builder::OutboxManagerBuilder).
     ///
@@ -168,12 +196,16 @@ where
 
         #[cfg(feature = "dlq")]
         {
-            let dlq_processor = DlqProcessor::new(
+            let mut dlq_processor = DlqProcessor::new(
                 self.dlq_heap.clone(),
                 self.storage.clone(),
                 self.config.clone(),
                 self.shutdown_rx.clone(),
-            );
+            )
+            .with_observers(self.dlq_observers.clone());
+            if let Some(destination) = self.dlq_destination.clone() {
+                dlq_processor = dlq_processor.with_destination(destination);
+            }
             tokio::spawn(async move { dlq_processor.run().await });
         }
 
@@ -242,7 +274,7 @@ where
 #[allow(clippy::unwrap_used)]
 mod tests {
     use crate::builder::OutboxManagerBuilder;
-    use crate::config::{IdempotencyStrategy, OutboxConfig};
+    use crate::config::OutboxConfig;
     #[cfg(feature = "dlq")]
     use crate::dlq::storage::MockDlqHeap;
     use crate::error::OutboxError;
@@ -266,14 +298,9 @@ mod tests {
     #[tokio::test]
     async fn test_event_send_success() {
         let config = OutboxConfig {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         };
 
         let mut storage_mock = MockOutboxStorage::<SomeDomainEvent>::new();
@@ -337,7 +364,7 @@ mod tests {
 
         for i in 1..=4 {
             let expected_type = i.to_string();
-            let expected_val = SomeDomainEvent::SomeEvent(format!("test{}", i));
+            let expected_val = SomeDomainEvent::SomeEvent(format!("test{i}"));
 
             transport_mock
                 .expect_publish()
@@ -411,14 +438,9 @@ mod tests {
         let transport_mock = MockTransport::<SomeDomainEvent>::new();
 
         let config = OutboxConfig::<SomeDomainEvent> {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         };
 
         #[cfg(feature = "dlq")]
@@ -471,10 +493,10 @@ mod tests {
             None,
         );
 
-        let id1 = e1.id.clone();
-        let id2 = e2.id.clone();
-        let id3 = e3.id.clone();
-        let id4 = e4.id.clone();
+        let id1 = e1.id;
+        let id2 = e2.id;
+        let id3 = e3.id;
+        let id4 = e4.id;
 
         storage_mock
             .expect_wait_for_notification()
@@ -498,7 +520,7 @@ mod tests {
                     return false;
                 }
 
-                let ids_set: std::collections::HashSet<_> = ids.iter().cloned().collect();
+                let ids_set: std::collections::HashSet<_> = ids.iter().copied().collect();
 
                 ids_set.len() == 3
                     && ids_set.contains(&id1)
@@ -540,21 +562,16 @@ mod tests {
             .returning(|_| Ok(()));
 
         let config = OutboxConfig::<SomeDomainEvent> {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         };
 
         #[cfg(feature = "dlq")]
         let dlq_heap_mock = {
             let mut m = MockDlqHeap::new();
             m.expect_record_success().returning(|_| Ok(()));
-            m.expect_record_failure().returning(|_| Ok(()));
+            m.expect_record_failure().returning(|_, _, _| Ok(()));
             m
         };
 
@@ -583,14 +600,9 @@ mod tests {
 
     fn default_config() -> OutboxConfig<SomeDomainEvent> {
         OutboxConfig {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         }
     }
 
@@ -702,7 +714,7 @@ mod tests {
         let manager = {
             let mut dlq = MockDlqHeap::new();
             dlq.expect_record_success().returning(|_| Ok(()));
-            dlq.expect_record_failure().returning(|_| Ok(()));
+            dlq.expect_record_failure().returning(|_, _, _| Ok(()));
             OutboxManagerBuilder::new()
                 .storage(Arc::new(storage_mock))
                 .publisher(Arc::new(transport_mock))
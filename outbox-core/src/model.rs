@@ -6,9 +6,12 @@
 //! function receives. When the `sqlx` feature is enabled, [`Event`] derives
 //! `sqlx::FromRow` so it can be decoded directly from a database row.
 
+use crate::clock::{Clock, SystemClock};
+use crate::error::OutboxError;
 use crate::object::{EventId, EventType, IdempotencyToken, Payload};
 use serde::Serialize;
 use std::fmt::Debug;
+use std::str::FromStr;
 use time::OffsetDateTime;
 
 /// A single outbox row representing one domain event to be published.
@@ -36,7 +39,8 @@ pub struct Event<PT> {
     /// The user payload, serialized as JSON when the `sqlx` feature is on.
     #[cfg_attr(feature = "sqlx", sqlx(json))]
     pub payload: Payload<PT>,
-    /// Wall-clock time the row was constructed, in UTC.
+    /// Time the row was constructed, in UTC, as told by the
+    /// [`Clock`] it was created with.
     pub created_at: OffsetDateTime,
     /// Expiration of the current processing lock. Fresh rows start with
     /// [`OffsetDateTime::UNIX_EPOCH`] (i.e. "not locked"); storage adapters
@@ -56,7 +60,7 @@ where
     /// defaults:
     ///
     /// - [`id`](Event::id) — a fresh random [`EventId`]
-    /// - [`created_at`](Event::created_at) — `OffsetDateTime::now_utc()`
+    /// - [`created_at`](Event::created_at) — the [`SystemClock`]'s current time
     /// - [`locked_until`](Event::locked_until) — `OffsetDateTime::UNIX_EPOCH`
     ///   (unlocked)
     /// - [`status`](Event::status) — [`EventStatus::Pending`]
@@ -64,13 +68,24 @@ where
         event_type: EventType,
         payload: Payload<PT>,
         idempotency_token: Option<IdempotencyToken>,
+    ) -> Self {
+        Self::new_with_clock(event_type, payload, idempotency_token, &SystemClock)
+    }
+
+    /// Like [`new`](Self::new), but stamps [`created_at`](Event::created_at)
+    /// with `clock`'s current time.
+    pub fn new_with_clock(
+        event_type: EventType,
+        payload: Payload<PT>,
+        idempotency_token: Option<IdempotencyToken>,
+        clock: &dyn Clock,
     ) -> Self {
         Self {
             id: EventId::default(),
             idempotency_token,
             event_type,
             payload,
-            created_at: OffsetDateTime::now_utc(),
+            created_at: clock.now(),
             locked_until: OffsetDateTime::UNIX_EPOCH,
             status: EventStatus::Pending,
         }
@@ -107,10 +122,42 @@ pub enum EventStatus {
     Sent,
 }
 
+impl EventStatus {
+    /// The variant name, which is how storage adapters without a native
+    /// enum type persist the status.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventStatus::Pending => "Pending",
+            EventStatus::Processing => "Processing",
+            EventStatus::Sent => "Sent",
+        }
+    }
+}
+
+impl FromStr for EventStatus {
+    type Err = OutboxError;
+
+    /// Parses a name written by [`as_str`](Self::as_str). Anything else can
+    /// only come from a corrupt row, so it fails with
+    /// [`OutboxError::DatabaseError`].
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "Pending" => Ok(EventStatus::Pending),
+            "Processing" => Ok(EventStatus::Processing),
+            "Sent" => Ok(EventStatus::Sent),
+            other => Err(OutboxError::DatabaseError(format!(
+                "unknown event status `{other}`"
+            ))),
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use crate::clock::ManualClock;
     use rstest::rstest;
     use serde::{Deserialize, Serialize};
 
@@ -147,6 +194,18 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn event_new_with_clock_takes_created_at_from_the_clock() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        clock.advance(std::time::Duration::from_secs(42));
+
+        let e = Event::new_with_clock(EventType::new("t"), payload("p"), None, &clock);
+        assert_eq!(
+            e.created_at,
+            OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(42)
+        );
+    }
+
     #[rstest]
     fn event_new_assigns_unique_ids_across_calls() {
         let a = Event::new(EventType::new("t"), payload("p"), None);
@@ -191,4 +250,20 @@ mod tests {
     ) {
         assert_eq!(a == b, expected);
     }
+
+    #[rstest]
+    #[case(EventStatus::Pending)]
+    #[case(EventStatus::Processing)]
+    #[case(EventStatus::Sent)]
+    fn event_status_round_trips_by_name(#[case] status: EventStatus) {
+        assert_eq!(status.as_str().parse::<EventStatus>().ok(), Some(status));
+    }
+
+    #[rstest]
+    fn event_status_rejects_unknown_name() {
+        assert!(matches!(
+            "pending".parse::<EventStatus>(),
+            Err(OutboxError::DatabaseError(_))
+        ));
+    }
 }
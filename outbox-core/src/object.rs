@@ -5,8 +5,10 @@
 //! transparent `sqlx::Type`, so they round-trip through database columns
 //! without additional conversion code.
 
+use crate::error::OutboxError;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Primary key of an outbox row.
@@ -37,6 +39,25 @@ impl EventId {
         self.0
     }
 }
+impl Display for EventId {
+    /// Formats the id as a hyphenated UUID, the text form storage adapters
+    /// persist it in.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+impl FromStr for EventId {
+    type Err = OutboxError;
+
+    /// Parses an id read back from storage. A value that is not a UUID can
+    /// only come from a corrupt row, so it fails with
+    /// [`OutboxError::DatabaseError`].
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(id)
+            .map(Self)
+            .map_err(|e| OutboxError::DatabaseError(format!("invalid event id `{id}`: {e}")))
+    }
+}
 
 /// Deduplication token attached to an [`Event`](crate::model::Event).
 ///
@@ -175,6 +196,22 @@ mod tests {
         assert_eq!(id.as_uuid().get_version_num(), 4);
     }
 
+    #[rstest]
+    fn event_id_round_trips_through_hyphenated_text() {
+        let id = EventId::default();
+        let text = id.to_string();
+        assert_eq!(text, id.as_uuid().hyphenated().to_string());
+        assert_eq!(text.parse::<EventId>().unwrap(), id);
+    }
+
+    #[rstest]
+    fn event_id_rejects_non_uuid_text() {
+        assert!(matches!(
+            "not-a-uuid".parse::<EventId>(),
+            Err(OutboxError::DatabaseError(_))
+        ));
+    }
+
     // ------------- IdempotencyToken -------------
 
     #[rstest]
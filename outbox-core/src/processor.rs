@@ -105,7 +105,8 @@ where
             #[cfg(feature = "metrics")]
             let start = std::time::Instant::now();
 
-            let event_type = event.event_type.to_string();
+            #[cfg(any(feature = "dlq", feature = "metrics"))]
+            let event_type = event.event_type.clone();
 
             match self.publisher.publish(event).await {
                 Ok(()) => {
@@ -118,13 +119,13 @@ where
 
                         metrics::counter!("outbox.events_total",
                             "status" => "success",
-                            "event_type" => event_type.clone()
+                            "event_type" => event_type.to_string()
                         )
                         .increment(1);
 
                         metrics::histogram!(
                             "outbox.publish_duration_seconds",
-                            "event_type" => event_type.clone()
+                            "event_type" => event_type.to_string()
                         )
                         .record(delta);
                     }
@@ -132,7 +133,7 @@ where
                 Err(e) => {
                     error!("Failed to publish event {:?}: {:?}", id, e);
                     #[cfg(feature = "dlq")]
-                    dlq_heap.record_failure(id).await?;
+                    dlq_heap.record_failure(id, &event_type, &e).await?;
 
                     #[cfg(feature = "metrics")]
                     {
@@ -140,14 +141,14 @@ where
 
                         metrics::counter!("outbox.events_total",
                             "status" => "error",
-                            "event_type" => event_type.clone()
+                            "event_type" => event_type.to_string()
                         )
                         .increment(1);
 
                         metrics::histogram!(
                             "outbox.publish_duration_seconds",
                             "status" => "error",
-                            "event_type" => event_type
+                            "event_type" => event_type.to_string()
                         )
                         .record(delta);
                     }
@@ -165,7 +166,7 @@ where
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use crate::config::{IdempotencyStrategy, OutboxConfig};
+    use crate::config::OutboxConfig;
     #[cfg(feature = "dlq")]
     use crate::dlq::storage::MockDlqHeap;
     use crate::model::EventStatus;
@@ -186,14 +187,9 @@ mod tests {
 
     fn config() -> Arc<OutboxConfig<TestEvent>> {
         Arc::new(OutboxConfig {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
             poll_interval_secs: 5,
-            lock_timeout_mins: 5,
-            idempotency_strategy: IdempotencyStrategy::None,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         })
     }
 
@@ -339,9 +335,13 @@ mod tests {
                 .times(2)
                 .returning(|_| Ok(()));
             dlq.expect_record_failure()
-                .withf(move |id| *id == id2)
+                .withf(move |id, event_type, err| {
+                    *id == id2
+                        && event_type.as_str() == "t2"
+                        && matches!(err, OutboxError::BrokerError(msg) if msg == "boom")
+                })
                 .times(1)
-                .returning(|_| Ok(()));
+                .returning(|_, _, _| Ok(()));
             processor.process_pending_events(Arc::new(dlq)).await
         };
 
@@ -376,7 +376,9 @@ mod tests {
         let result = {
             let mut dlq = MockDlqHeap::new();
             dlq.expect_record_success().times(0);
-            dlq.expect_record_failure().times(2).returning(|_| Ok(()));
+            dlq.expect_record_failure()
+                .times(2)
+                .returning(|_, _, _| Ok(()));
             processor.process_pending_events(Arc::new(dlq)).await
         };
 
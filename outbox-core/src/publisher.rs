@@ -5,6 +5,8 @@
 //! [`OutboxManager`](crate::manager::OutboxManager) to deliver events to a
 //! message bus.
 
+#[cfg(feature = "dlq")]
+use crate::dlq::model::DeadLetter;
 use crate::error::OutboxError;
 use crate::model::Event;
 use std::fmt::Debug;
@@ -40,4 +42,41 @@ where
     /// the `dlq` feature is on, tracked via the DLQ heap) while sibling
     /// events in the same batch are still processed.
     async fn publish(&self, event: Event<P>) -> Result<(), OutboxError>;
+
+    /// Sends a quarantined event to a dead-letter destination.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) when
+    /// this transport is configured as the DLQ destination (see
+    /// [`OutboxManagerBuilder::dlq_destination`](crate::builder::OutboxManagerBuilder::dlq_destination))
+    /// instead of the storage's dead-letter table. `letter` carries the
+    /// original event together with its failure data. Transports with message
+    /// headers should forward that data next to their usual headers, so
+    /// consumers of the destination can tell why the event ended up there:
+    ///
+    /// - `dlq_failure_count` — [`DeadLetter::failure_count`]
+    /// - `dlq_quarantined_at` — [`DeadLetter::quarantined_at`]
+    /// - `dlq_last_error` — [`DeadLetter::last_error`], omitted when `None`
+    /// - `dlq_first_failed_at` — [`DeadLetter::first_failed_at`], omitted
+    ///   when `None`
+    ///
+    /// Timestamps use the `Display` format of [`time::OffsetDateTime`].
+    ///
+    /// The default implementation publishes [`DeadLetter::event`] through
+    /// [`publish`](Self::publish) and drops the failure data. Only compiled
+    /// with the `dlq` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the broker call fails. The event then
+    /// stays in the outbox and keeps its claim in the
+    /// [`DlqHeap`](crate::dlq::storage::DlqHeap); once
+    /// `config.dlq_claim_timeout_secs` has passed the claim is reclaimed and
+    /// a later reaper pass publishes it again.
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<P>) -> Result<(), OutboxError>
+    where
+        P: 'static,
+    {
+        self.publish(letter.event).await
+    }
 }
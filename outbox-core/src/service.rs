@@ -5,24 +5,29 @@
 //! [`IdempotencyStrategy`](crate::config::IdempotencyStrategy) to produce (or
 //! accept) a token, optionally reserves that token through an external
 //! [`IdempotencyStorageProvider`] to reject duplicates, and then persists the
-//! event via an [`OutboxWriter`]. The worker side
+//! event via an [`OutboxWriter`] — or, for the `*_in` variants, via an
+//! [`OutboxTxWriter`] inside a transaction the caller owns. The worker side
 //! ([`OutboxManager`](crate::manager::OutboxManager)) picks it up later.
 
+use crate::config::IdempotencyStrategy;
 use crate::error::OutboxError;
 use crate::idempotency::storage::NoIdempotency;
 use crate::model::Event;
 use crate::object::{EventType, IdempotencyToken, Payload};
 use crate::prelude::{IdempotencyStorageProvider, OutboxConfig};
-use crate::storage::OutboxWriter;
+use crate::storage::{OutboxTxWriter, OutboxWriter};
 use serde::Serialize;
 use std::fmt::Debug;
 use std::sync::Arc;
+use tracing::warn;
 
 /// Producer-side facade for writing outbox events.
 ///
 /// The service is generic over:
 ///
-/// - `W` — [`OutboxWriter`] implementation (persists the event row)
+/// - `W` — [`OutboxWriter`] and/or [`OutboxTxWriter`] implementation
+///   (persists the event row); which methods are available depends on which
+///   of the two traits it implements
 /// - `S` — [`IdempotencyStorageProvider`] implementation used to reserve
 ///   tokens; set to [`NoIdempotency`] when no external reservation is needed
 /// - `P` — the user's domain event payload type (`Debug + Clone + Serialize`)
@@ -41,7 +46,7 @@ where
 
 impl<W, P> OutboxService<W, NoIdempotency, P>
 where
-    W: OutboxWriter<P> + Send + Sync + 'static,
+    W: Send + Sync + 'static,
     P: Debug + Clone + Serialize + Send + Sync,
 {
     /// Creates a service without any external idempotency reservation.
@@ -63,7 +68,7 @@ where
 
 impl<W, S, P> OutboxService<W, S, P>
 where
-    W: OutboxWriter<P> + Send + Sync + 'static,
+    W: Send + Sync + 'static,
     S: IdempotencyStorageProvider + Send + Sync + 'static,
     P: Debug + Clone + Serialize + Send + Sync,
 {
@@ -139,8 +144,186 @@ where
         get_event: F,
     ) -> Result<(), OutboxError>
     where
+        W: OutboxWriter<P>,
         F: FnOnce() -> Option<Event<P>>,
         P: Debug + Clone + Serialize + Send + Sync,
+    {
+        let event = self
+            .prepare_event(event_type, payload, provided_token, get_event)
+            .await?;
+        self.writer.insert_event(event).await
+    }
+
+    /// Adds several events to the outbox storage in a single writer call.
+    ///
+    /// Each item is an `(event_type, payload, provided_token)` triple. The
+    /// idempotency strategy is applied to every event individually, exactly as
+    /// in [`add_event`](Self::add_event); for the `Custom` strategy the
+    /// closure receives an [`Event`] built from the item itself, so no
+    /// `get_event` callback is needed.
+    ///
+    /// Tokens are reserved in order before anything is written. If any of
+    /// them turns out to be a duplicate, or its reservation fails, the whole
+    /// batch is rejected and nothing is inserted — the tokens already
+    /// reserved for earlier items are released through
+    /// [`IdempotencyStorageProvider::release`], so the corrected batch can be
+    /// retried. Once every reservation succeeds, the events are handed to
+    /// [`OutboxWriter::insert_events`] in one call.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::DuplicateEvent`] if any event token has already
+    /// been used. Returns any [`OutboxError`] variant propagated from the
+    /// reservation calls or from the writer's `insert_events`.
+    pub async fn add_events<'a, I>(&self, events: I) -> Result<(), OutboxError>
+    where
+        W: OutboxWriter<P>,
+        I: IntoIterator<Item = (&'a str, P, Option<String>)>,
+        P: 'static,
+    {
+        let prepared = self.prepare_events(events).await?;
+        if prepared.is_empty() {
+            return Ok(());
+        }
+        self.writer.insert_events(prepared).await
+    }
+
+    /// Adds a new event inside a transaction owned by the caller.
+    ///
+    /// Behaves exactly like [`add_event`](Self::add_event) — same token
+    /// resolution, same reservation — but the row is written through
+    /// [`OutboxTxWriter::insert_event_in`] using `tx`, so it becomes visible
+    /// only when the caller commits, together with the business data written
+    /// in the same transaction. The service itself holds no transaction and
+    /// can be shared across requests.
+    ///
+    /// The idempotency reservation is not part of `tx`: if the transaction
+    /// is rolled back, a token reserved through an external provider stays
+    /// reserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::DuplicateEvent`] if the event token has already
+    /// been used. Returns any [`OutboxError`] variant propagated from the
+    /// reservation call or from the writer's `insert_event_in`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the idempotency strategy is set to `Custom`, but `get_event`
+    /// returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut tx = pool.begin().await?;
+    /// // ... business writes on `tx` ...
+    /// service
+    ///     .add_event_in(&mut tx, "order.created", payload, None, || None)
+    ///     .await?;
+    /// tx.commit().await?;
+    /// ```
+    pub async fn add_event_in<Tx, F>(
+        &self,
+        tx: &mut Tx,
+        event_type: &str,
+        payload: P,
+        provided_token: Option<String>,
+        get_event: F,
+    ) -> Result<(), OutboxError>
+    where
+        W: OutboxTxWriter<P, Tx>,
+        Tx: Send,
+        F: FnOnce() -> Option<Event<P>>,
+    {
+        let event = self
+            .prepare_event(event_type, payload, provided_token, get_event)
+            .await?;
+        self.writer.insert_event_in(tx, event).await
+    }
+
+    /// Adds several events inside a transaction owned by the caller.
+    ///
+    /// The transactional counterpart of [`add_events`](Self::add_events):
+    /// idempotency is applied per event and the batch is handed to
+    /// [`OutboxTxWriter::insert_events_in`] using `tx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::DuplicateEvent`] if any event token has already
+    /// been used. Returns any [`OutboxError`] variant propagated from the
+    /// reservation calls or from the writer's `insert_events_in`.
+    pub async fn add_events_in<'a, Tx, I>(&self, tx: &mut Tx, events: I) -> Result<(), OutboxError>
+    where
+        W: OutboxTxWriter<P, Tx>,
+        Tx: Send,
+        I: IntoIterator<Item = (&'a str, P, Option<String>)>,
+        P: 'static,
+    {
+        let prepared = self.prepare_events(events).await?;
+        if prepared.is_empty() {
+            return Ok(());
+        }
+        self.writer.insert_events_in(tx, prepared).await
+    }
+
+    async fn prepare_events<'a, I>(&self, events: I) -> Result<Vec<Event<P>>, OutboxError>
+    where
+        I: IntoIterator<Item = (&'a str, P, Option<String>)>,
+    {
+        let mut prepared = Vec::new();
+        for (event_type, payload, provided_token) in events {
+            let context = matches!(
+                self.config.idempotency_strategy,
+                IdempotencyStrategy::Custom(_)
+            )
+            .then(|| {
+                Event::new_with_clock(
+                    EventType::new(event_type),
+                    Payload::from_ref(&payload),
+                    None,
+                    self.config.clock.as_ref(),
+                )
+            });
+
+            match self
+                .prepare_event(event_type, payload, provided_token, || context)
+                .await
+            {
+                Ok(event) => prepared.push(event),
+                Err(e) => {
+                    self.release_tokens(&prepared).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(prepared)
+    }
+
+    /// Releases the tokens reserved for `events`. A failed release is only
+    /// logged: the caller is already returning the error that caused it.
+    async fn release_tokens(&self, events: &[Event<P>]) {
+        let Some(i_provider) = &self.idempotency_storage else {
+            return;
+        };
+        for token in events.iter().filter_map(|e| e.idempotency_token.as_ref()) {
+            if let Err(e) = i_provider.release(token).await {
+                warn!(
+                    "Failed to release idempotency token {}: {e}",
+                    token.as_str()
+                );
+            }
+        }
+    }
+
+    async fn prepare_event<F>(
+        &self,
+        event_type: &str,
+        payload: P,
+        provided_token: Option<String>,
+        get_event: F,
+    ) -> Result<Event<P>, OutboxError>
+    where
+        F: FnOnce() -> Option<Event<P>>,
     {
         let i_token = self
             .config
@@ -155,8 +338,12 @@ where
             return Err(OutboxError::DuplicateEvent);
         }
 
-        let event = Event::new(EventType::new(event_type), Payload::new(payload), i_token);
-        self.writer.insert_event(event).await
+        Ok(Event::new_with_clock(
+            EventType::new(event_type),
+            Payload::new(payload),
+            i_token,
+            self.config.clock.as_ref(),
+        ))
     }
 }
 
@@ -164,6 +351,7 @@ where
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use crate::clock::ManualClock;
     use crate::config::IdempotencyStrategy;
     use crate::idempotency::storage::MockIdempotencyStorageProvider;
     use crate::storage::MockOutboxWriter;
@@ -179,16 +367,33 @@ mod tests {
         TestPayload { kind: "k".into() }
     }
 
+    #[derive(Debug, Default)]
+    struct FakeTx {
+        statements: usize,
+    }
+
+    mockall::mock! {
+        TxWriter {}
+        #[async_trait::async_trait]
+        impl OutboxTxWriter<TestPayload, FakeTx> for TxWriter {
+            async fn insert_event_in(
+                &self,
+                tx: &mut FakeTx,
+                event: Event<TestPayload>,
+            ) -> Result<(), OutboxError>;
+            async fn insert_events_in(
+                &self,
+                tx: &mut FakeTx,
+                events: Vec<Event<TestPayload>>,
+            ) -> Result<(), OutboxError>;
+        }
+    }
+
     fn config_with(strategy: IdempotencyStrategy<TestPayload>) -> Arc<OutboxConfig<TestPayload>> {
         Arc::new(OutboxConfig {
-            batch_size: 100,
-            retention_days: 7,
-            gc_interval_secs: 3600,
-            poll_interval_secs: 10,
-            lock_timeout_mins: 5,
             idempotency_strategy: strategy,
-            dlq_threshold: 10,
             dlq_interval_secs: 1,
+            ..OutboxConfig::default()
         })
     }
 
@@ -226,6 +431,27 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn events_are_stamped_with_the_configured_clock() {
+        let clock = ManualClock::new(time::OffsetDateTime::UNIX_EPOCH);
+        clock.advance(std::time::Duration::from_hours(5));
+        let mut writer = MockOutboxWriter::<TestPayload>::new();
+        writer
+            .expect_insert_event()
+            .withf(|e| e.created_at == time::OffsetDateTime::UNIX_EPOCH + time::Duration::hours(5))
+            .times(1)
+            .returning(|_| Ok(()));
+        let config = OutboxConfig {
+            clock: Arc::new(clock),
+            ..OutboxConfig::default()
+        };
+
+        let service = OutboxService::new(Arc::new(writer), Arc::new(config));
+        let result = service.add_event("t", payload(), None, || None).await;
+        assert!(result.is_ok());
+    }
+
     #[rstest]
     #[tokio::test]
     async fn uuid_strategy_with_storage_reserves_same_token_as_inserted() {
@@ -438,4 +664,205 @@ mod tests {
         let result = service.add_event("t", payload(), None, || None).await;
         assert!(matches!(result, Err(OutboxError::DatabaseError(_))));
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_events_reserves_each_token_and_inserts_batch_in_one_call() {
+        let mut writer = MockOutboxWriter::<TestPayload>::new();
+        let mut idem = MockIdempotencyStorageProvider::new();
+
+        idem.expect_try_reserve().times(3).returning(|_| Ok(true));
+        writer.expect_insert_event().times(0);
+        writer
+            .expect_insert_events()
+            .withf(|events| {
+                let tokens: Vec<_> = events
+                    .iter()
+                    .map(|e| e.idempotency_token.as_ref().map(|t| t.as_str().to_owned()))
+                    .collect();
+                events.len() == 3
+                    && events[1].event_type.as_str() == "b"
+                    && tokens
+                        == vec![
+                            Some("t-a".to_string()),
+                            Some("t-b".to_string()),
+                            Some("t-c".to_string()),
+                        ]
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = OutboxService::with_idempotency(
+            Arc::new(writer),
+            config_with(IdempotencyStrategy::Provided),
+            Arc::new(idem),
+        );
+        let result = service
+            .add_events(vec![
+                ("a", payload(), Some("t-a".to_string())),
+                ("b", payload(), Some("t-b".to_string())),
+                ("c", payload(), Some("t-c".to_string())),
+            ])
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_events_rejects_whole_batch_when_one_token_is_duplicate() {
+        let mut writer = MockOutboxWriter::<TestPayload>::new();
+        let mut idem = MockIdempotencyStorageProvider::new();
+
+        let reserved = Arc::new(std::sync::Mutex::new(std::collections::HashSet::from([
+            "dup".to_string(),
+        ])));
+        let reserved_r = reserved.clone();
+        idem.expect_try_reserve()
+            .returning(move |t| Ok(reserved_r.lock().unwrap().insert(t.as_str().to_owned())));
+        let reserved_d = reserved.clone();
+        idem.expect_release().returning(move |t| {
+            reserved_d.lock().unwrap().remove(t.as_str());
+            Ok(())
+        });
+        writer
+            .expect_insert_events()
+            .withf(|events| events.len() == 2)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = OutboxService::with_idempotency(
+            Arc::new(writer),
+            config_with(IdempotencyStrategy::Provided),
+            Arc::new(idem),
+        );
+        let result = service
+            .add_events(vec![
+                ("a", payload(), Some("fresh".to_string())),
+                ("b", payload(), Some("dup".to_string())),
+            ])
+            .await;
+        assert!(matches!(result, Err(OutboxError::DuplicateEvent)));
+        assert!(!reserved.lock().unwrap().contains("fresh"));
+
+        let retry = service
+            .add_events(vec![
+                ("a", payload(), Some("fresh".to_string())),
+                ("b", payload(), Some("fixed".to_string())),
+            ])
+            .await;
+        assert!(retry.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_events_custom_strategy_derives_token_from_each_item() {
+        fn derive(event: &Event<TestPayload>) -> String {
+            format!("{}:{}", event.event_type, event.payload.as_value().kind)
+        }
+
+        let mut writer = MockOutboxWriter::<TestPayload>::new();
+        writer
+            .expect_insert_events()
+            .withf(|events| {
+                events
+                    .iter()
+                    .map(|e| e.idempotency_token.as_ref().map(|t| t.as_str().to_owned()))
+                    .collect::<Vec<_>>()
+                    == vec![Some("x:one".to_string()), Some("y:two".to_string())]
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = OutboxService::new(
+            Arc::new(writer),
+            config_with(IdempotencyStrategy::Custom(derive)),
+        );
+        let result = service
+            .add_events(vec![
+                ("x", TestPayload { kind: "one".into() }, None),
+                ("y", TestPayload { kind: "two".into() }, None),
+            ])
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_events_with_empty_input_does_not_call_writer() {
+        let mut writer = MockOutboxWriter::<TestPayload>::new();
+        writer.expect_insert_events().times(0);
+
+        let service = OutboxService::new(Arc::new(writer), config_with(IdempotencyStrategy::Uuid));
+        let result = service.add_events(Vec::new()).await;
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_event_in_writes_through_borrowed_transaction() {
+        let mut writer = MockTxWriter::new();
+        writer
+            .expect_insert_event_in()
+            .withf(|_, e| e.event_type.as_str() == "t" && e.idempotency_token.is_some())
+            .times(1)
+            .returning(|tx, _| {
+                tx.statements += 1;
+                Ok(())
+            });
+
+        let service = OutboxService::new(Arc::new(writer), config_with(IdempotencyStrategy::Uuid));
+        let mut tx = FakeTx::default();
+        let result = service
+            .add_event_in(&mut tx, "t", payload(), None, || None)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(tx.statements, 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_event_in_duplicate_leaves_transaction_untouched() {
+        let mut writer = MockTxWriter::new();
+        let mut idem = MockIdempotencyStorageProvider::new();
+
+        idem.expect_try_reserve().times(1).returning(|_| Ok(false));
+        writer.expect_insert_event_in().times(0);
+
+        let service = OutboxService::with_idempotency(
+            Arc::new(writer),
+            config_with(IdempotencyStrategy::Provided),
+            Arc::new(idem),
+        );
+        let mut tx = FakeTx::default();
+        let result = service
+            .add_event_in(&mut tx, "t", payload(), Some("dup".into()), || None)
+            .await;
+        assert!(matches!(result, Err(OutboxError::DuplicateEvent)));
+        assert_eq!(tx.statements, 0);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn add_events_in_hands_whole_batch_to_transaction_writer() {
+        let mut writer = MockTxWriter::new();
+        writer
+            .expect_insert_events_in()
+            .withf(|_, events| events.len() == 2)
+            .times(1)
+            .returning(|tx, events| {
+                tx.statements += events.len();
+                Ok(())
+            });
+
+        let service = OutboxService::new(Arc::new(writer), config_with(IdempotencyStrategy::None));
+        let mut tx = FakeTx::default();
+        let result = service
+            .add_events_in(
+                &mut tx,
+                vec![("a", payload(), None), ("b", payload(), None)],
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(tx.statements, 2);
+    }
 }
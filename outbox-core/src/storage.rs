@@ -1,9 +1,11 @@
 //! Storage abstractions backing the outbox table.
 //!
-//! Two traits split the read/write responsibilities:
+//! Three traits split the read/write responsibilities:
 //!
 //! - [`OutboxWriter`] — producer-side insert path, used by
 //!   [`OutboxService`](crate::service::OutboxService) to persist new events.
+//! - [`OutboxTxWriter`] — the same insert path, but bound to a transaction
+//!   borrowed per call so events are written atomically with business data.
 //! - [`OutboxStorage`] — worker-side read and lifecycle path, used by
 //!   [`OutboxManager`](crate::manager::OutboxManager) to fetch pending rows,
 //!   record status transitions, prune old data, and wait for notifications.
@@ -18,6 +20,7 @@ use crate::object::EventId;
 use async_trait::async_trait;
 use serde::Serialize;
 use std::fmt::Debug;
+use std::time::Duration;
 
 /// Worker-side storage contract.
 ///
@@ -25,6 +28,7 @@ use std::fmt::Debug;
 /// [`OutboxManager`](crate::manager::OutboxManager) drives on every tick:
 /// claiming pending rows, recording their outcome, cleaning up finished data,
 /// and blocking until an external notification arrives.
+#[cfg_attr(test, allow(clippy::used_underscore_binding))]
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait OutboxStorage<P>
@@ -104,6 +108,11 @@ where
     /// by GC, manual operator action, etc.) are silently dropped — only
     /// matched rows are moved.
     ///
+    /// Returns the entries that were actually moved, with
+    /// [`event_type`](crate::dlq::model::DlqEntry::event_type) filled in from
+    /// the moved row. The processor hands this list to the configured
+    /// [`DlqObserver`](crate::dlq::observer::DlqObserver)s.
+    ///
     /// # Default implementation
     ///
     /// The default implementation returns an [`OutboxError::ConfigError`].
@@ -127,13 +136,137 @@ where
     async fn quarantine_events(
         &self,
         _entries: &[crate::dlq::model::DlqEntry],
-    ) -> Result<(), OutboxError> {
+    ) -> Result<Vec<crate::dlq::model::DlqEntry>, OutboxError> {
         Err(OutboxError::ConfigError(
             "OutboxStorage::quarantine_events: DLQ is not implemented by this backend \
              (rebuild the storage crate with its `dlq` feature enabled)"
                 .to_string(),
         ))
     }
+
+    /// Returns the events with the given ids from the active outbox table,
+    /// in any order. Ids without a row are skipped.
+    ///
+    /// Used by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) when a
+    /// [`Transport`](crate::publisher::Transport) is configured as the
+    /// dead-letter destination: the drained events are loaded, published to
+    /// that transport and then removed with
+    /// [`delete_events`](Self::delete_events).
+    ///
+    /// # Default implementation
+    ///
+    /// Returns an [`OutboxError::ConfigError`], like
+    /// [`quarantine_events`](Self::quarantine_events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the underlying datastore call fails.
+    async fn fetch_events(&self, _ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        Err(OutboxError::ConfigError(
+            "OutboxStorage::fetch_events: a DLQ transport destination is not supported by this \
+             backend"
+                .to_string(),
+        ))
+    }
+
+    /// Deletes the events with the given ids from the active outbox table and
+    /// returns the number of rows removed. Unknown ids are ignored.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) only
+    /// after the events were published to the dead-letter
+    /// [`Transport`](crate::publisher::Transport), so a failed publish never
+    /// loses an event.
+    ///
+    /// # Default implementation
+    ///
+    /// Returns an [`OutboxError::ConfigError`], like
+    /// [`quarantine_events`](Self::quarantine_events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the underlying datastore call fails.
+    async fn delete_events(&self, _ids: &[EventId]) -> Result<u64, OutboxError> {
+        Err(OutboxError::ConfigError(
+            "OutboxStorage::delete_events: a DLQ transport destination is not supported by this \
+             backend"
+                .to_string(),
+        ))
+    }
+
+    /// Deletes quarantined events that have been in the dead-letter table
+    /// for longer than `config.dlq_retention_days`.
+    ///
+    /// Called by the garbage collector right after
+    /// [`delete_garbage`](Self::delete_garbage) when the `dlq` feature is
+    /// enabled. Like `delete_garbage`, implementations may cap the number of
+    /// rows removed per call; the remainder is picked up on the next pass.
+    ///
+    /// # Default implementation
+    ///
+    /// Does nothing and returns `Ok(())` — a backend without a dead-letter
+    /// table has nothing to purge. Not feature-gated for the same reason as
+    /// [`quarantine_events`](Self::quarantine_events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the underlying datastore call fails.
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        Ok(())
+    }
+
+    /// Moves dead letters that have been quarantined for at least `cooldown`
+    /// and revived fewer than `max_revivals` times back into the active
+    /// outbox as [`Pending`](crate::model::EventStatus::Pending) events.
+    /// Returns the ids that were revived.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) on
+    /// each tick when `config.dlq_revival_cooldown_secs` is set. Each revived
+    /// event carries its revival count back into the outbox so that, if it
+    /// is quarantined again, the count survives; once it reaches
+    /// `max_revivals` the dead letter is left alone. A dead letter whose
+    /// idempotency token is held by a row in the outbox is skipped as well,
+    /// so one re-emitted event does not fail the whole batch; it is revived
+    /// once that row is gone. Like
+    /// [`quarantine_events`](Self::quarantine_events), the move must be
+    /// atomic and implementations may cap the number of rows per call.
+    ///
+    /// # Default implementation
+    ///
+    /// Does nothing and returns an empty list. Not feature-gated for the same
+    /// reason as [`quarantine_events`](Self::quarantine_events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the underlying datastore call fails.
+    async fn revive_dead_letters(
+        &self,
+        _cooldown: Duration,
+        _max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the ids from `ids` that have no row in the active outbox
+    /// table, in any order.
+    ///
+    /// Called by [`DlqProcessor`](crate::dlq::processor::DlqProcessor) when it
+    /// reconciles the [`DlqHeap`](crate::dlq::storage::DlqHeap) with the
+    /// outbox: failure counters for the returned ids are orphaned (the event
+    /// was deleted, or succeeded while its `record_success` call failed) and
+    /// are removed with [`DlqHeap::forget`](crate::dlq::storage::DlqHeap::forget).
+    ///
+    /// # Default implementation
+    ///
+    /// Returns an empty list, so no counter is ever treated as orphaned. Not
+    /// feature-gated for the same reason as
+    /// [`quarantine_events`](Self::quarantine_events).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the underlying datastore call fails.
+    async fn missing_events(&self, _ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        Ok(Vec::new())
+    }
 }
 
 /// Producer-side storage contract.
@@ -153,8 +286,92 @@ where
     ///
     /// # Errors
     ///
-    /// Returns an [`OutboxError`] if the insert fails — typically a
-    /// [`DatabaseError`](OutboxError::DatabaseError) on a unique-constraint
-    /// violation or connection issue.
+    /// Returns [`OutboxError::DuplicateEvent`] if the storage already holds
+    /// an event with the same idempotency token, or another [`OutboxError`]
+    /// (typically [`DatabaseError`](OutboxError::DatabaseError)) if the insert
+    /// fails for any other reason.
     async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError>;
+
+    /// Persists several [`Event`] rows in the outbox table.
+    ///
+    /// Called by [`OutboxService::add_events`](crate::service::OutboxService::add_events)
+    /// once every event in the batch has passed its idempotency reservation.
+    /// Backends are expected to override this with a single round trip (for
+    /// example a multi-row `INSERT`) that either persists the whole batch or
+    /// nothing.
+    ///
+    /// # Default implementation
+    ///
+    /// Calls [`insert_event`](Self::insert_event) once per event, in order,
+    /// and stops at the first error. Events inserted before the failure are
+    /// **not** rolled back, so the default is only atomic when the writer
+    /// itself is bound to a transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if any insert fails.
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError>
+    where
+        P: 'static,
+    {
+        for event in events {
+            self.insert_event(event).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Producer-side storage contract for writes that join a caller-owned
+/// transaction.
+///
+/// Where [`OutboxWriter`] owns its connection (or transaction) for the whole
+/// lifetime of the writer, this trait receives the transaction as `&mut Tx`
+/// on every call. That lets an [`OutboxService`](crate::service::OutboxService)
+/// be constructed once at startup and still write each event inside whatever
+/// business transaction the caller currently has open. `Tx` is the backend's
+/// connection or transaction type — for example `sqlx::PgConnection` or
+/// `sqlx::Transaction<'_, Postgres>`.
+///
+/// Committing or rolling back the transaction is the caller's
+/// responsibility; implementations must never do either.
+#[async_trait]
+pub trait OutboxTxWriter<P, Tx>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+    Tx: Send,
+{
+    /// Persists a single [`Event`] row using the supplied transaction.
+    ///
+    /// Called by [`OutboxService::add_event_in`](crate::service::OutboxService::add_event_in)
+    /// after any configured idempotency reservation has succeeded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if the insert fails. The transaction is
+    /// left open; most databases require it to be rolled back afterwards.
+    async fn insert_event_in(&self, tx: &mut Tx, event: Event<P>) -> Result<(), OutboxError>;
+
+    /// Persists several [`Event`] rows using the supplied transaction.
+    ///
+    /// Called by [`OutboxService::add_events_in`](crate::service::OutboxService::add_events_in).
+    ///
+    /// # Default implementation
+    ///
+    /// Calls [`insert_event_in`](Self::insert_event_in) once per event, in
+    /// order, and stops at the first error. Since every insert goes through
+    /// the same transaction, the batch is still atomic once the caller
+    /// commits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OutboxError`] if any insert fails.
+    async fn insert_events_in(&self, tx: &mut Tx, events: Vec<Event<P>>) -> Result<(), OutboxError>
+    where
+        P: 'static,
+    {
+        for event in events {
+            self.insert_event_in(tx, event).await?;
+        }
+        Ok(())
+    }
 }
@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
 use outbox_core::prelude::{Event, OutboxError, Transport};
 use rdkafka::ClientConfig;
 use rdkafka::message::{Header, OwnedHeaders};
@@ -32,15 +34,8 @@ impl KafkaTransport {
     }
 }
 
-#[async_trait]
-impl<PT> Transport<PT> for KafkaTransport
-where
-    PT: Debug + Clone + Send + Sync + Serialize + KafkaKeyExtractable + 'static,
-{
-    async fn publish(&self, event: Event<PT>) -> Result<(), OutboxError> {
-        let payload_bytes = serde_json::to_vec(&event.payload)
-            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
-
+impl KafkaTransport {
+    fn event_headers<PT>(event: &Event<PT>) -> OwnedHeaders {
         let mut headers = OwnedHeaders::new();
 
         headers = headers
@@ -57,12 +52,21 @@ where
                 value: Some(&event.created_at.to_string()),
             });
 
-        if let Some(i_token) = event.idempotency_token {
+        if let Some(i_token) = &event.idempotency_token {
             headers = headers.insert(Header {
                 key: "idempotency_token",
                 value: Some(i_token.as_str()),
             });
         }
+        headers
+    }
+
+    async fn send<PT>(&self, event: &Event<PT>, headers: OwnedHeaders) -> Result<(), OutboxError>
+    where
+        PT: Debug + Clone + Send + Sync + Serialize + KafkaKeyExtractable,
+    {
+        let payload_bytes = serde_json::to_vec(&event.payload)
+            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
 
         self.producer
             .send(
@@ -77,3 +81,42 @@ where
         Ok(())
     }
 }
+
+#[async_trait]
+impl<PT> Transport<PT> for KafkaTransport
+where
+    PT: Debug + Clone + Send + Sync + Serialize + KafkaKeyExtractable + 'static,
+{
+    async fn publish(&self, event: Event<PT>) -> Result<(), OutboxError> {
+        let headers = Self::event_headers(&event);
+        self.send(&event, headers).await
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<PT>) -> Result<(), OutboxError> {
+        let mut headers = Self::event_headers(&letter.event)
+            .insert(Header {
+                key: "dlq_failure_count",
+                value: Some(&letter.failure_count.to_string()),
+            })
+            .insert(Header {
+                key: "dlq_quarantined_at",
+                value: Some(&letter.quarantined_at.to_string()),
+            });
+
+        if let Some(last_error) = &letter.last_error {
+            headers = headers.insert(Header {
+                key: "dlq_last_error",
+                value: Some(last_error.as_str()),
+            });
+        }
+        if let Some(first_failed_at) = letter.first_failed_at {
+            headers = headers.insert(Header {
+                key: "dlq_first_failed_at",
+                value: Some(&first_failed_at.to_string()),
+            });
+        }
+
+        self.send(&letter.event, headers).await
+    }
+}
@@ -0,0 +1,363 @@
+//! In-memory [`DlqHeap`] implementation.
+//!
+//! Like the Postgres heap, failure counters live on the outbox row itself, so
+//! a counter disappears together with its event and there is nothing to
+//! reconcile:
+//! - `record_failure` → increments the row's counter, overwrites its last
+//!   error and sets its first-failure time if unset
+//! - `record_success` → resets the counter, error, timestamp and claim
+//! - `drain_exceeded` → up to the policy's batch size of unclaimed rows the
+//!   policy considers exceeded are claimed and returned under the same lock,
+//!   so the same id is never returned twice; their counters stay as they are
+//! - `ack_drained` → resets the counter, error, timestamp and claim of rows
+//!   that are still in the outbox
+//! - `reclaim_stale` → releases claims older than the timeout, so the next
+//!   drain returns those rows again
+//!
+//! Failures recorded for an id that is not in the outbox are ignored.
+
+use crate::{InMemoryOutbox, Row};
+use async_trait::async_trait;
+use outbox_core::prelude::{DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError};
+use serde::Serialize;
+use std::fmt::Debug;
+use std::time::Duration;
+
+#[async_trait]
+impl<P> DlqHeap for InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn record_failure(
+        &self,
+        id: EventId,
+        _event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
+        let now = self.now();
+        if let Some(row) = self.state().events.get_mut(&id) {
+            row.failure_count += 1;
+            row.last_error = Some(error.to_string());
+            row.first_failed_at.get_or_insert(now);
+        }
+        Ok(())
+    }
+
+    async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
+        if let Some(row) = self.state().events.get_mut(&id) {
+            reset(row);
+        }
+        Ok(())
+    }
+
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
+        let now = self.now();
+        Ok(self
+            .state()
+            .events
+            .values_mut()
+            .filter(|row| {
+                row.dlq_claimed_at.is_none()
+                    && policy.is_exceeded(
+                        row.failure_count,
+                        Some(row.event.event_type.as_str()),
+                        row.first_failed_at,
+                        now,
+                    )
+            })
+            .take(policy.batch_size as usize)
+            .map(|row| {
+                row.dlq_claimed_at = Some(now);
+                DlqEntry::new(row.event.id, row.failure_count, row.last_error.clone())
+                    .with_first_failed_at(row.first_failed_at)
+                    .with_event_type(row.event.event_type.clone())
+            })
+            .collect())
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        let mut state = self.state();
+        for id in ids {
+            if let Some(row) = state.events.get_mut(id) {
+                reset(row);
+            }
+        }
+        Ok(())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let Some(cutoff) = time::Duration::try_from(timeout)
+            .ok()
+            .and_then(|timeout| self.now().checked_sub(timeout))
+        else {
+            return Ok(0);
+        };
+        let mut reclaimed = 0;
+        for row in self.state().events.values_mut() {
+            if row.dlq_claimed_at.is_some_and(|at| at <= cutoff) {
+                row.dlq_claimed_at = None;
+                reclaimed += 1;
+            }
+        }
+        Ok(reclaimed)
+    }
+}
+
+fn reset<P>(row: &mut Row<P>) {
+    row.failure_count = 0;
+    row.last_error = None;
+    row.first_failed_at = None;
+    row.dlq_claimed_at = None;
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::InMemoryOutbox;
+    use crate::tests::{TestPayload, event, outbox};
+    use async_trait::async_trait;
+    use outbox_core::prelude::*;
+    use rstest::rstest;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    fn broker_error() -> OutboxError {
+        OutboxError::BrokerError("nope".into())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn drain_returns_events_over_threshold_once_then_quarantine_moves_them() {
+        let outbox = outbox();
+        let failing = event(None);
+        let healthy = event(None);
+        let (failing_id, event_type) = (failing.id, failing.event_type.clone());
+        outbox.insert_events(vec![failing, healthy]).await.unwrap();
+        for _ in 0..3 {
+            outbox
+                .record_failure(failing_id, &event_type, &broker_error())
+                .await
+                .unwrap();
+        }
+
+        let entries = outbox.drain_exceeded(&DlqPolicy::new(3)).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, failing_id);
+        assert_eq!(entries[0].failure_count, 3);
+        assert_eq!(entries[0].last_error.as_deref(), Some("Broker error: nope"));
+        assert!(entries[0].first_failed_at.is_some());
+        assert!(
+            outbox
+                .drain_exceeded(&DlqPolicy::new(3))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let moved = outbox.quarantine_events(&entries).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(outbox.len(), 1);
+        let letter = outbox.get(failing_id).await.unwrap().unwrap();
+        assert_eq!(letter.failure_count, 3);
+        assert_eq!(letter.event.status, EventStatus::Pending);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn unacked_claim_is_drained_again_once_reclaimed() {
+        let outbox = outbox();
+        let e = event(None);
+        let (id, event_type) = (e.id, e.event_type.clone());
+        outbox.insert_event(e).await.unwrap();
+        for _ in 0..2 {
+            outbox
+                .record_failure(id, &event_type, &broker_error())
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(2);
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 1);
+
+        assert_eq!(
+            outbox.reclaim_stale(Duration::from_mins(1)).await.unwrap(),
+            0
+        );
+        assert_eq!(outbox.reclaim_stale(Duration::MAX).await.unwrap(), 0);
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 1);
+        let entries = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].failure_count, 2);
+        assert_eq!(entries[0].last_error.as_deref(), Some("Broker error: nope"));
+
+        outbox.ack_drained(&[id]).await.unwrap();
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 0);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn drain_stops_at_batch_size_and_leaves_the_rest_for_the_next_pass() {
+        let outbox = outbox();
+        let events = vec![event(None), event(None), event(None)];
+        let failing: Vec<_> = events
+            .iter()
+            .map(|e| (e.id, e.event_type.clone()))
+            .collect();
+        outbox.insert_events(events).await.unwrap();
+        for (id, event_type) in &failing {
+            outbox
+                .record_failure(*id, event_type, &broker_error())
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(1).with_batch_size(2);
+
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 2);
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 1);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+    }
+
+    /// Fails every publish, and the first dead letter it is handed.
+    #[derive(Default)]
+    struct FlakyDestination {
+        dead_letters: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Transport<TestPayload> for FlakyDestination {
+        async fn publish(&self, _event: Event<TestPayload>) -> Result<(), OutboxError> {
+            Err(broker_error())
+        }
+
+        async fn publish_dead_letter(
+            &self,
+            _letter: DeadLetter<TestPayload>,
+        ) -> Result<(), OutboxError> {
+            if self.dead_letters.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(broker_error());
+            }
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn reaper_retries_a_failed_quarantine() {
+        let config = Arc::new(
+            OutboxConfig::default()
+                .with_dlq_threshold(1)
+                .with_dlq_interval_secs(1)
+                .with_dlq_claim_timeout_secs(0),
+        );
+        let outbox = Arc::new(InMemoryOutbox::new(config.clone()));
+        outbox.insert_event(event(None)).await.unwrap();
+        let transport = Arc::new(FlakyDestination::default());
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let manager = OutboxManagerBuilder::new()
+            .storage(outbox.clone())
+            .publisher(transport.clone())
+            .config(config)
+            .shutdown_rx(shutdown_rx)
+            .dlq_heap(outbox.clone())
+            .dlq_destination(transport.clone())
+            .build()
+            .unwrap();
+        let handle = tokio::spawn(manager.run());
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !outbox.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        shutdown_tx.send(true).unwrap();
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(transport.dead_letters.load(Ordering::SeqCst), 2);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn record_success_resets_counter() {
+        let outbox = outbox();
+        let e = event(None);
+        let (id, event_type) = (e.id, e.event_type.clone());
+        outbox.insert_event(e).await.unwrap();
+        outbox
+            .record_failure(id, &event_type, &broker_error())
+            .await
+            .unwrap();
+
+        outbox.record_success(id).await.unwrap();
+
+        assert!(
+            outbox
+                .drain_exceeded(&DlqPolicy::new(1))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn revive_moves_cooled_down_dead_letters_back_up_to_the_limit() {
+        let outbox = outbox();
+        let e = event(None);
+        let (id, event_type) = (e.id, e.event_type.clone());
+        outbox.insert_event(e).await.unwrap();
+
+        for round in 1..=2 {
+            outbox
+                .record_failure(id, &event_type, &broker_error())
+                .await
+                .unwrap();
+            let entries = outbox.drain_exceeded(&DlqPolicy::new(1)).await.unwrap();
+            outbox.quarantine_events(&entries).await.unwrap();
+            assert!(outbox.is_empty());
+
+            let revived = outbox.revive_dead_letters(Duration::ZERO, 1).await.unwrap();
+            if round == 1 {
+                assert_eq!(revived, vec![id]);
+                assert_eq!(outbox.count_with_status(&EventStatus::Pending), 1);
+            } else {
+                assert!(revived.is_empty());
+                assert_eq!(outbox.get(id).await.unwrap().unwrap().revival_count, 1);
+            }
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let outbox = outbox();
+        let (taken, free) = (event(Some("t-1")), event(None));
+        let ids = [taken.id, free.id];
+        outbox.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        outbox.insert_event(event(Some("t-1"))).await.unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert_eq!(revived, vec![ids[1]]);
+        assert!(outbox.get(ids[0]).await.unwrap().is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn missing_events_reports_ids_not_in_outbox() {
+        let outbox = outbox();
+        let e = event(None);
+        let live = e.id;
+        outbox.insert_event(e).await.unwrap();
+        let gone = EventId::default();
+
+        let missing = outbox.missing_events(&[live, gone]).await.unwrap();
+
+        assert_eq!(missing, vec![gone]);
+    }
+}
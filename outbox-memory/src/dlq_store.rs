@@ -0,0 +1,238 @@
+//! In-memory [`DlqStore`] implementation.
+//!
+//! Dead letters live in a map next to the outbox rows, behind the same lock,
+//! so a requeue is atomic: a dead letter is moved back only if every one of
+//! the selected events can be inserted, otherwise nothing moves.
+
+use crate::{InMemoryOutbox, Row, State};
+use async_trait::async_trait;
+use outbox_core::prelude::{
+    DeadLetter, DlqFilter, DlqPage, DlqStore, Event, EventId, EventStatus, EventType, OutboxError,
+    Payload,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use time::OffsetDateTime;
+use tracing::debug;
+
+#[async_trait]
+impl<P> DlqStore<P> for InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn list(
+        &self,
+        filter: &DlqFilter,
+        page: DlqPage,
+    ) -> Result<Vec<DeadLetter<P>>, OutboxError> {
+        let state = self.state();
+        let mut letters: Vec<&DeadLetter<P>> = state
+            .dead_letters
+            .values()
+            .filter(|letter| matches(filter, letter))
+            .collect();
+        letters.sort_by(|a, b| {
+            b.quarantined_at
+                .cmp(&a.quarantined_at)
+                .then_with(|| a.event.id.as_uuid().cmp(&b.event.id.as_uuid()))
+        });
+        Ok(letters
+            .into_iter()
+            .skip(usize::try_from(page.offset).unwrap_or(usize::MAX))
+            .take(usize::try_from(page.limit).unwrap_or(usize::MAX))
+            .cloned()
+            .collect())
+    }
+
+    async fn get(&self, id: EventId) -> Result<Option<DeadLetter<P>>, OutboxError> {
+        Ok(self.state().dead_letters.get(&id).cloned())
+    }
+
+    async fn count_by_event_type(&self) -> Result<Vec<(EventType, u64)>, OutboxError> {
+        let mut counts: HashMap<&str, u64> = HashMap::new();
+        let state = self.state();
+        for letter in state.dead_letters.values() {
+            *counts.entry(letter.event.event_type.as_str()).or_default() += 1;
+        }
+        let mut counts: Vec<(&str, u64)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        Ok(counts
+            .into_iter()
+            .map(|(event_type, count)| (EventType::load(event_type), count))
+            .collect())
+    }
+
+    async fn delete(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let mut state = self.state();
+        let deleted = ids
+            .iter()
+            .filter(|id| state.dead_letters.remove(id).is_some())
+            .count();
+        debug!("DLQ: deleted {deleted} dead letters");
+        Ok(deleted as u64)
+    }
+
+    async fn requeue(&self, filter: &DlqFilter) -> Result<Vec<EventId>, OutboxError> {
+        let requeued = {
+            let mut state = self.state();
+            let ids: Vec<EventId> = state
+                .dead_letters
+                .values()
+                .filter(|letter| matches(filter, letter))
+                .filter(|letter| {
+                    !letter
+                        .event
+                        .idempotency_token
+                        .as_ref()
+                        .is_some_and(|token| state.holds_token(token))
+                })
+                .map(|letter| letter.event.id)
+                .collect();
+            requeue_ids(&mut state, &ids, |letter| pending_row(letter.event))?
+        };
+        if !requeued.is_empty() {
+            self.wake();
+        }
+        debug!("DLQ: requeued {} dead letters", requeued.len());
+        Ok(requeued)
+    }
+
+    async fn requeue_with_payload(&self, id: EventId, payload: P) -> Result<bool, OutboxError> {
+        let requeued = requeue_ids(&mut self.state(), &[id], |letter| {
+            let mut event = letter.event;
+            event.payload = Payload::new(payload.clone());
+            pending_row(event)
+        })?;
+        if requeued.is_empty() {
+            return Ok(false);
+        }
+        self.wake();
+        Ok(true)
+    }
+}
+
+fn matches<P>(filter: &DlqFilter, letter: &DeadLetter<P>) -> bool {
+    filter
+        .ids
+        .as_ref()
+        .is_none_or(|ids| ids.contains(&letter.event.id))
+        && filter
+            .event_type
+            .as_deref()
+            .is_none_or(|t| letter.event.event_type.as_str() == t)
+        && filter
+            .quarantined_after
+            .is_none_or(|after| letter.quarantined_at >= after)
+        && filter
+            .quarantined_before
+            .is_none_or(|before| letter.quarantined_at < before)
+}
+
+/// Turns a dead-lettered event back into a fresh `Pending` row that sorts
+/// ahead of every other pending event.
+pub(crate) fn pending_row<P>(mut event: Event<P>) -> Row<P> {
+    event.status = EventStatus::Pending;
+    event.locked_until = OffsetDateTime::UNIX_EPOCH;
+    Row::new(event)
+}
+
+/// Moves the dead letters with `ids` back into the outbox, building each row
+/// with `to_row`. Either every dead letter is moved or, if one of them would
+/// collide with an existing row or idempotency token, none is.
+pub(crate) fn requeue_ids<P, F>(
+    state: &mut State<P>,
+    ids: &[EventId],
+    to_row: F,
+) -> Result<Vec<EventId>, OutboxError>
+where
+    P: Clone,
+    F: Fn(DeadLetter<P>) -> Row<P>,
+{
+    let rows: Vec<Row<P>> = ids
+        .iter()
+        .filter_map(|id| state.dead_letters.get(id).cloned().map(&to_row))
+        .collect();
+    let requeued: Vec<EventId> = rows.iter().map(|row| row.event.id).collect();
+    state.insert_rows(rows)?;
+    for id in &requeued {
+        state.dead_letters.remove(id);
+    }
+    Ok(requeued)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::tests::{TestPayload, event, outbox};
+    use outbox_core::prelude::*;
+    use rstest::rstest;
+
+    async fn quarantined(
+        outbox: &crate::InMemoryOutbox<TestPayload>,
+        event: Event<TestPayload>,
+    ) -> EventId {
+        let id = event.id;
+        outbox.insert_event(event).await.unwrap();
+        outbox
+            .quarantine_events(&[DlqEntry::new(id, 5, Some("boom".into()))])
+            .await
+            .unwrap();
+        id
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn requeue_moves_matching_dead_letters_back_as_pending() {
+        let outbox = outbox();
+        let id = quarantined(&outbox, event(None)).await;
+        let counts = outbox.count_by_event_type().await.unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!((counts[0].0.as_str(), counts[0].1), ("OrderCreated", 1));
+
+        let requeued = outbox
+            .requeue(&DlqFilter::all().with_event_type("OrderCreated"))
+            .await
+            .unwrap();
+
+        assert_eq!(requeued, vec![id]);
+        assert!(outbox.get(id).await.unwrap().is_none());
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap()[0].id, id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn requeue_skips_dead_letters_whose_token_is_taken_again() {
+        let outbox = outbox();
+        let taken = quarantined(&outbox, event(Some("t-1"))).await;
+        let free = quarantined(&outbox, event(None)).await;
+        outbox.insert_event(event(Some("t-1"))).await.unwrap();
+
+        let requeued = outbox.requeue(&DlqFilter::all()).await.unwrap();
+
+        assert_eq!(requeued, vec![free]);
+        assert!(outbox.get(taken).await.unwrap().is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn requeue_with_payload_replaces_payload() {
+        let outbox = outbox();
+        let id = quarantined(&outbox, event(None)).await;
+
+        assert!(
+            outbox
+                .requeue_with_payload(id, TestPayload(7))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !outbox
+                .requeue_with_payload(id, TestPayload(8))
+                .await
+                .unwrap()
+        );
+
+        assert_eq!(*outbox.events()[0].payload.as_value(), TestPayload(7));
+    }
+}
@@ -0,0 +1,96 @@
+//! In-memory [`IdempotencyStorageProvider`].
+//!
+//! Tokens are reserved in a map with an expiry, like the
+//! `outbox_idempotency_tokens` table of the Postgres backend. A reservation
+//! succeeds if the token is unknown or its previous reservation expired;
+//! expired entries are dropped by
+//! [`delete_garbage`](outbox_core::prelude::OutboxStorage::delete_garbage).
+
+use crate::InMemoryOutbox;
+use async_trait::async_trait;
+use outbox_core::prelude::{IdempotencyStorageProvider, IdempotencyToken, OutboxError};
+use serde::Serialize;
+use std::fmt::Debug;
+
+#[async_trait]
+impl<P> IdempotencyStorageProvider for InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn try_reserve(&self, token: &IdempotencyToken) -> Result<bool, OutboxError> {
+        let now = self.now();
+        let mut state = self.state();
+        let expires_at = now + state.token_ttl;
+
+        match state.tokens.get_mut(token.as_str()) {
+            Some(current) if *current > now => Ok(false),
+            Some(current) => {
+                *current = expires_at;
+                Ok(true)
+            }
+            None => {
+                state.tokens.insert(token.as_str().to_owned(), expires_at);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn release(&self, token: &IdempotencyToken) -> Result<(), OutboxError> {
+        self.state().tokens.remove(token.as_str());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::InMemoryOutbox;
+    use crate::tests::outbox;
+    use outbox_core::prelude::{
+        IdempotencyStorageProvider, IdempotencyToken, ManualClock, OutboxConfig, OutboxStorage,
+    };
+    use rstest::rstest;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[rstest]
+    #[tokio::test]
+    async fn try_reserve_rejects_token_until_it_expires() {
+        let clock = ManualClock::default();
+        let outbox = InMemoryOutbox::<crate::tests::TestPayload>::new(Arc::new(
+            OutboxConfig::default().with_clock(Arc::new(clock.clone())),
+        ))
+        .with_token_ttl(Duration::from_mins(1));
+        let token = IdempotencyToken::new("order-1".into());
+
+        assert!(outbox.try_reserve(&token).await.unwrap());
+        assert!(!outbox.try_reserve(&token).await.unwrap());
+
+        clock.advance(Duration::from_mins(1));
+        assert!(outbox.try_reserve(&token).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn released_token_can_be_reserved_again() {
+        let outbox = outbox();
+        let token = IdempotencyToken::new("order-1".into());
+        assert!(outbox.try_reserve(&token).await.unwrap());
+
+        outbox.release(&token).await.unwrap();
+
+        assert!(outbox.try_reserve(&token).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_garbage_drops_expired_reservations() {
+        let outbox = outbox().with_token_ttl(Duration::ZERO);
+        let token = IdempotencyToken::new("order-1".into());
+        outbox.try_reserve(&token).await.unwrap();
+
+        outbox.delete_garbage().await.unwrap();
+
+        assert!(outbox.state().tokens.is_empty());
+    }
+}
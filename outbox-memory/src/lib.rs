@@ -0,0 +1,633 @@
+//! In-memory storage backend for `outbox-core`.
+//!
+//! [`InMemoryOutbox`] keeps the outbox table, the idempotency reservations
+//! and — with the `dlq` feature — the failure counters and the dead-letter
+//! table in process memory behind a single mutex. It follows the same
+//! semantics as the Postgres backend, so code tested against it behaves the
+//! same way in production:
+//!
+//! - `fetch_next_to_process` claims `Pending` rows and `Processing` rows whose
+//!   lock expired, oldest lock first, and locks them for `lock_timeout_mins`.
+//! - `delete_garbage` removes `Sent` rows older than `retention_days` and
+//!   expired idempotency reservations.
+//! - inserting an event whose idempotency token is already held by another
+//!   row fails with [`OutboxError::DuplicateEvent`]; batch inserts are
+//!   all-or-nothing.
+//! - `wait_for_notification` is woken by every insert, like a `NOTIFY` from
+//!   the Postgres trigger.
+//!
+//! Nothing is persisted: dropping the last clone of an [`InMemoryOutbox`]
+//! drops every event. Clones share the same state.
+
+#[cfg(feature = "dlq")]
+mod dlq;
+#[cfg(feature = "dlq")]
+mod dlq_store;
+mod idempotency;
+
+use async_trait::async_trait;
+use outbox_core::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use tracing::debug;
+
+#[derive(Clone)]
+pub struct InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    inner: Arc<InMemoryOutboxInner<P>>,
+}
+
+impl<P> InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new(config: Arc<OutboxConfig<P>>) -> Self {
+        Self {
+            inner: Arc::new(InMemoryOutboxInner {
+                config,
+                state: Mutex::new(State::default()),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Sets how long a token reserved through
+    /// [`IdempotencyStorageProvider::try_reserve`] blocks duplicates.
+    /// Defaults to 24 hours.
+    #[must_use]
+    pub fn with_token_ttl(self, ttl: Duration) -> Self {
+        self.state().token_ttl = ttl;
+        self
+    }
+
+    /// Returns a snapshot of every event currently in the outbox, oldest
+    /// first.
+    pub fn events(&self) -> Vec<Event<P>> {
+        let mut events: Vec<Event<P>> = self
+            .state()
+            .events
+            .values()
+            .map(|row| row.event.clone())
+            .collect();
+        events.sort_by_key(|e| e.created_at);
+        events
+    }
+
+    /// Returns the number of events currently in the outbox.
+    pub fn len(&self) -> usize {
+        self.state().events.len()
+    }
+
+    /// Returns `true` if the outbox holds no events.
+    pub fn is_empty(&self) -> bool {
+        self.state().events.is_empty()
+    }
+
+    /// Returns the number of events with the given status.
+    pub fn count_with_status(&self, status: &EventStatus) -> usize {
+        self.state()
+            .events
+            .values()
+            .filter(|row| row.event.status == *status)
+            .count()
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        self.inner.config.clock.now()
+    }
+
+    /// Wakes every worker waiting in `wait_for_notification`, like a
+    /// Postgres `NOTIFY`, and leaves a permit for the next one that starts
+    /// waiting.
+    fn wake(&self) {
+        self.inner.notify.notify_waiters();
+        self.inner.notify.notify_one();
+    }
+
+    fn state(&self) -> MutexGuard<'_, State<P>> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+struct InMemoryOutboxInner<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    config: Arc<OutboxConfig<P>>,
+    state: Mutex<State<P>>,
+    notify: Notify,
+}
+
+/// One outbox row: the event plus the columns the DLQ migrations add to
+/// `outbox_events` in Postgres.
+struct Row<P> {
+    event: Event<P>,
+    #[cfg(feature = "dlq")]
+    failure_count: u32,
+    #[cfg(feature = "dlq")]
+    last_error: Option<String>,
+    #[cfg(feature = "dlq")]
+    first_failed_at: Option<OffsetDateTime>,
+    #[cfg(feature = "dlq")]
+    revival_count: u32,
+    #[cfg(feature = "dlq")]
+    dlq_claimed_at: Option<OffsetDateTime>,
+}
+
+impl<P> Row<P> {
+    fn new(event: Event<P>) -> Self {
+        Self {
+            event,
+            #[cfg(feature = "dlq")]
+            failure_count: 0,
+            #[cfg(feature = "dlq")]
+            last_error: None,
+            #[cfg(feature = "dlq")]
+            first_failed_at: None,
+            #[cfg(feature = "dlq")]
+            revival_count: 0,
+            #[cfg(feature = "dlq")]
+            dlq_claimed_at: None,
+        }
+    }
+}
+
+struct State<P> {
+    events: HashMap<EventId, Row<P>>,
+    /// Idempotency tokens of the rows in `events`.
+    event_tokens: HashSet<String>,
+    tokens: HashMap<String, OffsetDateTime>,
+    token_ttl: Duration,
+    #[cfg(feature = "dlq")]
+    dead_letters: HashMap<EventId, DeadLetter<P>>,
+}
+
+impl<P> Default for State<P> {
+    fn default() -> Self {
+        Self {
+            events: HashMap::new(),
+            event_tokens: HashSet::new(),
+            tokens: HashMap::new(),
+            token_ttl: Duration::from_hours(24),
+            #[cfg(feature = "dlq")]
+            dead_letters: HashMap::new(),
+        }
+    }
+}
+
+impl<P> State<P> {
+    /// Whether a row in the outbox carries `token`.
+    #[cfg(feature = "dlq")]
+    fn holds_token(&self, token: &IdempotencyToken) -> bool {
+        self.event_tokens.contains(token.as_str())
+    }
+
+    /// Inserts `rows`, or none of them if any id or idempotency token is
+    /// already taken (mirrors the primary key and the
+    /// `idx_outbox_idempotency` unique index).
+    fn insert_rows(&mut self, rows: Vec<Row<P>>) -> Result<(), OutboxError> {
+        let mut batch_tokens = HashSet::new();
+        for row in &rows {
+            if self.events.contains_key(&row.event.id) {
+                return Err(OutboxError::DatabaseError(format!(
+                    "event {:?} already exists",
+                    row.event.id
+                )));
+            }
+            if let Some(token) = &row.event.idempotency_token
+                && (self.event_tokens.contains(token.as_str())
+                    || !batch_tokens.insert(token.as_str()))
+            {
+                return Err(OutboxError::DuplicateEvent);
+            }
+        }
+        for row in rows {
+            if let Some(token) = &row.event.idempotency_token {
+                self.event_tokens.insert(token.as_str().to_owned());
+            }
+            self.events.insert(row.event.id, row);
+        }
+        Ok(())
+    }
+
+    /// Removes the row with `id`, releasing its idempotency token.
+    #[cfg(feature = "dlq")]
+    fn remove_row(&mut self, id: &EventId) -> Option<Row<P>> {
+        let row = self.events.remove(id)?;
+        if let Some(token) = &row.event.idempotency_token {
+            self.event_tokens.remove(token.as_str());
+        }
+        Some(row)
+    }
+}
+
+#[async_trait]
+impl<P> OutboxStorage<P> for InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn fetch_next_to_process(&self, limit: u32) -> Result<Vec<Event<P>>, OutboxError> {
+        // Nothing here ever waits, so without this a worker retrying an
+        // event that keeps failing would never give the runtime back.
+        tokio::task::yield_now().await;
+        let now = self.now();
+        let locked_until = now + time::Duration::minutes(self.inner.config.lock_timeout_mins);
+        let mut state = self.state();
+
+        let mut eligible: Vec<&mut Row<P>> = state
+            .events
+            .values_mut()
+            .filter(|row| match row.event.status {
+                EventStatus::Pending => true,
+                EventStatus::Processing => row.event.locked_until < now,
+                EventStatus::Sent => false,
+            })
+            .collect();
+        eligible.sort_by_key(|row| (row.event.locked_until, row.event.created_at));
+
+        Ok(eligible
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(|row| {
+                row.event.status = EventStatus::Processing;
+                row.event.locked_until = locked_until;
+                row.event.clone()
+            })
+            .collect())
+    }
+
+    async fn update_status(&self, ids: &[EventId], status: EventStatus) -> Result<(), OutboxError> {
+        let mut state = self.state();
+        for id in ids {
+            if let Some(row) = state.events.get_mut(id) {
+                row.event.status = status.clone();
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let now = self.now();
+        let cutoff = now - time::Duration::days(self.inner.config.retention_days);
+        let mut state = self.state();
+
+        let before = state.events.len();
+        let State {
+            events,
+            event_tokens,
+            ..
+        } = &mut *state;
+        events.retain(|_, row| {
+            let keep = row.event.status != EventStatus::Sent || row.event.created_at >= cutoff;
+            if let (false, Some(token)) = (keep, &row.event.idempotency_token) {
+                event_tokens.remove(token.as_str());
+            }
+            keep
+        });
+        debug!(
+            "Garbage collector: deleted {} old messages",
+            before - state.events.len()
+        );
+
+        let before = state.tokens.len();
+        state.tokens.retain(|_, expires_at| *expires_at > now);
+        debug!(
+            "Garbage collector: deleted {} expired idempotency tokens",
+            before - state.tokens.len()
+        );
+        Ok(())
+    }
+
+    /// Waits until an event is inserted. `channel` is ignored — there is a
+    /// single in-process channel. Every waiting worker is woken, and an
+    /// insert that happens while nobody is waiting is remembered, so the
+    /// next call returns immediately.
+    async fn wait_for_notification(&self, _channel: &str) -> Result<(), OutboxError> {
+        self.inner.notify.notified().await;
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        let state = self.state();
+        Ok(ids
+            .iter()
+            .filter_map(|id| state.events.get(id).map(|row| row.event.clone()))
+            .collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let mut state = self.state();
+        let deleted = ids
+            .iter()
+            .filter(|id| state.remove_row(id).is_some())
+            .count();
+        debug!("DLQ reaper: deleted {deleted} events published to the DLQ destination");
+        Ok(deleted as u64)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
+        let now = self.now();
+        let mut state = self.state();
+
+        let mut moved = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let Some(row) = state.remove_row(&entry.id) else {
+                continue;
+            };
+            let event_type = row.event.event_type.clone();
+            let letter = DeadLetter::new(
+                row.event,
+                entry.failure_count,
+                entry.last_error.clone(),
+                now,
+            )
+            .with_first_failed_at(entry.first_failed_at)
+            .with_revival_count(row.revival_count);
+            state.dead_letters.insert(entry.id, letter);
+            moved.push(entry.clone().with_event_type(event_type));
+        }
+        debug!(
+            "DLQ reaper: quarantined {}/{} events",
+            moved.len(),
+            entries.len()
+        );
+        Ok(moved)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let cutoff = self.now() - time::Duration::days(self.inner.config.dlq_retention_days);
+        let mut state = self.state();
+
+        let before = state.dead_letters.len();
+        state
+            .dead_letters
+            .retain(|_, letter| letter.quarantined_at >= cutoff);
+        debug!(
+            "Garbage collector: purged {} expired dead letters",
+            before - state.dead_letters.len()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let cutoff = self.now() - cooldown;
+        let revived = {
+            let mut state = self.state();
+            let ids: Vec<EventId> = state
+                .dead_letters
+                .values()
+                .filter(|l| l.quarantined_at <= cutoff && l.revival_count < max_revivals)
+                .filter(|l| {
+                    !l.event
+                        .idempotency_token
+                        .as_ref()
+                        .is_some_and(|token| state.holds_token(token))
+                })
+                .map(|l| l.event.id)
+                .collect();
+            dlq_store::requeue_ids(&mut state, &ids, |letter| {
+                let mut row = dlq_store::pending_row(letter.event);
+                row.revival_count = letter.revival_count + 1;
+                row
+            })?
+        };
+        if !revived.is_empty() {
+            self.wake();
+        }
+        debug!("DLQ reaper: revived {} dead letters", revived.len());
+        Ok(revived)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        let state = self.state();
+        Ok(ids
+            .iter()
+            .filter(|id| !state.events.contains_key(id))
+            .copied()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<P> OutboxWriter<P> for InMemoryOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        self.insert_events(vec![event]).await
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.state()
+            .insert_rows(events.into_iter().map(Row::new).collect())?;
+        self.wake();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use outbox_core::prelude::ManualClock;
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    pub(crate) struct TestPayload(pub(crate) u32);
+
+    pub(crate) fn outbox() -> InMemoryOutbox<TestPayload> {
+        InMemoryOutbox::new(Arc::new(OutboxConfig::default()))
+    }
+
+    pub(crate) fn event(token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_claims_pending_events_and_locks_them() {
+        let outbox = outbox();
+        outbox
+            .insert_events(vec![event(None), event(None), event(None)])
+            .await
+            .unwrap();
+
+        let claimed = outbox.fetch_next_to_process(2).await.unwrap();
+
+        assert_eq!(claimed.len(), 2);
+        assert!(claimed.iter().all(|e| e.status == EventStatus::Processing));
+        assert!(
+            claimed
+                .iter()
+                .all(|e| e.locked_until > OffsetDateTime::now_utc())
+        );
+        assert_eq!(outbox.count_with_status(&EventStatus::Processing), 2);
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_reclaims_processing_events_whose_lock_expired() {
+        let outbox = outbox();
+        let mut stale = event(None);
+        stale.status = EventStatus::Processing;
+        stale.locked_until = OffsetDateTime::now_utc() - time::Duration::seconds(1);
+        let id = stale.id;
+        outbox.insert_event(stale).await.unwrap();
+
+        let claimed = outbox.fetch_next_to_process(10).await.unwrap();
+
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].id, id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn locks_expire_on_the_configured_clock() {
+        let clock = ManualClock::default();
+        let outbox = InMemoryOutbox::new(Arc::new(
+            OutboxConfig::default().with_clock(Arc::new(clock.clone())),
+        ));
+        outbox.insert_event(event(None)).await.unwrap();
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+
+        clock.advance(Duration::from_mins(4));
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_mins(2));
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_status_marks_events_sent() {
+        let outbox = outbox();
+        let e = event(None);
+        let id = e.id;
+        outbox.insert_event(e).await.unwrap();
+
+        outbox
+            .update_status(&[id], EventStatus::Sent)
+            .await
+            .unwrap();
+
+        assert_eq!(outbox.count_with_status(&EventStatus::Sent), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn insert_rejects_duplicate_idempotency_token() {
+        let outbox = outbox();
+        outbox.insert_event(event(Some("t-1"))).await.unwrap();
+
+        let err = outbox.insert_event(event(Some("t-1"))).await.unwrap_err();
+
+        assert!(matches!(err, OutboxError::DuplicateEvent));
+        assert_eq!(outbox.len(), 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn insert_events_is_all_or_nothing() {
+        let outbox = outbox();
+
+        let err = outbox
+            .insert_events(vec![event(Some("a")), event(None), event(Some("a"))])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, OutboxError::DuplicateEvent));
+        assert!(outbox.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_garbage_removes_only_old_sent_events() {
+        let outbox = outbox();
+        let mut old_sent = event(Some("token-1"));
+        old_sent.status = EventStatus::Sent;
+        old_sent.created_at = OffsetDateTime::now_utc() - time::Duration::days(8);
+        let mut old_pending = event(None);
+        old_pending.created_at = old_sent.created_at;
+        let mut fresh_sent = event(None);
+        fresh_sent.status = EventStatus::Sent;
+        outbox
+            .insert_events(vec![old_sent, old_pending, fresh_sent])
+            .await
+            .unwrap();
+
+        outbox.delete_garbage().await.unwrap();
+
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.count_with_status(&EventStatus::Sent), 1);
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn insert_wakes_every_waiting_worker() {
+        let outbox = outbox();
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let waiter = outbox.clone();
+                tokio::spawn(async move { waiter.wait_for_notification("outbox").await })
+            })
+            .collect();
+        tokio::task::yield_now().await;
+
+        outbox.insert_event(event(None)).await.unwrap();
+
+        for handle in handles {
+            tokio::time::timeout(Duration::from_secs(1), handle)
+                .await
+                .expect("insert did not wake every waiter")
+                .unwrap()
+                .unwrap();
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn insert_without_waiter_is_remembered() {
+        let outbox = outbox();
+        outbox.insert_event(event(None)).await.unwrap();
+
+        tokio::time::timeout(
+            Duration::from_millis(100),
+            outbox.wait_for_notification("outbox"),
+        )
+        .await
+        .expect("pending notification was lost")
+        .unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+//! MongoDB-backed [`DlqHeap`] implementation and the moves between the
+//! outbox and the dead-letter collection.
+//!
+//! Failure counters live on the outbox document itself, in `failure_count`,
+//! `last_error` and `first_failed_at`, just as in the Postgres backend:
+//! - `record_failure` → an update pipeline that increments `failure_count`,
+//!   overwrites `last_error` and sets `first_failed_at` if unset
+//! - `record_success` → reset the counter, error, timestamp and claim
+//! - `drain_exceeded` → repeated `findOneAndUpdate` calls that set
+//!   `dlq_claimed_at` on one unclaimed, exceeded document at a time, until
+//!   none is left or the policy's batch size is reached. Per-type
+//!   thresholds become a `$switch` on `event_type`. Each document is claimed
+//!   atomically, so the same id is not returned to two concurrent callers.
+//! - `ack_drained` → reset the counter, error, timestamp and claim of
+//!   documents still in the outbox
+//! - `reclaim_stale` → clear `dlq_claimed_at` on claims older than the
+//!   timeout, so the next drain returns those documents again
+//!
+//! Quarantine and revival copy whole documents between `outbox_events` and
+//! `outbox_dead_letters` inside the caller's transaction.
+
+use crate::document::{from_bson_date, id_list, to_bson_date};
+use crate::{MongoOutbox, MongoOutboxInner, map_insert_error};
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::ClientSession;
+use mongodb::bson::{Bson, DateTime, Document, doc};
+use outbox_core::prelude::{DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Revives at most this many dead letters per call, like the SQL backends.
+const REVIVE_BATCH: i64 = 5000;
+
+#[async_trait]
+impl<P> DlqHeap for MongoOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn record_failure(
+        &self,
+        id: EventId,
+        _event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
+        let now = to_bson_date(self.inner.config.clock.now());
+        self.inner
+            .events
+            .update_one(
+                doc! { "_id": id.to_string() },
+                vec![doc! {
+                    "$set": {
+                        "failure_count": { "$add": [{ "$ifNull": ["$failure_count", 0] }, 1] },
+                        "last_error": error.to_string(),
+                        "first_failed_at": { "$ifNull": ["$first_failed_at", now] },
+                    }
+                }],
+            )
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
+        self.inner
+            .events
+            .update_one(
+                doc! { "_id": id.to_string(), "failure_count": { "$gt": 0 } },
+                reset_failures(),
+            )
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
+        let threshold: Bson = if policy.type_thresholds.is_empty() {
+            i64::from(policy.threshold).into()
+        } else {
+            doc! {
+                "$switch": {
+                    "branches": policy
+                        .type_thresholds
+                        .iter()
+                        .map(|(event_type, threshold)| doc! {
+                            "case": { "$eq": ["$event_type", event_type] },
+                            "then": i64::from(*threshold),
+                        })
+                        .collect::<Vec<_>>(),
+                    "default": i64::from(policy.threshold),
+                }
+            }
+            .into()
+        };
+        let now = self.inner.config.clock.now();
+        let mut exceeded = vec![doc! { "$expr": { "$gte": ["$failure_count", threshold] } }];
+        if let Some(cutoff) = policy.failing_since_cutoff(now) {
+            exceeded.push(doc! { "first_failed_at": { "$lte": to_bson_date(cutoff) } });
+        }
+        let filter = doc! {
+            "failure_count": { "$gt": 0 },
+            "dlq_claimed_at": Bson::Null,
+            "$or": exceeded,
+        };
+        let claim = doc! { "$set": { "dlq_claimed_at": to_bson_date(now) } };
+
+        let raw = self.inner.events.clone_with_type::<Document>();
+        let mut drained = Vec::new();
+        while drained.len() < policy.batch_size as usize
+            && let Some(document) = raw
+                .find_one_and_update(filter.clone(), claim.clone())
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+        {
+            drained.push(failure_entry(&document)?.with_event_type(EventType::load(
+                document.get_str("event_type").unwrap_or(""),
+            )));
+        }
+        Ok(drained)
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.inner
+            .events
+            .update_many(doc! { "_id": { "$in": id_list(ids) } }, reset_failures())
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let Some(cutoff) = time::Duration::try_from(timeout)
+            .ok()
+            .and_then(|timeout| self.inner.config.clock.now().checked_sub(timeout))
+        else {
+            return Ok(0);
+        };
+        let cutoff = to_bson_date(cutoff);
+        let result = self
+            .inner
+            .events
+            .update_many(
+                doc! { "dlq_claimed_at": { "$lte": cutoff } },
+                doc! { "$set": { "dlq_claimed_at": Bson::Null } },
+            )
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(result.modified_count)
+    }
+}
+
+fn reset_failures() -> Document {
+    doc! {
+        "$set": {
+            "failure_count": 0,
+            "last_error": Bson::Null,
+            "first_failed_at": Bson::Null,
+            "dlq_claimed_at": Bson::Null,
+        }
+    }
+}
+
+/// Reads the failure bookkeeping of an outbox document into a [`DlqEntry`].
+fn failure_entry(document: &Document) -> Result<DlqEntry, OutboxError> {
+    let id = document
+        .get_str("_id")
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    let failure_count = match document.get("failure_count") {
+        Some(Bson::Int32(n)) => u32::try_from(*n).unwrap_or(0),
+        Some(Bson::Int64(n)) => u32::try_from(*n).unwrap_or(u32::MAX),
+        _ => 0,
+    };
+    Ok(DlqEntry::new(
+        id.parse()?,
+        failure_count,
+        document.get_str("last_error").ok().map(str::to_string),
+    )
+    .with_first_failed_at(
+        document
+            .get_datetime("first_failed_at")
+            .ok()
+            .map(|at| from_bson_date(*at)),
+    ))
+}
+
+/// Copies the outbox documents named by `entries` into the dead-letter
+/// collection, carrying the entries' failure data forward, and deletes them
+/// from the outbox. Entries without an outbox document are skipped.
+pub(crate) async fn move_to_dead_letters<P>(
+    inner: &MongoOutboxInner<P>,
+    session: &mut ClientSession,
+    entries: &[DlqEntry],
+    now: OffsetDateTime,
+) -> Result<Vec<DlqEntry>, OutboxError>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    let by_id: HashMap<String, &DlqEntry> = entries.iter().map(|e| (e.id.to_string(), e)).collect();
+    let raw = inner.events.clone_with_type::<Document>();
+    let documents: Vec<Document> = raw
+        .find(doc! { "_id": { "$in": id_list(entries.iter().map(|e| &e.id)) } })
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+        .stream(&mut *session)
+        .try_collect()
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+    let mut moved = Vec::new();
+    let mut dead_letters = Vec::new();
+    for mut document in documents {
+        let Some(entry) = document
+            .get_str("_id")
+            .ok()
+            .and_then(|id| by_id.get(id).copied())
+        else {
+            continue;
+        };
+        let event_type = document.get_str("event_type").unwrap_or("").to_string();
+        if let Some(status) = document.remove("status") {
+            document.insert("original_status", status);
+        }
+        document.insert("failure_count", i64::from(entry.failure_count));
+        document.insert("last_error", entry.last_error.clone());
+        document.insert("first_failed_at", entry.first_failed_at.map(to_bson_date));
+        if !document.contains_key("revival_count") {
+            document.insert("revival_count", 0_i64);
+        }
+        document.insert("quarantined_at", to_bson_date(now));
+        dead_letters.push(document);
+        moved.push(entry.clone().with_event_type(EventType::load(&event_type)));
+    }
+    if moved.is_empty() {
+        return Ok(moved);
+    }
+
+    inner
+        .dead_letters
+        .insert_many(dead_letters)
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    raw.delete_many(doc! { "_id": { "$in": id_list(moved.iter().map(|e| &e.id)) } })
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    Ok(moved)
+}
+
+/// Moves dead letters quarantined at or before `cutoff`, and revived fewer
+/// than `max_revivals` times, back into the outbox as `Pending` with their
+/// revival count incremented. Dead letters whose idempotency token is held
+/// by an outbox document are left where they are.
+pub(crate) async fn move_to_outbox<P>(
+    inner: &MongoOutboxInner<P>,
+    session: &mut ClientSession,
+    cutoff: OffsetDateTime,
+    max_revivals: u32,
+) -> Result<Vec<EventId>, OutboxError>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    let dead_letters: Vec<Document> = inner
+        .dead_letters
+        .find(doc! {
+            "quarantined_at": { "$lte": to_bson_date(cutoff) },
+            "revival_count": { "$lt": i64::from(max_revivals) },
+        })
+        .sort(doc! { "quarantined_at": 1 })
+        .limit(REVIVE_BATCH)
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+        .stream(&mut *session)
+        .try_collect()
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    if dead_letters.is_empty() {
+        return Ok(Vec::new());
+    }
+    let held = held_tokens(inner, session, &dead_letters).await?;
+
+    let mut ids = Vec::with_capacity(dead_letters.len());
+    let mut events = Vec::with_capacity(dead_letters.len());
+    for letter in dead_letters {
+        if letter
+            .get_str("idempotency_token")
+            .is_ok_and(|token| held.contains(token))
+        {
+            continue;
+        }
+        let id = letter
+            .get_str("_id")
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            .to_string();
+        let revival_count = match letter.get("revival_count") {
+            Some(Bson::Int32(n)) => i64::from(*n),
+            Some(Bson::Int64(n)) => *n,
+            _ => 0,
+        };
+        events.push(doc! {
+            "_id": id.clone(),
+            "idempotency_token": letter.get("idempotency_token").cloned().unwrap_or(Bson::Null),
+            "event_type": letter.get("event_type").cloned().unwrap_or(Bson::Null),
+            "payload": letter.get("payload").cloned().unwrap_or(Bson::Null),
+            "status": "Pending",
+            "created_at": letter.get("created_at").cloned().unwrap_or(Bson::Null),
+            "locked_until": DateTime::from_millis(0),
+            "revival_count": revival_count + 1,
+        });
+        ids.push(id);
+    }
+
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+    inner
+        .events
+        .clone_with_type::<Document>()
+        .insert_many(events)
+        .session(&mut *session)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+    inner
+        .dead_letters
+        .delete_many(doc! { "_id": { "$in": &ids } })
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    ids.iter().map(|id| id.parse()).collect()
+}
+
+/// Returns the idempotency tokens of `dead_letters` that an outbox document
+/// already carries, so reviving those letters would be a duplicate.
+async fn held_tokens<P>(
+    inner: &MongoOutboxInner<P>,
+    session: &mut ClientSession,
+    dead_letters: &[Document],
+) -> Result<HashSet<String>, OutboxError>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    let tokens: Vec<&str> = dead_letters
+        .iter()
+        .filter_map(|letter| letter.get_str("idempotency_token").ok())
+        .collect();
+    if tokens.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let holders: Vec<Document> = inner
+        .events
+        .clone_with_type::<Document>()
+        .find(doc! { "idempotency_token": { "$in": tokens } })
+        .projection(doc! { "idempotency_token": 1 })
+        .session(&mut *session)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+        .stream(&mut *session)
+        .try_collect()
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    Ok(holders
+        .iter()
+        .filter_map(|holder| holder.get_str("idempotency_token").ok())
+        .map(str::to_string)
+        .collect())
+}
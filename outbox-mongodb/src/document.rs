@@ -0,0 +1,131 @@
+//! Conversions between outbox types and the documents stored in MongoDB.
+//!
+//! Ids are stored in `_id` as hyphenated UUID strings and [`EventStatus`] by
+//! variant name. BSON dates have millisecond precision, so timestamps read
+//! back from the database are truncated to the millisecond.
+
+use mongodb::bson::{self, Bson, DateTime};
+use outbox_core::prelude::{Event, EventId, EventType, IdempotencyToken, OutboxError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use time::OffsetDateTime;
+
+/// An [`Event`] as stored in the `outbox_events` collection. DLQ bookkeeping
+/// fields, when present, are ignored on read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EventDocument {
+    #[serde(rename = "_id")]
+    pub(crate) id: String,
+    pub(crate) idempotency_token: Option<String>,
+    pub(crate) event_type: String,
+    pub(crate) payload: Bson,
+    pub(crate) status: String,
+    pub(crate) created_at: DateTime,
+    pub(crate) locked_until: DateTime,
+}
+
+impl EventDocument {
+    pub(crate) fn from_event<P>(event: &Event<P>) -> Result<Self, OutboxError>
+    where
+        P: Debug + Clone + Serialize + Send + Sync,
+    {
+        Ok(Self {
+            id: event.id.to_string(),
+            idempotency_token: event
+                .idempotency_token
+                .as_ref()
+                .map(|t| t.as_str().to_string()),
+            event_type: event.event_type.as_str().to_string(),
+            payload: bson::to_bson(&event.payload)
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+            status: event.status.as_str().to_string(),
+            created_at: to_bson_date(event.created_at),
+            locked_until: to_bson_date(event.locked_until),
+        })
+    }
+
+    pub(crate) fn into_event<P>(self) -> Result<Event<P>, OutboxError>
+    where
+        P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync,
+    {
+        Ok(Event {
+            id: self.id.parse()?,
+            idempotency_token: self.idempotency_token.map(IdempotencyToken::new),
+            event_type: EventType::load(&self.event_type),
+            payload: bson::from_bson(self.payload)
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+            status: self.status.parse()?,
+            created_at: from_bson_date(self.created_at),
+            locked_until: from_bson_date(self.locked_until),
+        })
+    }
+}
+
+pub(crate) fn to_bson_date(at: OffsetDateTime) -> DateTime {
+    DateTime::from_millis(
+        i64::try_from(at.unix_timestamp_nanos() / 1_000_000).unwrap_or(
+            if at < OffsetDateTime::UNIX_EPOCH {
+                i64::MIN
+            } else {
+                i64::MAX
+            },
+        ),
+    )
+}
+
+pub(crate) fn from_bson_date(at: DateTime) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(at.timestamp_millis()) * 1_000_000)
+        .unwrap_or(if at.timestamp_millis() < 0 {
+            time::PrimitiveDateTime::MIN.assume_utc()
+        } else {
+            time::PrimitiveDateTime::MAX.assume_utc()
+        })
+}
+
+/// The ids as a BSON array for `{ "_id": { "$in": .. } }` filters.
+pub(crate) fn id_list<'a>(ids: impl IntoIterator<Item = &'a EventId>) -> Vec<String> {
+    ids.into_iter().map(ToString::to_string).collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use outbox_core::prelude::{EventStatus, Payload};
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload {
+        order: u32,
+    }
+
+    #[rstest]
+    fn event_round_trips_through_a_document() {
+        let event = Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload { order: 7 }),
+            Some(IdempotencyToken::new("token-1".to_string())),
+        );
+
+        let document = bson::to_document(&EventDocument::from_event(&event).unwrap()).unwrap();
+        let back: Event<TestPayload> = bson::from_document::<EventDocument>(document)
+            .unwrap()
+            .into_event()
+            .unwrap();
+
+        assert_eq!(back.id, event.id);
+        assert_eq!(back.payload.as_value(), &TestPayload { order: 7 });
+        assert_eq!(
+            back.idempotency_token
+                .as_ref()
+                .map(IdempotencyToken::as_str),
+            Some("token-1")
+        );
+        assert_eq!(back.status, EventStatus::Pending);
+        assert_eq!(
+            back.created_at.unix_timestamp_nanos() / 1_000_000,
+            event.created_at.unix_timestamp_nanos() / 1_000_000
+        );
+    }
+}
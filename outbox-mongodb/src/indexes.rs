@@ -0,0 +1,105 @@
+//! Index setup for the MongoDB outbox.
+//!
+//! MongoDB creates collections on first write, so the only schema to manage
+//! is the set of indexes. [`create_indexes`] creates them under
+//! fixed names; MongoDB treats creating an existing index with the same
+//! definition as a no-op, so it is safe to call on every startup.
+
+use crate::EVENTS_COLLECTION;
+use crate::document::EventDocument;
+use mongodb::bson::doc;
+use mongodb::options::IndexOptions;
+use mongodb::{Database, IndexModel};
+use outbox_core::prelude::OutboxError;
+
+fn index(keys: mongodb::bson::Document, options: IndexOptions) -> IndexModel {
+    IndexModel::builder().keys(keys).options(options).build()
+}
+
+/// Creates the indexes the outbox relies on, including the DLQ ones when
+/// the `dlq` feature is enabled.
+///
+/// `idx_outbox_idempotency` is a unique index on `idempotency_token`
+/// restricted to documents that carry a token, so events without one
+/// never collide.
+///
+/// # Errors
+///
+/// Returns [`OutboxError::DatabaseError`] if an index cannot be created,
+/// e.g. because one with the same name but a different definition exists.
+pub async fn create_indexes(database: &Database) -> Result<(), OutboxError> {
+    database
+        .collection::<EventDocument>(EVENTS_COLLECTION)
+        .create_indexes([
+            index(
+                doc! { "status": 1, "locked_until": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_processing_queue".to_string())
+                    .build(),
+            ),
+            index(
+                doc! { "status": 1, "created_at": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_created_at".to_string())
+                    .build(),
+            ),
+            index(
+                doc! { "idempotency_token": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_idempotency".to_string())
+                    .unique(true)
+                    .partial_filter_expression(doc! { "idempotency_token": { "$type": "string" } })
+                    .build(),
+            ),
+        ])
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+    #[cfg(feature = "dlq")]
+    create_dlq_indexes(database).await?;
+    Ok(())
+}
+
+#[cfg(feature = "dlq")]
+async fn create_dlq_indexes(database: &Database) -> Result<(), OutboxError> {
+    database
+        .collection::<EventDocument>(EVENTS_COLLECTION)
+        .create_indexes([
+            index(
+                doc! { "failure_count": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_failure_count".to_string())
+                    .partial_filter_expression(doc! { "failure_count": { "$gt": 0 } })
+                    .build(),
+            ),
+            index(
+                doc! { "dlq_claimed_at": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_dlq_claimed_at".to_string())
+                    .partial_filter_expression(doc! { "dlq_claimed_at": { "$type": "date" } })
+                    .build(),
+            ),
+        ])
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+    database
+        .collection::<mongodb::bson::Document>(crate::DEAD_LETTERS_COLLECTION)
+        .create_indexes([
+            index(
+                doc! { "quarantined_at": -1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_dlq_quarantined_at".to_string())
+                    .build(),
+            ),
+            index(
+                doc! { "event_type": 1 },
+                IndexOptions::builder()
+                    .name("idx_outbox_dlq_event_type".to_string())
+                    .build(),
+            ),
+        ])
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    Ok(())
+}
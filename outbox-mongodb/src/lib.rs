@@ -0,0 +1,750 @@
+//! MongoDB storage backend for `outbox-core`, built on the official driver.
+//!
+//! [`MongoOutbox`] implements [`OutboxStorage`] (and, with the `dlq`
+//! feature, the quarantine methods and [`DlqHeap`]) over an `outbox_events`
+//! collection. [`MongoWriter`] inserts events through a [`ClientSession`], so
+//! an event is committed in the same multi-document transaction as the
+//! aggregates it describes.
+//!
+//! Workers claim events one at a time with `findOneAndUpdate`, which flips a
+//! document to `Processing` and stamps its lease (`locked_until`) atomically,
+//! so two workers never receive the same event. `wait_for_notification`
+//! follows a change stream of inserts into the collection.
+//!
+//! Transactions and change streams both need a replica set or a sharded
+//! cluster; a single-node replica set is enough for development.
+
+#[cfg(feature = "dlq")]
+mod dlq;
+mod document;
+mod indexes;
+
+pub use indexes::create_indexes;
+
+use crate::document::{EventDocument, id_list, to_bson_date};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mongodb::bson::{Document, doc};
+use mongodb::change_stream::ChangeStream;
+use mongodb::change_stream::event::ChangeStreamEvent;
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::ReturnDocument;
+use mongodb::{Client, ClientSession, Collection, Database};
+use outbox_core::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+pub(crate) const EVENTS_COLLECTION: &str = "outbox_events";
+#[cfg(feature = "dlq")]
+pub(crate) const DEAD_LETTERS_COLLECTION: &str = "outbox_dead_letters";
+
+/// MongoDB error code for a unique index violation.
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Clone)]
+pub struct MongoOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    inner: Arc<MongoOutboxInner<P>>,
+}
+
+impl<P> MongoOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new(database: &Database, config: Arc<OutboxConfig<P>>) -> Self {
+        Self {
+            inner: Arc::new(MongoOutboxInner {
+                client: database.client().clone(),
+                events: database.collection(EVENTS_COLLECTION),
+                #[cfg(feature = "dlq")]
+                dead_letters: database.collection(DEAD_LETTERS_COLLECTION),
+                config,
+                change_stream: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Starts a session with an open transaction, for moves between the
+    /// outbox and the dead-letter collection. Dropping the session without
+    /// committing aborts the transaction.
+    #[cfg(feature = "dlq")]
+    async fn begin(&self) -> Result<ClientSession, OutboxError> {
+        let mut session = self
+            .inner
+            .client
+            .start_session()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        session
+            .start_transaction()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(session)
+    }
+}
+
+struct MongoOutboxInner<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    #[cfg_attr(not(feature = "dlq"), allow(dead_code))]
+    client: Client,
+    events: Collection<EventDocument>,
+    #[cfg(feature = "dlq")]
+    dead_letters: Collection<Document>,
+    config: Arc<OutboxConfig<P>>,
+    change_stream: Mutex<Option<ChangeStream<ChangeStreamEvent<Document>>>>,
+}
+
+#[async_trait]
+impl<P> OutboxStorage<P> for MongoOutbox<P>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn fetch_next_to_process(&self, limit: u32) -> Result<Vec<Event<P>>, OutboxError> {
+        let now = self.inner.config.clock.now();
+        let locked_until = now + time::Duration::minutes(self.inner.config.lock_timeout_mins);
+        let filter = doc! {
+            "$or": [
+                { "status": "Pending" },
+                { "status": "Processing", "locked_until": { "$lt": to_bson_date(now) } },
+            ]
+        };
+        let claim = doc! {
+            "$set": { "status": "Processing", "locked_until": to_bson_date(locked_until) }
+        };
+
+        let mut claimed = Vec::new();
+        for _ in 0..limit {
+            let Some(document) = self
+                .inner
+                .events
+                .find_one_and_update(filter.clone(), claim.clone())
+                .sort(doc! { "locked_until": 1 })
+                .return_document(ReturnDocument::After)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            else {
+                break;
+            };
+            claimed.push(document.into_event()?);
+        }
+        Ok(claimed)
+    }
+
+    async fn update_status(&self, ids: &[EventId], status: EventStatus) -> Result<(), OutboxError> {
+        self.inner
+            .events
+            .update_many(
+                doc! { "_id": { "$in": id_list(ids) } },
+                doc! { "$set": { "status": status.as_str() } },
+            )
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let cutoff =
+            self.inner.config.clock.now() - time::Duration::days(self.inner.config.retention_days);
+        let result = self
+            .inner
+            .events
+            .delete_many(doc! { "status": "Sent", "created_at": { "$lt": to_bson_date(cutoff) } })
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: deleted {} old messages",
+            result.deleted_count
+        );
+        Ok(())
+    }
+
+    /// Waits for the next insert into `outbox_events`, whoever made it.
+    ///
+    /// The change stream is opened on first use and kept between calls, so
+    /// inserts made while the worker was busy are not missed; it is reopened
+    /// after an error. `_channel` is ignored.
+    async fn wait_for_notification(&self, _channel: &str) -> Result<(), OutboxError> {
+        let mut guard = self.inner.change_stream.lock().await;
+
+        if guard.is_none() {
+            let stream = self
+                .inner
+                .events
+                .clone_with_type::<Document>()
+                .watch()
+                .pipeline([doc! { "$match": { "operationType": "insert" } }])
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+            *guard = Some(stream);
+        }
+        let Some(stream) = guard.as_mut() else {
+            return Ok(());
+        };
+        match stream.next().await {
+            Some(Ok(_)) => Ok(()),
+            Some(Err(e)) => {
+                *guard = None;
+                Err(OutboxError::DatabaseError(e.to_string()))
+            }
+            None => {
+                *guard = None;
+                Err(OutboxError::DatabaseError(
+                    "Outbox change stream closed".to_string(),
+                ))
+            }
+        }
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        let documents: Vec<EventDocument> = self
+            .inner
+            .events
+            .find(doc! { "_id": { "$in": id_list(ids) } })
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        documents
+            .into_iter()
+            .map(EventDocument::into_event)
+            .collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let result = self
+            .inner
+            .events
+            .delete_many(doc! { "_id": { "$in": id_list(ids) } })
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "DLQ reaper: deleted {} events published to the DLQ destination",
+            result.deleted_count
+        );
+        Ok(result.deleted_count)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let cutoff = self.inner.config.clock.now()
+            - time::Duration::days(self.inner.config.dlq_retention_days);
+        let result = self
+            .inner
+            .dead_letters
+            .delete_many(doc! { "quarantined_at": { "$lt": to_bson_date(cutoff) } })
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: purged {} expired dead letters",
+            result.deleted_count
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut session = self.begin().await?;
+        let moved = dlq::move_to_dead_letters(
+            &self.inner,
+            &mut session,
+            entries,
+            self.inner.config.clock.now(),
+        )
+        .await?;
+        session
+            .commit_transaction()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        debug!(
+            "DLQ reaper: quarantined {}/{} events",
+            moved.len(),
+            entries.len()
+        );
+        Ok(moved)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: std::time::Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let cutoff = self.inner.config.clock.now() - cooldown;
+        let mut session = self.begin().await?;
+        let revived = dlq::move_to_outbox(&self.inner, &mut session, cutoff, max_revivals).await?;
+        session
+            .commit_transaction()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        debug!("DLQ reaper: revived {} dead letters", revived.len());
+        Ok(revived)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        let present: std::collections::HashSet<String> = self
+            .inner
+            .events
+            .clone_with_type::<Document>()
+            .find(doc! { "_id": { "$in": id_list(ids) } })
+            .projection(doc! { "_id": 1 })
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .map(|document| {
+                document
+                    .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+                    .and_then(|d| {
+                        d.get_str("_id")
+                            .map(str::to_string)
+                            .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ids
+            .iter()
+            .filter(|id| !present.contains(&id.to_string()))
+            .copied()
+            .collect())
+    }
+}
+
+/// Inserts events into the `outbox_events` collection.
+///
+/// As an [`OutboxTxWriter`] it writes through the caller's [`ClientSession`],
+/// so the events commit or abort together with everything else in the
+/// session's transaction. As a plain [`OutboxWriter`] it inserts directly,
+/// wrapping batches in a transaction of their own so they stay
+/// all-or-nothing.
+#[derive(Clone)]
+pub struct MongoWriter {
+    client: Client,
+    events: Collection<EventDocument>,
+}
+
+impl MongoWriter {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            client: database.client().clone(),
+            events: database.collection(EVENTS_COLLECTION),
+        }
+    }
+}
+
+#[async_trait]
+impl<P> OutboxWriter<P> for MongoWriter
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        self.events
+            .insert_one(EventDocument::from_event(&event)?)
+            .await
+            .map_err(|e| map_insert_error(&e))?;
+        Ok(())
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut session = self
+            .client
+            .start_session()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        session
+            .start_transaction()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        self.insert_events_in(&mut session, events).await?;
+        session
+            .commit_transaction()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl<P> OutboxTxWriter<P, ClientSession> for MongoWriter
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut ClientSession,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        self.events
+            .insert_one(EventDocument::from_event(&event)?)
+            .session(tx)
+            .await
+            .map_err(|e| map_insert_error(&e))?;
+        Ok(())
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut ClientSession,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let documents = events
+            .iter()
+            .map(EventDocument::from_event)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.events
+            .insert_many(documents)
+            .session(tx)
+            .await
+            .map_err(|e| map_insert_error(&e))?;
+        Ok(())
+    }
+}
+
+/// Reports a violation of `idx_outbox_idempotency` as [`OutboxError::DuplicateEvent`].
+pub(crate) fn map_insert_error(e: &mongodb::error::Error) -> OutboxError {
+    let duplicate = |code: i32, message: &str| {
+        code == DUPLICATE_KEY && message.contains("idx_outbox_idempotency")
+    };
+    let is_duplicate = match e.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(error)) => duplicate(error.code, &error.message),
+        ErrorKind::InsertMany(error) => error
+            .write_errors
+            .iter()
+            .flatten()
+            .any(|error| duplicate(error.code, &error.message)),
+        ErrorKind::Command(error) => duplicate(error.code, &error.message),
+        _ => false,
+    };
+    if is_duplicate {
+        OutboxError::DuplicateEvent
+    } else {
+        OutboxError::DatabaseError(e.to_string())
+    }
+}
+
+/// These tests need a MongoDB replica set (a single node is enough). Point
+/// `OUTBOX_MONGODB_URL` at it and run
+/// `cargo test -p outbox-mongodb --all-features -- --ignored`. Every test
+/// works in a fresh database that it drops when it passes.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::time::Duration;
+    use time::OffsetDateTime;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload(u32);
+
+    async fn database() -> Database {
+        let url = std::env::var("OUTBOX_MONGODB_URL")
+            .unwrap_or_else(|_| "mongodb://localhost:27017/?replicaSet=rs0".to_string());
+        let client = Client::with_uri_str(&url).await.unwrap();
+        let database = client.database(&format!("outbox_test_{}", uuid::Uuid::new_v4().simple()));
+        crate::create_indexes(&database).await.unwrap();
+        database
+    }
+
+    fn event(token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    async fn count(database: &Database, status: &EventStatus) -> u64 {
+        database
+            .collection::<Document>(EVENTS_COLLECTION)
+            .count_documents(doc! { "status": status.as_str() })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn fetch_claims_pending_events_and_locks_them() {
+        let database = database().await;
+        let outbox = MongoOutbox::<TestPayload>::new(&database, Arc::new(OutboxConfig::default()));
+        let writer = MongoWriter::new(&database);
+        let inserted = event(Some("token-1"));
+        writer.insert_event(inserted.clone()).await.unwrap();
+        writer
+            .insert_events(vec![event(None), event(None)])
+            .await
+            .unwrap();
+
+        let claimed = outbox.fetch_next_to_process(2).await.unwrap();
+
+        assert_eq!(claimed.len(), 2);
+        assert!(claimed.iter().all(|e| e.status == EventStatus::Processing));
+        assert!(
+            claimed
+                .iter()
+                .all(|e| e.locked_until > OffsetDateTime::now_utc())
+        );
+        let rest = outbox.fetch_next_to_process(10).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        let round_trip = claimed
+            .iter()
+            .chain(&rest)
+            .find(|e| e.id == inserted.id)
+            .unwrap();
+        assert_eq!(round_trip.payload.as_value(), &TestPayload(1));
+        database.drop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn locks_expire_on_the_configured_clock() {
+        let database = database().await;
+        let clock = ManualClock::default();
+        let outbox = MongoOutbox::<TestPayload>::new(
+            &database,
+            Arc::new(OutboxConfig::default().with_clock(Arc::new(clock.clone()))),
+        );
+        MongoWriter::new(&database)
+            .insert_event(event(None))
+            .await
+            .unwrap();
+
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_mins(6));
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        database.drop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn insert_rejects_duplicate_idempotency_token() {
+        let database = database().await;
+        let writer = MongoWriter::new(&database);
+        writer.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let single = writer.insert_event(event(Some("token-1"))).await;
+        let batch = writer
+            .insert_events(vec![event(Some("token-2")), event(Some("token-1"))])
+            .await;
+
+        assert!(matches!(single, Err(OutboxError::DuplicateEvent)));
+        assert!(matches!(batch, Err(OutboxError::DuplicateEvent)));
+        assert_eq!(count(&database, &EventStatus::Pending).await, 1);
+        database.drop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn writer_inserts_within_the_callers_transaction() {
+        let database = database().await;
+        let writer = MongoWriter::new(&database);
+
+        let mut session = database.client().start_session().await.unwrap();
+        session.start_transaction().await.unwrap();
+        writer
+            .insert_events_in(&mut session, vec![event(None), event(None)])
+            .await
+            .unwrap();
+        session.abort_transaction().await.unwrap();
+        assert_eq!(count(&database, &EventStatus::Pending).await, 0);
+
+        session.start_transaction().await.unwrap();
+        writer
+            .insert_event_in(&mut session, event(None))
+            .await
+            .unwrap();
+        session.commit_transaction().await.unwrap();
+        assert_eq!(count(&database, &EventStatus::Pending).await, 1);
+        database.drop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn insert_wakes_waiting_worker() {
+        let database = database().await;
+        let outbox = MongoOutbox::<TestPayload>::new(&database, Arc::new(OutboxConfig::default()));
+        // Open the change stream before inserting, as the manager does.
+        let waiter = {
+            let outbox = outbox.clone();
+            tokio::spawn(async move { outbox.wait_for_notification("outbox_event").await })
+        };
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        MongoWriter::new(&database)
+            .insert_event(event(None))
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        database.drop().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn delete_garbage_removes_only_old_sent_events() {
+        let database = database().await;
+        let clock = ManualClock::default();
+        let outbox = MongoOutbox::<TestPayload>::new(
+            &database,
+            Arc::new(OutboxConfig::default().with_clock(Arc::new(clock.clone()))),
+        );
+        let old = event(None);
+        MongoWriter::new(&database)
+            .insert_events(vec![old.clone(), event(None)])
+            .await
+            .unwrap();
+        outbox
+            .update_status(&[old.id], EventStatus::Sent)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_hours(24 * 8));
+        outbox.delete_garbage().await.unwrap();
+
+        assert_eq!(count(&database, &EventStatus::Sent).await, 0);
+        assert_eq!(count(&database, &EventStatus::Pending).await, 1);
+        database.drop().await.unwrap();
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn exceeded_events_are_quarantined_and_revived() {
+        let database = database().await;
+        let clock = ManualClock::default();
+        let outbox = MongoOutbox::<TestPayload>::new(
+            &database,
+            Arc::new(OutboxConfig::default().with_clock(Arc::new(clock.clone()))),
+        );
+        let failing = event(Some("token-1"));
+        MongoWriter::new(&database)
+            .insert_events(vec![failing.clone(), event(None)])
+            .await
+            .unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..3 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+
+        let policy = DlqPolicy::new(3);
+        let drained = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 3);
+        assert!(drained[0].first_failed_at.is_some());
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+
+        let moved = outbox.quarantine_events(&drained).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(
+            outbox.missing_events(&[failing.id]).await.unwrap(),
+            vec![failing.id]
+        );
+
+        let cooldown = Duration::from_mins(10);
+        assert!(
+            outbox
+                .revive_dead_letters(cooldown, 3)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        clock.advance(cooldown);
+        assert_eq!(
+            outbox.revive_dead_letters(cooldown, 3).await.unwrap(),
+            vec![failing.id]
+        );
+        assert_eq!(count(&database, &EventStatus::Pending).await, 2);
+        database.drop().await.unwrap();
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn unacked_claim_is_drained_again_once_reclaimed() {
+        let database = database().await;
+        let outbox = MongoOutbox::<TestPayload>::new(&database, Arc::new(OutboxConfig::default()));
+        let failing = event(None);
+        MongoWriter::new(&database)
+            .insert_events(vec![failing.clone()])
+            .await
+            .unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..2 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(2);
+
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 1);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 1);
+        let drained = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 2);
+
+        outbox.ack_drained(&[failing.id]).await.unwrap();
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 0);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+        database.drop().await.unwrap();
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MongoDB replica set at OUTBOX_MONGODB_URL"]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let database = database().await;
+        let outbox = MongoOutbox::<TestPayload>::new(&database, Arc::new(OutboxConfig::default()));
+        let writer = MongoWriter::new(&database);
+        let (taken, free) = (event(Some("token-1")), event(None));
+        let ids = [taken.id, free.id];
+        writer.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        writer.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert_eq!(revived, vec![ids[1]]);
+        assert_eq!(count(&database, &EventStatus::Pending).await, 2);
+        database.drop().await.unwrap();
+    }
+}
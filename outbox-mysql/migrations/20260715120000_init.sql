@@ -0,0 +1,22 @@
+-- Outbox table for the MySQL backend (MySQL 8.0+ or MariaDB 10.6+, both of
+-- which support `FOR UPDATE SKIP LOCKED`).
+--
+-- Ids are stored as hyphenated UUID text. Timestamps are `datetime(6)` in
+-- UTC, written by the application from `OutboxConfig::clock`, so the server
+-- and session time zones do not matter. A fresh row is unlocked with
+-- `locked_until` at the Unix epoch. MySQL has no partial indexes, so the
+-- processing queue index covers every row.
+
+create table outbox_events
+(
+    id                char(36)     not null primary key,
+    idempotency_token varchar(255)          default null,
+    event_type        varchar(255) not null,
+    payload           json         not null,
+    status            enum ('Pending', 'Processing', 'Sent') not null default 'Pending',
+    created_at        datetime(6)  not null,
+    locked_until      datetime(6)  not null default '1970-01-01 00:00:00',
+    index idx_outbox_processing_queue (status, locked_until),
+    index idx_outbox_created_at (created_at),
+    unique index idx_outbox_idempotency (idempotency_token)
+);
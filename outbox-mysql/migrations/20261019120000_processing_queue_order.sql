@@ -0,0 +1,12 @@
+-- Reorders the processing queue index to `(locked_until, status)`, matching
+-- the Postgres and SQLite backends.
+--
+-- The claim query filters on `status` with an `OR` and orders by
+-- `locked_until`. With `status` leading, InnoDB had to filesort the matches,
+-- and `FOR UPDATE SKIP LOCKED` locked every row it scanned to do so, which
+-- serialized concurrent workers. Leading with `locked_until` lets it walk
+-- the index in claim order and stop after `LIMIT` rows.
+
+alter table outbox_events
+    drop index idx_outbox_processing_queue,
+    add index idx_outbox_processing_queue (locked_until, status);
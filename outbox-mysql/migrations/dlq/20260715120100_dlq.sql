@@ -0,0 +1,31 @@
+-- Dead-letter table and failure bookkeeping for the MySQL backend.
+--
+-- `failure_count`, `last_error` and `first_failed_at` on `outbox_events` back
+-- the MySQL `DlqHeap`; `revival_count` counts automatic revivals. The DLQ
+-- reaper moves rows that crossed the threshold into `outbox_dead_letters`
+-- in one transaction.
+
+alter table outbox_events
+    add column failure_count   integer     not null default 0,
+    add column last_error      text                 default null,
+    add column first_failed_at datetime(6)          default null,
+    add column revival_count   integer     not null default 0,
+    add index idx_outbox_failure_count (failure_count);
+
+create table outbox_dead_letters
+(
+    id                char(36)     not null primary key,
+    idempotency_token varchar(255)          default null,
+    event_type        varchar(255) not null,
+    payload           json         not null,
+    original_status   enum ('Pending', 'Processing', 'Sent') not null,
+    created_at        datetime(6)  not null,
+    locked_until      datetime(6)  not null,
+    failure_count     integer      not null,
+    last_error        text                  default null,
+    first_failed_at   datetime(6)           default null,
+    revival_count     integer      not null default 0,
+    quarantined_at    datetime(6)  not null,
+    index idx_outbox_dlq_quarantined_at (quarantined_at),
+    index idx_outbox_dlq_event_type (event_type)
+);
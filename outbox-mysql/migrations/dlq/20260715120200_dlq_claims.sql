@@ -0,0 +1,9 @@
+-- Claims taken by `drain_exceeded`.
+--
+-- A drained row keeps its failure counters until `ack_drained` resets them
+-- after the quarantine committed; `reclaim_stale` clears claims that were
+-- never acknowledged so the row is drained again.
+
+alter table outbox_events
+    add column dlq_claimed_at datetime(6) default null,
+    add index idx_outbox_dlq_claimed_at (dlq_claimed_at);
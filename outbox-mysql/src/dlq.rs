@@ -0,0 +1,191 @@
+//! MySQL-backed [`DlqHeap`] implementation.
+//!
+//! Failure counters live on the outbox row itself, in the `failure_count`,
+//! `last_error` and `first_failed_at` columns added by the `dlq` migrations,
+//! just as in the Postgres backend:
+//! - `record_failure` → `UPDATE .. SET failure_count = failure_count + 1`,
+//!   overwriting `last_error` and setting `first_failed_at` if unset
+//! - `record_success` → reset the counter, error, timestamp and claim
+//! - `drain_exceeded` → select up to the policy's batch size of unclaimed
+//!   rows whose `failure_count` reached the threshold for their
+//!   `event_type`, or whose `first_failed_at` is older than the policy's
+//!   maximum failure age, then set their `dlq_claimed_at` in the same
+//!   transaction. Per-type thresholds become a
+//!   `CASE event_type` expression. Rows are locked with
+//!   `FOR UPDATE SKIP LOCKED`, so the same id is not returned to two
+//!   concurrent callers.
+//! - `ack_drained` → reset the counter, error, timestamp and claim of rows
+//!   still in the outbox
+//! - `reclaim_stale` → clear `dlq_claimed_at` on claims older than the
+//!   timeout, so the next drain returns those rows again
+
+use crate::MySqlOutbox;
+use crate::row::push_ids;
+use async_trait::async_trait;
+use outbox_core::prelude::{DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError};
+use serde::Serialize;
+use sqlx::{MySql, QueryBuilder};
+use std::fmt::Debug;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+#[async_trait]
+impl<P> DlqHeap for MySqlOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn record_failure(
+        &self,
+        id: EventId,
+        _event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = failure_count + 1,
+                last_error = ?,
+                first_failed_at = COALESCE(first_failed_at, ?)
+            WHERE id = ?
+            ",
+        )
+        .bind(error.to_string())
+        .bind(self.inner.config.clock.now())
+        .bind(id.to_string())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id = ?
+                AND failure_count > 0
+            ",
+        )
+        .bind(id.to_string())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
+        let now = self.inner.config.clock.now();
+        let failing_since = policy.failing_since_cutoff(now);
+
+        let mut select = QueryBuilder::<MySql>::new(
+            r"
+            SELECT id, failure_count, last_error, first_failed_at, event_type
+            FROM outbox_events
+            WHERE failure_count > 0
+                AND dlq_claimed_at IS NULL
+                AND (failure_count >= ",
+        );
+        if policy.type_thresholds.is_empty() {
+            select.push_bind(policy.threshold);
+        } else {
+            select.push("CASE event_type");
+            for (event_type, threshold) in &policy.type_thresholds {
+                select.push(" WHEN ");
+                select.push_bind(event_type.clone());
+                select.push(" THEN ");
+                select.push_bind(*threshold);
+            }
+            select.push(" ELSE ");
+            select.push_bind(policy.threshold);
+            select.push(" END");
+        }
+        select.push(" OR first_failed_at <= ");
+        select.push_bind(failing_since);
+        select.push(") LIMIT ");
+        select.push_bind(policy.batch_size);
+        select.push(" FOR UPDATE SKIP LOCKED");
+
+        let mut tx = self.begin().await?;
+        let rows: Vec<(String, i32, Option<String>, Option<OffsetDateTime>, String)> = select
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        if !rows.is_empty() {
+            let mut claim =
+                QueryBuilder::<MySql>::new("UPDATE outbox_events SET dlq_claimed_at = ");
+            claim.push_bind(now);
+            claim.push(" WHERE id IN ");
+            push_ids(&mut claim, rows.iter().map(|row| row.0.clone()));
+            claim
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(
+                |(id, failure_count, last_error, first_failed_at, event_type)| {
+                    Ok(DlqEntry::new(
+                        id.parse()?,
+                        u32::try_from(failure_count).unwrap_or(0),
+                        last_error,
+                    )
+                    .with_first_failed_at(first_failed_at)
+                    .with_event_type(EventType::load(&event_type)))
+                },
+            )
+            .collect()
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut ack = QueryBuilder::<MySql>::new(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id IN ",
+        );
+        push_ids(&mut ack, ids.iter().map(ToString::to_string));
+        ack.build()
+            .execute(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let Some(cutoff) = time::Duration::try_from(timeout)
+            .ok()
+            .and_then(|timeout| self.inner.config.clock.now().checked_sub(timeout))
+        else {
+            return Ok(0);
+        };
+        let result = sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET dlq_claimed_at = NULL
+            WHERE dlq_claimed_at <= ?
+            ",
+        )
+        .bind(cutoff)
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,950 @@
+//! MySQL storage backend for `outbox-core`, built on sqlx's MySQL driver.
+//!
+//! [`MySqlOutbox`] implements [`OutboxStorage`] (and, with the `dlq`
+//! feature, the quarantine methods and [`DlqHeap`]) over an `outbox_events`
+//! table created by [`migrate`]. [`MySqlWriter`] inserts events through a
+//! pool, a connection or a transaction, so an event can be committed
+//! together with the business data it describes.
+//!
+//! Requires MySQL 8.0+ or MariaDB 10.6+: workers claim events with
+//! `SELECT .. FOR UPDATE SKIP LOCKED` inside a short transaction, so several
+//! workers can poll the same table without blocking each other or receiving
+//! the same row. There is no `LISTEN`/`NOTIFY`: `wait_for_notification`
+//! returns after the poll interval, or earlier when an insert made through
+//! the [`MySqlOutbox`] itself (or a call to [`MySqlOutbox::wake`]) wakes it
+//! in process.
+
+#[cfg(feature = "dlq")]
+mod dlq;
+mod migrate;
+mod row;
+
+pub use migrate::{migrate, verify_schema};
+
+use crate::row::{EVENT_COLUMNS, EventRow, event_from_row, push_ids};
+use async_trait::async_trait;
+use outbox_core::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::types::Json;
+use sqlx::{Executor, MySql, MySqlConnection, MySqlPool, QueryBuilder, Transaction};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::debug;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct MySqlOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    inner: Arc<MySqlOutboxInner<P>>,
+    poll_interval: Duration,
+}
+
+impl<P> MySqlOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new(pool: MySqlPool, config: Arc<OutboxConfig<P>>) -> Self {
+        Self {
+            inner: Arc::new(MySqlOutboxInner {
+                pool,
+                config,
+                notify: Notify::new(),
+            }),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets how long `wait_for_notification` waits for an in-process wake-up
+    /// before returning anyway, so that events written by other processes or
+    /// through [`MySqlWriter`] are picked up. Defaults to one second.
+    #[must_use]
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Wakes a worker waiting in `wait_for_notification` of this process.
+    ///
+    /// Inserts made through the [`MySqlOutbox`] itself do this already; call
+    /// it after committing a transaction written through [`MySqlWriter`] to
+    /// skip the wait for the next poll.
+    pub fn wake(&self) {
+        self.inner.notify.notify_one();
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, MySql>, OutboxError> {
+        self.inner
+            .pool
+            .begin()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+    }
+}
+
+struct MySqlOutboxInner<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pool: MySqlPool,
+    config: Arc<OutboxConfig<P>>,
+    notify: Notify,
+}
+
+#[async_trait]
+impl<P> OutboxStorage<P> for MySqlOutbox<P>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn fetch_next_to_process(&self, limit: u32) -> Result<Vec<Event<P>>, OutboxError> {
+        let now = self.inner.config.clock.now();
+        let locked_until = now + time::Duration::minutes(self.inner.config.lock_timeout_mins);
+
+        let mut tx = self.begin().await?;
+        let rows: Vec<EventRow> = sqlx::query_as(&format!(
+            r"
+            SELECT {EVENT_COLUMNS}
+            FROM outbox_events
+            WHERE status = 'Pending'
+                OR (status = 'Processing' AND locked_until < ?)
+            ORDER BY locked_until ASC
+            LIMIT ?
+            FOR UPDATE SKIP LOCKED
+            "
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        if !rows.is_empty() {
+            let mut update = QueryBuilder::<MySql>::new(
+                "UPDATE outbox_events SET status = 'Processing', locked_until = ",
+            );
+            update.push_bind(locked_until);
+            update.push(" WHERE id IN ");
+            push_ids(&mut update, rows.iter().map(|row| row.0.clone()));
+            update
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                let mut event = event_from_row(row)?;
+                event.status = EventStatus::Processing;
+                event.locked_until = locked_until;
+                Ok(event)
+            })
+            .collect()
+    }
+
+    async fn update_status(&self, ids: &[EventId], status: EventStatus) -> Result<(), OutboxError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut query = QueryBuilder::<MySql>::new("UPDATE outbox_events SET status = ");
+        query.push_bind(status.as_str());
+        query.push(" WHERE id IN ");
+        push_ids(&mut query, ids.iter().map(ToString::to_string));
+        query
+            .build()
+            .execute(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let cutoff =
+            self.inner.config.clock.now() - time::Duration::days(self.inner.config.retention_days);
+        let result = sqlx::query(
+            r"
+            DELETE
+            FROM outbox_events
+            WHERE status = 'Sent'
+                AND created_at < ?
+            LIMIT 5000",
+        )
+        .bind(cutoff)
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: deleted {} old messages",
+            result.rows_affected()
+        );
+        Ok(())
+    }
+
+    async fn wait_for_notification(&self, _channel: &str) -> Result<(), OutboxError> {
+        tokio::select! {
+            () = self.inner.notify.notified() => {}
+            () = tokio::time::sleep(self.poll_interval) => {}
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(format!(
+            "SELECT {EVENT_COLUMNS} FROM outbox_events WHERE id IN "
+        ));
+        push_ids(&mut query, ids.iter().map(ToString::to_string));
+        let rows: Vec<EventRow> = query
+            .build_query_as()
+            .fetch_all(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        rows.into_iter().map(event_from_row).collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut query = QueryBuilder::<MySql>::new("DELETE FROM outbox_events WHERE id IN ");
+        push_ids(&mut query, ids.iter().map(ToString::to_string));
+        let result = query
+            .build()
+            .execute(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "DLQ reaper: deleted {} events published to the DLQ destination",
+            result.rows_affected()
+        );
+        Ok(result.rows_affected())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let cutoff = self.inner.config.clock.now()
+            - time::Duration::days(self.inner.config.dlq_retention_days);
+        let result = sqlx::query(
+            r"
+            DELETE
+            FROM outbox_dead_letters
+            WHERE quarantined_at < ?
+            ORDER BY quarantined_at
+            LIMIT 5000",
+        )
+        .bind(cutoff)
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: purged {} expired dead letters",
+            result.rows_affected()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut tx = self.begin().await?;
+
+        // Lock the rows first, so the copy and the delete below see the same set.
+        let mut lock =
+            QueryBuilder::<MySql>::new("SELECT id, event_type FROM outbox_events WHERE id IN ");
+        push_ids(&mut lock, entries.iter().map(|e| e.id.to_string()));
+        lock.push(" FOR UPDATE");
+        let found: Vec<(String, String)> = lock
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        let event_types: std::collections::HashMap<String, String> = found.into_iter().collect();
+        let moved: Vec<&DlqEntry> = entries
+            .iter()
+            .filter(|e| event_types.contains_key(&e.id.to_string()))
+            .collect();
+
+        if !moved.is_empty() {
+            let mut insert = QueryBuilder::<MySql>::new(
+                r"
+                INSERT INTO outbox_dead_letters (
+                    id,
+                    idempotency_token,
+                    event_type,
+                    payload,
+                    original_status,
+                    created_at,
+                    locked_until,
+                    failure_count,
+                    last_error,
+                    first_failed_at,
+                    revival_count,
+                    quarantined_at
+                )
+                SELECT
+                    o.id,
+                    o.idempotency_token,
+                    o.event_type,
+                    o.payload,
+                    o.status,
+                    o.created_at,
+                    o.locked_until,
+                    f.failure_count,
+                    f.last_error,
+                    f.first_failed_at,
+                    o.revival_count,
+                    ",
+            );
+            insert.push_bind(self.inner.config.clock.now());
+            insert.push(" FROM outbox_events AS o JOIN ");
+            push_failures(&mut insert, &moved);
+            insert.push(" AS f ON o.id = f.id");
+            insert
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+            let mut delete = QueryBuilder::<MySql>::new("DELETE FROM outbox_events WHERE id IN ");
+            push_ids(&mut delete, moved.iter().map(|e| e.id.to_string()));
+            delete
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        debug!(
+            "DLQ reaper: quarantined {}/{} events",
+            moved.len(),
+            entries.len()
+        );
+        Ok(moved
+            .into_iter()
+            .filter_map(|entry| {
+                event_types
+                    .get(&entry.id.to_string())
+                    .map(|t| entry.clone().with_event_type(EventType::load(t)))
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let cutoff = self.inner.config.clock.now() - cooldown;
+
+        let mut tx = self.begin().await?;
+        let ids: Vec<String> = sqlx::query_scalar(
+            r"
+            SELECT d.id
+            FROM outbox_dead_letters AS d
+            WHERE d.quarantined_at <= ?
+                AND d.revival_count < ?
+                AND NOT EXISTS (
+                    SELECT 1 FROM outbox_events AS o
+                    WHERE o.idempotency_token = d.idempotency_token
+                )
+            ORDER BY d.quarantined_at
+            LIMIT 5000
+            FOR UPDATE OF d SKIP LOCKED
+            ",
+        )
+        .bind(cutoff)
+        .bind(max_revivals)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        if !ids.is_empty() {
+            let mut insert = QueryBuilder::<MySql>::new(
+                r"
+                INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until, revival_count)
+                SELECT id, idempotency_token, event_type, payload, 'Pending', created_at, '1970-01-01 00:00:00', revival_count + 1
+                FROM outbox_dead_letters
+                WHERE id IN ",
+            );
+            push_ids(&mut insert, ids.iter().cloned());
+            insert
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| map_insert_error(&e))?;
+
+            let mut delete =
+                QueryBuilder::<MySql>::new("DELETE FROM outbox_dead_letters WHERE id IN ");
+            push_ids(&mut delete, ids.iter().cloned());
+            delete
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        if !ids.is_empty() {
+            self.wake();
+        }
+        debug!("DLQ reaper: revived {} dead letters", ids.len());
+        ids.iter().map(|id| id.parse()).collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new("SELECT id FROM outbox_events WHERE id IN ");
+        push_ids(&mut query, ids.iter().map(ToString::to_string));
+        let present: std::collections::HashSet<String> = query
+            .build_query_scalar()
+            .fetch_all(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .collect();
+        Ok(ids
+            .iter()
+            .filter(|id| !present.contains(&id.to_string()))
+            .copied()
+            .collect())
+    }
+}
+
+/// Appends a derived table with the failure data of `entries`, one
+/// `SELECT` per entry joined by `UNION ALL`, which MySQL and MariaDB both
+/// accept (unlike a `VALUES` table constructor).
+#[cfg(feature = "dlq")]
+fn push_failures(query: &mut QueryBuilder<'_, MySql>, entries: &[&DlqEntry]) {
+    query.push("(");
+    for (i, entry) in entries.iter().enumerate() {
+        if i == 0 {
+            query.push("SELECT ");
+            query.push_bind(entry.id.to_string());
+            query.push(" AS id, ");
+            query.push_bind(entry.failure_count);
+            query.push(" AS failure_count, ");
+            query.push_bind(entry.last_error.clone());
+            query.push(" AS last_error, ");
+            query.push_bind(entry.first_failed_at);
+            query.push(" AS first_failed_at");
+        } else {
+            query.push(" UNION ALL SELECT ");
+            let mut row = query.separated(", ");
+            row.push_bind(entry.id.to_string());
+            row.push_bind(entry.failure_count);
+            row.push_bind(entry.last_error.clone());
+            row.push_bind(entry.first_failed_at);
+        }
+    }
+    query.push(")");
+}
+
+/// Writes through the [`MySqlOutbox`]'s own pool and wakes this process'
+/// workers right away.
+#[async_trait]
+impl<P> OutboxWriter<P> for MySqlOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        insert_event(&self.inner.pool, event).await?;
+        self.wake();
+        Ok(())
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        insert_events(&self.inner.pool, events).await?;
+        self.wake();
+        Ok(())
+    }
+}
+
+/// Inserts events through the executor `E`, usually a [`MySqlPool`].
+///
+/// `MySqlWriter<MySqlPool>` is also an [`OutboxTxWriter`] for [`Transaction`] and
+/// [`MySqlConnection`]; those inserts go through the caller's transaction and
+/// never touch the pool.
+pub struct MySqlWriter<E>(pub E);
+
+#[async_trait]
+impl<E, P> OutboxWriter<P> for MySqlWriter<E>
+where
+    for<'c> &'c E: Executor<'c, Database = MySql>,
+    E: Send + Sync,
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        insert_event(&self.0, event).await
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        insert_events(&self.0, events).await
+    }
+}
+
+#[async_trait]
+impl<P> OutboxTxWriter<P, MySqlConnection> for MySqlWriter<MySqlPool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut MySqlConnection,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut MySqlConnection,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(tx, events).await
+    }
+}
+
+#[async_trait]
+impl<'t, P> OutboxTxWriter<P, Transaction<'t, MySql>> for MySqlWriter<MySqlPool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut Transaction<'t, MySql>,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(&mut **tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut Transaction<'t, MySql>,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(&mut **tx, events).await
+    }
+}
+
+async fn insert_event<'e, X, P>(executor: X, event: Event<P>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = MySql>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    sqlx::query(
+        r"
+        INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ",
+    )
+    .bind(event.id.to_string())
+    .bind(event.idempotency_token.as_ref().map(IdempotencyToken::as_str))
+    .bind(event.event_type.as_str())
+    .bind(Json(&event.payload))
+    .bind(event.status.as_str())
+    .bind(event.created_at)
+    .bind(event.locked_until)
+    .execute(executor)
+    .await
+    .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+/// Inserts the whole batch with one multi-row `INSERT .. VALUES`, so the
+/// insert is all-or-nothing without needing a transaction of its own. Each
+/// row takes seven placeholders, which caps a batch at 9362 events.
+async fn insert_events<'e, X, P>(executor: X, events: Vec<Event<P>>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = MySql>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut query = QueryBuilder::<MySql>::new(
+        "INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until) ",
+    );
+    query.push_values(&events, |mut row, event| {
+        row.push_bind(event.id.to_string())
+            .push_bind(
+                event
+                    .idempotency_token
+                    .as_ref()
+                    .map(|t| t.as_str().to_string()),
+            )
+            .push_bind(event.event_type.as_str().to_string())
+            .push_bind(Json(event.payload.clone()))
+            .push_bind(event.status.as_str())
+            .push_bind(event.created_at)
+            .push_bind(event.locked_until);
+    });
+    query
+        .build()
+        .execute(executor)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+/// Reports a violation of `idx_outbox_idempotency` as [`OutboxError::DuplicateEvent`].
+fn map_insert_error(e: &sqlx::Error) -> OutboxError {
+    if let Some(db) = e.as_database_error()
+        && db.is_unique_violation()
+        && db.message().contains("idx_outbox_idempotency")
+    {
+        return OutboxError::DuplicateEvent;
+    }
+    OutboxError::DatabaseError(e.to_string())
+}
+
+/// These tests need a MySQL 8 or MariaDB 10.6+ server. Point
+/// `OUTBOX_MYSQL_URL` at it (the user must be allowed to create databases)
+/// and run `cargo test -p outbox-mysql --all-features -- --ignored`. Every
+/// test works in a fresh database that it drops when it passes.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use sqlx::mysql::MySqlPoolOptions;
+    use time::OffsetDateTime;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload(u32);
+
+    struct TestDb {
+        server: MySqlPool,
+        name: String,
+        pool: MySqlPool,
+    }
+
+    impl TestDb {
+        async fn create() -> Self {
+            let url = std::env::var("OUTBOX_MYSQL_URL")
+                .unwrap_or_else(|_| "mysql://root@localhost:3306".to_string());
+            let server = MySqlPoolOptions::new()
+                .max_connections(1)
+                .connect(&url)
+                .await
+                .unwrap();
+            let name = format!("outbox_test_{}", uuid::Uuid::new_v4().simple());
+            server
+                .execute(format!("CREATE DATABASE {name}").as_str())
+                .await
+                .unwrap();
+            let pool = MySqlPoolOptions::new()
+                .connect(&format!("{}/{name}", url.trim_end_matches('/')))
+                .await
+                .unwrap();
+            crate::migrate(&pool).await.unwrap();
+            Self { server, name, pool }
+        }
+
+        fn outbox(&self, config: OutboxConfig<TestPayload>) -> MySqlOutbox<TestPayload> {
+            MySqlOutbox::new(self.pool.clone(), Arc::new(config))
+        }
+
+        async fn drop(self) {
+            self.pool.close().await;
+            self.server
+                .execute(format!("DROP DATABASE {}", self.name).as_str())
+                .await
+                .unwrap();
+        }
+    }
+
+    fn event(token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    async fn count(db: &TestDb, status: &EventStatus) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events WHERE status = ?")
+            .bind(status.as_str())
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn fetch_claims_pending_events_and_locks_them() {
+        let db = TestDb::create().await;
+        let outbox = db.outbox(OutboxConfig::default());
+        let inserted = event(Some("token-1"));
+        outbox.insert_event(inserted.clone()).await.unwrap();
+        outbox
+            .insert_events(vec![event(None), event(None)])
+            .await
+            .unwrap();
+
+        let claimed = outbox.fetch_next_to_process(2).await.unwrap();
+
+        assert_eq!(claimed.len(), 2);
+        assert!(claimed.iter().all(|e| e.status == EventStatus::Processing));
+        assert!(
+            claimed
+                .iter()
+                .all(|e| e.locked_until > OffsetDateTime::now_utc())
+        );
+        assert_eq!(count(&db, &EventStatus::Processing).await, 2);
+
+        let rest = outbox.fetch_next_to_process(10).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        let round_trip = claimed
+            .iter()
+            .chain(&rest)
+            .find(|e| e.id == inserted.id)
+            .unwrap();
+        assert_eq!(
+            round_trip
+                .idempotency_token
+                .as_ref()
+                .map(IdempotencyToken::as_str),
+            Some("token-1")
+        );
+        assert_eq!(round_trip.payload.as_value(), &TestPayload(1));
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn concurrent_fetches_skip_each_others_rows() {
+        let db = TestDb::create().await;
+        let outbox = db.outbox(OutboxConfig::default());
+        outbox
+            .insert_events((0..20).map(|_| event(None)).collect())
+            .await
+            .unwrap();
+
+        let (a, b) = tokio::join!(
+            outbox.fetch_next_to_process(15),
+            outbox.fetch_next_to_process(15)
+        );
+        let (a, b) = (a.unwrap(), b.unwrap());
+
+        assert_eq!(a.len() + b.len(), 20);
+        assert!(a.iter().all(|e| b.iter().all(|o| o.id != e.id)));
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn locks_expire_on_the_configured_clock() {
+        let db = TestDb::create().await;
+        let clock = ManualClock::default();
+        let outbox = db.outbox(OutboxConfig::default().with_clock(Arc::new(clock.clone())));
+        outbox.insert_event(event(None)).await.unwrap();
+
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_mins(6));
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn insert_rejects_duplicate_idempotency_token() {
+        let db = TestDb::create().await;
+        let outbox = db.outbox(OutboxConfig::default());
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let single = outbox.insert_event(event(Some("token-1"))).await;
+        let batch = outbox
+            .insert_events(vec![event(Some("token-2")), event(Some("token-1"))])
+            .await;
+
+        assert!(matches!(single, Err(OutboxError::DuplicateEvent)));
+        assert!(matches!(batch, Err(OutboxError::DuplicateEvent)));
+        assert_eq!(count(&db, &EventStatus::Pending).await, 1);
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn writer_inserts_within_the_callers_transaction() {
+        let db = TestDb::create().await;
+        let writer = MySqlWriter(db.pool.clone());
+
+        let mut tx = db.pool.begin().await.unwrap();
+        writer
+            .insert_events_in(&mut tx, vec![event(None), event(None)])
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(count(&db, &EventStatus::Pending).await, 0);
+
+        let mut tx = db.pool.begin().await.unwrap();
+        writer.insert_event_in(&mut tx, event(None)).await.unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(count(&db, &EventStatus::Pending).await, 1);
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn delete_garbage_removes_only_old_sent_events() {
+        let db = TestDb::create().await;
+        let clock = ManualClock::default();
+        let outbox = db.outbox(OutboxConfig::default().with_clock(Arc::new(clock.clone())));
+        let old = event(None);
+        outbox
+            .insert_events(vec![old.clone(), event(None)])
+            .await
+            .unwrap();
+        outbox
+            .update_status(&[old.id], EventStatus::Sent)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_hours(24 * 8));
+        outbox.delete_garbage().await.unwrap();
+
+        assert_eq!(count(&db, &EventStatus::Sent).await, 0);
+        assert_eq!(count(&db, &EventStatus::Pending).await, 1);
+        db.drop().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn verify_schema_accepts_migrated_database() {
+        let db = TestDb::create().await;
+        crate::verify_schema(&db.pool).await.unwrap();
+        db.drop().await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn exceeded_events_are_quarantined_and_revived() {
+        let db = TestDb::create().await;
+        let clock = ManualClock::default();
+        let outbox = db.outbox(OutboxConfig::default().with_clock(Arc::new(clock.clone())));
+        let failing = event(Some("token-1"));
+        outbox
+            .insert_events(vec![failing.clone(), event(None)])
+            .await
+            .unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..3 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+
+        let policy = DlqPolicy::new(3);
+        let drained = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 3);
+        assert!(drained[0].first_failed_at.is_some());
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+
+        let moved = outbox.quarantine_events(&drained).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(
+            outbox.missing_events(&[failing.id]).await.unwrap(),
+            vec![failing.id]
+        );
+
+        let cooldown = Duration::from_mins(10);
+        assert!(
+            outbox
+                .revive_dead_letters(cooldown, 3)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        clock.advance(cooldown);
+        assert_eq!(
+            outbox.revive_dead_letters(cooldown, 3).await.unwrap(),
+            vec![failing.id]
+        );
+        assert_eq!(count(&db, &EventStatus::Pending).await, 2);
+        db.drop().await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn unacked_claim_is_drained_again_once_reclaimed() {
+        let db = TestDb::create().await;
+        let outbox = db.outbox(OutboxConfig::default());
+        let failing = event(None);
+        outbox.insert_events(vec![failing.clone()]).await.unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..2 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(2);
+
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 1);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 1);
+        let drained = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 2);
+
+        outbox.ack_drained(&[failing.id]).await.unwrap();
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 0);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+        db.drop().await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a MySQL server at OUTBOX_MYSQL_URL"]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let db = TestDb::create().await;
+        let outbox = db.outbox(OutboxConfig::default());
+        let (taken, free) = (event(Some("token-1")), event(None));
+        let ids = [taken.id, free.id];
+        outbox.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert_eq!(revived, vec![ids[1]]);
+        assert_eq!(count(&db, &EventStatus::Pending).await, 2);
+        db.drop().await;
+    }
+}
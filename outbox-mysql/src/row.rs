@@ -0,0 +1,78 @@
+//! Conversions between outbox types and their MySQL column representation.
+//!
+//! Ids are stored as hyphenated UUID text and the status as an `enum` column
+//! holding [`EventStatus::as_str`](outbox_core::prelude::EventStatus::as_str). Lists of ids are spliced into `IN (..)`
+//! with one placeholder each by [`push_ids`], MySQL's stand-in for Postgres'
+//! `= ANY($1)`.
+
+use outbox_core::prelude::{Event, EventType, IdempotencyToken, OutboxError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::types::Json;
+use sqlx::{MySql, QueryBuilder};
+use std::fmt::Debug;
+use time::OffsetDateTime;
+
+/// Columns selected for an [`Event`], in this order:
+/// `id, idempotency_token, event_type, payload, status, created_at, locked_until`.
+pub(crate) type EventRow = (
+    String,
+    Option<String>,
+    String,
+    Json<serde_json::Value>,
+    String,
+    OffsetDateTime,
+    OffsetDateTime,
+);
+
+pub(crate) const EVENT_COLUMNS: &str =
+    "id, idempotency_token, event_type, payload, status, created_at, locked_until";
+
+/// Appends `(?, ?, ..)` with one bound placeholder per id. `ids` must not be
+/// empty: `IN ()` is a syntax error in MySQL.
+pub(crate) fn push_ids<I>(query: &mut QueryBuilder<'_, MySql>, ids: I)
+where
+    I: IntoIterator<Item = String>,
+{
+    query.push("(");
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")");
+}
+
+pub(crate) fn event_from_row<P>(row: EventRow) -> Result<Event<P>, OutboxError>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync,
+{
+    let (id, idempotency_token, event_type, Json(payload), status, created_at, locked_until) = row;
+    Ok(Event {
+        id: id.parse()?,
+        idempotency_token: idempotency_token.map(IdempotencyToken::new),
+        event_type: EventType::load(&event_type),
+        payload: serde_json::from_value(payload)
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+        status: status.parse()?,
+        created_at,
+        locked_until,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use sqlx::Execute;
+
+    #[rstest]
+    fn push_ids_binds_one_placeholder_per_id() {
+        let mut query = QueryBuilder::<MySql>::new("DELETE FROM outbox_events WHERE id IN ");
+        push_ids(&mut query, ["a".to_string(), "b".to_string()]);
+
+        assert_eq!(
+            query.build().sql(),
+            "DELETE FROM outbox_events WHERE id IN (?, ?)"
+        );
+    }
+}
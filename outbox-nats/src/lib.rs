@@ -0,0 +1,344 @@
+//! NATS JetStream transport for `outbox-core`, built on `async-nats`.
+//!
+//! [`NatsTransport`] publishes every event to a JetStream subject chosen by
+//! its event type — `outbox.<event_type>` unless
+//! [`with_subject_prefix`](NatsTransport::with_subject_prefix) or
+//! [`with_subject`](NatsTransport::with_subject) say otherwise — and waits
+//! for the stream's publish ack, so `publish` only returns `Ok` once the
+//! message is stored. The transport creates no streams; a stream has to
+//! capture the subjects before anything is published.
+//!
+//! The relay delivers at least once: an event published right before a crash
+//! is published again after the restart. Every message therefore carries the
+//! `Nats-Msg-Id` header, set to the event's idempotency token or, without
+//! one, its id. JetStream drops a message whose id it has already stored
+//! within the stream's `duplicate_window` and acks it as a duplicate, which
+//! the transport treats as a successful publish.
+
+use async_nats::header::{HeaderMap, HeaderValue, NATS_MESSAGE_ID};
+use async_nats::{ConnectOptions, Subject, jetstream};
+use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
+use outbox_core::prelude::{Event, EventType, OutboxError, Transport};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use tracing::{debug, error};
+
+pub struct NatsTransport {
+    jetstream: jetstream::Context,
+    subject_prefix: String,
+    subjects: HashMap<String, String>,
+}
+
+impl NatsTransport {
+    /// Connects to the NATS server at `url` and creates a transport that
+    /// publishes to `outbox.<event_type>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::InfrastructureError`] if the connection to the
+    /// server cannot be established.
+    pub async fn new(url: &str) -> Result<Self, OutboxError> {
+        Self::with_options(url, ConnectOptions::new()).await
+    }
+
+    /// Like [`new`](Self::new), with custom connect options such as
+    /// credentials or TLS.
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Self::new).
+    pub async fn with_options(url: &str, options: ConnectOptions) -> Result<Self, OutboxError> {
+        let client = options.connect(url).await.map_err(|e| {
+            error!("NATS connection failed: {:?}", e);
+            OutboxError::InfrastructureError("NATS connection failed".to_string())
+        })?;
+        Ok(Self {
+            jetstream: jetstream::new(client),
+            subject_prefix: "outbox".to_owned(),
+            subjects: HashMap::new(),
+        })
+    }
+
+    /// Sets the prefix of the default subjects, `<prefix>.<event_type>`.
+    /// Defaults to `outbox`.
+    #[must_use]
+    pub fn with_subject_prefix(mut self, subject_prefix: &str) -> Self {
+        subject_prefix.clone_into(&mut self.subject_prefix);
+        self
+    }
+
+    /// Publishes events of `event_type` to `subject` instead of the default
+    /// `<prefix>.<event_type>`.
+    #[must_use]
+    pub fn with_subject(mut self, event_type: &str, subject: &str) -> Self {
+        self.subjects
+            .insert(event_type.to_owned(), subject.to_owned());
+        self
+    }
+
+    /// The subject events of `event_type` are published to.
+    pub fn subject_for(&self, event_type: &EventType) -> String {
+        subject_for(&self.subject_prefix, &self.subjects, event_type)
+    }
+
+    /// Publishes one message and waits for the stream's ack.
+    async fn send<P>(&self, event: &Event<P>, headers: HeaderMap) -> Result<(), OutboxError>
+    where
+        P: Serialize,
+    {
+        let subject = Subject::validated(self.subject_for(&event.event_type)).map_err(|e| {
+            OutboxError::InfrastructureError(format!(
+                "Invalid NATS subject for event type '{}': {e}",
+                event.event_type.as_str()
+            ))
+        })?;
+        let payload = serde_json::to_vec(&event.payload)
+            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
+
+        let ack = self
+            .jetstream
+            .publish_with_headers(subject.clone(), headers, payload.into())
+            .await
+            .map_err(|e| {
+                error!("NATS publish to {} failed: {:?}", subject, e);
+                OutboxError::BrokerError(e.to_string())
+            })?
+            .await
+            .map_err(|e| {
+                error!("NATS publish to {} was not acked: {:?}", subject, e);
+                OutboxError::BrokerError(e.to_string())
+            })?;
+        if ack.duplicate {
+            debug!(
+                "Message {:?} already stored in stream {}",
+                event.id, ack.stream
+            );
+        }
+        Ok(())
+    }
+}
+
+fn subject_for(
+    subject_prefix: &str,
+    subjects: &HashMap<String, String>,
+    event_type: &EventType,
+) -> String {
+    subjects
+        .get(event_type.as_str())
+        .cloned()
+        .unwrap_or_else(|| format!("{subject_prefix}.{}", event_type.as_str()))
+}
+
+/// Adds `Nats-Msg-Id` and the `event_id`, `event_type`, `created_at` and
+/// `idempotency_token` headers to `headers`. `msg_id_suffix` is appended to
+/// the message id, so a message that must not be deduplicated against the
+/// event itself gets an id of its own.
+fn message_headers<P>(
+    event: &Event<P>,
+    mut headers: HeaderMap,
+    msg_id_suffix: &str,
+) -> Result<HeaderMap, OutboxError> {
+    let id = event.id.as_uuid().to_string();
+    let msg_id = event
+        .idempotency_token
+        .as_ref()
+        .map_or(id.as_str(), |token| token.as_str());
+    headers.insert(
+        NATS_MESSAGE_ID,
+        header_value(&format!("{msg_id}{msg_id_suffix}"))?,
+    );
+    headers.insert("event_id", header_value(&id)?);
+    headers.insert("event_type", header_value(event.event_type.as_str())?);
+    headers.insert("created_at", header_value(&event.created_at.to_string())?);
+    if let Some(token) = &event.idempotency_token {
+        headers.insert("idempotency_token", header_value(token.as_str())?);
+    }
+    Ok(headers)
+}
+
+/// Header values cannot contain line breaks.
+fn header_value(value: &str) -> Result<HeaderValue, OutboxError> {
+    value.parse().map_err(|e| {
+        OutboxError::InfrastructureError(format!("Invalid NATS header value '{value}': {e}"))
+    })
+}
+
+#[async_trait]
+impl<P> Transport<P> for NatsTransport
+where
+    P: Debug + Clone + Send + Sync + Serialize + 'static,
+{
+    async fn publish(&self, event: Event<P>) -> Result<(), OutboxError> {
+        let headers = message_headers(&event, HeaderMap::new(), "")?;
+        self.send(&event, headers).await
+    }
+
+    /// Line breaks in `dlq_last_error` are replaced with spaces, since header
+    /// values cannot contain them.
+    ///
+    /// The `Nats-Msg-Id` gets a `:dlq:<revival_count>` suffix: an event that
+    /// is revived and quarantined again within the duplicate window is a new
+    /// dead letter, not a redelivery of the previous one.
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<P>) -> Result<(), OutboxError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "dlq_failure_count",
+            header_value(&letter.failure_count.to_string())?,
+        );
+        headers.insert(
+            "dlq_quarantined_at",
+            header_value(&letter.quarantined_at.to_string())?,
+        );
+        if let Some(last_error) = &letter.last_error {
+            headers.insert(
+                "dlq_last_error",
+                header_value(&last_error.replace(['\r', '\n'], " "))?,
+            );
+        }
+        if let Some(first_failed_at) = letter.first_failed_at {
+            headers.insert(
+                "dlq_first_failed_at",
+                header_value(&first_failed_at.to_string())?,
+            );
+        }
+
+        let headers = message_headers(
+            &letter.event,
+            headers,
+            &format!(":dlq:{}", letter.revival_count),
+        )?;
+        self.send(&letter.event, headers).await
+    }
+}
+
+/// The tests that talk to a server are ignored by default. Point
+/// `OUTBOX_NATS_URL` at a NATS server with JetStream enabled and run
+/// `cargo test -p outbox-nats -- --ignored`. The test deletes the stream it
+/// created when it passes.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use outbox_core::prelude::{IdempotencyToken, Payload};
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, Serialize)]
+    struct TestPayload {
+        order: u32,
+    }
+
+    fn url() -> String {
+        std::env::var("OUTBOX_NATS_URL").unwrap_or_else(|_| "nats://127.0.0.1:4222".to_string())
+    }
+
+    fn event(event_type: &str, token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new(event_type),
+            Payload::new(TestPayload { order: 7 }),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+        headers.get(name).map(HeaderValue::as_str)
+    }
+
+    #[rstest]
+    fn subjects_are_derived_from_the_event_type() {
+        let subjects = HashMap::from([("OrderPaid".to_owned(), "payments.paid".to_owned())]);
+
+        assert_eq!(
+            subject_for("outbox", &subjects, &EventType::new("OrderCreated")),
+            "outbox.OrderCreated"
+        );
+        assert_eq!(
+            subject_for("outbox", &subjects, &EventType::new("OrderPaid")),
+            "payments.paid"
+        );
+    }
+
+    #[rstest]
+    #[case::token(Some("token-1"), "token-1")]
+    #[case::no_token(None, "")]
+    fn msg_id_is_the_token_or_the_event_id(#[case] token: Option<&str>, #[case] expected: &str) {
+        let event = event("OrderCreated", token);
+        let id = event.id.as_uuid().to_string();
+
+        let headers = message_headers(&event, HeaderMap::new(), "").unwrap();
+
+        let expected = if expected.is_empty() { &id } else { expected };
+        assert_eq!(header(&headers, "Nats-Msg-Id"), Some(expected));
+        assert_eq!(header(&headers, "event_id"), Some(id.as_str()));
+        assert_eq!(header(&headers, "event_type"), Some("OrderCreated"));
+        assert_eq!(header(&headers, "idempotency_token"), token);
+    }
+
+    #[rstest]
+    fn msg_id_suffix_is_appended() {
+        let headers = message_headers(
+            &event("OrderCreated", Some("token-1")),
+            HeaderMap::new(),
+            ":dlq:2",
+        )
+        .unwrap();
+
+        assert_eq!(header(&headers, "Nats-Msg-Id"), Some("token-1:dlq:2"));
+    }
+
+    #[rstest]
+    fn tokens_with_line_breaks_are_rejected() {
+        let result = message_headers(
+            &event("OrderCreated", Some("token\r\n1")),
+            HeaderMap::new(),
+            "",
+        );
+
+        assert!(matches!(result, Err(OutboxError::InfrastructureError(_))));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a NATS server with JetStream at OUTBOX_NATS_URL"]
+    async fn redelivered_events_are_deduplicated_by_the_stream() {
+        let prefix = format!("outbox_test_{}", std::process::id());
+        let transport = NatsTransport::new(&url())
+            .await
+            .unwrap()
+            .with_subject_prefix(&prefix);
+        let stream = transport
+            .jetstream
+            .get_or_create_stream(jetstream::stream::Config {
+                name: prefix.clone(),
+                subjects: vec![format!("{prefix}.>")],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let created = event("OrderCreated", Some("token-1"));
+
+        transport.publish(created.clone()).await.unwrap();
+        transport.publish(created.clone()).await.unwrap();
+        transport
+            .publish(event("OrderCreated", None))
+            .await
+            .unwrap();
+
+        assert_eq!(stream.get_info().await.unwrap().state.messages, 2);
+        let message = stream.get_raw_message(1).await.unwrap();
+        assert_eq!(message.subject.as_str(), format!("{prefix}.OrderCreated"));
+        assert_eq!(message.payload.as_ref(), b"{\"order\":7}");
+        assert_eq!(
+            header(&message.headers, "event_id"),
+            Some(created.id.as_uuid().to_string().as_str())
+        );
+        assert!(matches!(
+            transport.publish(event("Order Paid", None)).await,
+            Err(OutboxError::InfrastructureError(_))
+        ));
+
+        transport.jetstream.delete_stream(&prefix).await.unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+-- Token reservations made by `PostgresIdempotencyProvider`.
+--
+-- A token is reserved by inserting it with an expiry; an expired row can be
+-- re-reserved in place. Expired rows are removed by the garbage collector
+-- together with old `Sent` events.
+
+create table outbox_idempotency_tokens
+(
+    token      text        primary key,
+    expires_at timestamptz not null
+);
+
+create index idx_outbox_idempotency_tokens_expires_at
+    on outbox_idempotency_tokens (expires_at);
@@ -0,0 +1,15 @@
+-- Wake workers only when a row becomes claimable.
+--
+-- The trigger used to fire on every update, so claiming, sending and the DLQ
+-- failure counters on `outbox_events` all woke every listening worker for
+-- nothing. Only inserts and status changes back to `Pending` (requeued or
+-- revived events) produce work.
+
+drop trigger outbox_events_notify_trigger on outbox_events;
+
+create trigger outbox_events_notify_trigger
+    after insert or update of status
+    on outbox_events
+    for each row
+    when (new.status = 'Pending')
+execute function notify_outbox_event();
@@ -0,0 +1,14 @@
+-- Failure bookkeeping for the Postgres `DlqHeap` implementation.
+--
+-- `failure_count` is bumped by the worker after every failed publish and
+-- reset after a successful one. The DLQ reaper drains rows whose counter has
+-- crossed the configured threshold. The partial index keeps that scan cheap:
+-- healthy rows (counter at zero) never enter it.
+
+alter table outbox_events
+    add column failure_count integer not null default 0,
+    add column last_error    text             default null;
+
+create index idx_outbox_failure_count
+    on outbox_events (failure_count)
+    where failure_count > 0;
@@ -0,0 +1,11 @@
+-- Remembers when an event started failing, next to its latest error.
+--
+-- Set by the first `record_failure` after a success (or since insert),
+-- cleared by `record_success`, and carried into `outbox_dead_letters` when
+-- the event is quarantined.
+
+alter table outbox_events
+    add column first_failed_at timestamptz default null;
+
+alter table outbox_dead_letters
+    add column first_failed_at timestamptz default null;
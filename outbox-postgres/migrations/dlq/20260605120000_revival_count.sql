@@ -0,0 +1,12 @@
+-- Counts automatic revivals of dead letters.
+--
+-- `revive_dead_letters` moves dead letters past their cooldown back into
+-- `outbox_events` with `revival_count + 1`; `quarantine_events` carries the
+-- value back into `outbox_dead_letters`, so an event stops being revived
+-- once it reaches `dlq_max_revivals`. Operator requeues start over at zero.
+
+alter table outbox_events
+    add column revival_count integer not null default 0;
+
+alter table outbox_dead_letters
+    add column revival_count integer not null default 0;
@@ -0,0 +1,14 @@
+-- Claims taken by `drain_exceeded`.
+--
+-- Draining used to reset the failure counters straight away, so a failed
+-- quarantine lost them. A drained row now only gets `dlq_claimed_at`; the
+-- counters are reset by `ack_drained` once the quarantine committed, and
+-- `reclaim_stale` clears claims that were never acknowledged so the row is
+-- drained again. The partial index keeps that scan limited to claimed rows.
+
+alter table outbox_events
+    add column dlq_claimed_at timestamptz default null;
+
+create index idx_outbox_dlq_claimed_at
+    on outbox_events (dlq_claimed_at)
+    where dlq_claimed_at is not null;
@@ -0,0 +1,253 @@
+//! Postgres-backed [`DlqHeap`] implementation.
+//!
+//! Failure counters live on the outbox row itself, in the `failure_count`,
+//! `last_error` and `first_failed_at` columns added by the `dlq` migrations,
+//! so the whole DLQ pipeline runs on the same database as the outbox:
+//! - `record_failure` → `UPDATE .. SET failure_count = failure_count + 1`,
+//!   overwriting `last_error` and setting `first_failed_at` if unset
+//! - `record_success` → reset the counter, error, timestamp and claim
+//! - `drain_exceeded` → a single statement that selects up to the policy's
+//!   batch size of unclaimed rows whose `failure_count` reached the threshold
+//!   for their `event_type`, or whose `first_failed_at` is older than the
+//!   policy's maximum failure age, and claims them by setting
+//!   `dlq_claimed_at`. Per-type thresholds are
+//!   passed as two parallel arrays. Rows are locked with
+//!   `FOR UPDATE SKIP LOCKED`, so the same id is not returned to two
+//!   concurrent callers.
+//! - `ack_drained` → reset the counter, error, timestamp and claim of rows
+//!   still in the outbox
+//! - `reclaim_stale` → clear `dlq_claimed_at` on claims older than the
+//!   timeout, so the next drain returns those rows again
+//!
+//! The drained rows stay in `outbox_events` until
+//! [`quarantine_events`](outbox_core::prelude::OutboxStorage::quarantine_events)
+//! moves them out; the entry carries the counter and error forward.
+
+use crate::PostgresOutbox;
+use async_trait::async_trait;
+use outbox_core::prelude::{DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError};
+use serde::Serialize;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::types::uuid;
+use std::fmt::Debug;
+use std::time::Duration;
+
+#[async_trait]
+impl<P> DlqHeap for PostgresOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn record_failure(
+        &self,
+        id: EventId,
+        _event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = failure_count + 1,
+                last_error = $2,
+                first_failed_at = COALESCE(first_failed_at, $3)
+            WHERE id = $1
+            ",
+        )
+        .bind(id.as_uuid())
+        .bind(error.to_string())
+        .bind(self.inner.config.clock.now())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id = $1
+                AND failure_count > 0
+            ",
+        )
+        .bind(id.as_uuid())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
+        let threshold = i32::try_from(policy.threshold).unwrap_or(i32::MAX);
+        let (types, type_thresholds): (Vec<String>, Vec<i32>) = policy
+            .type_thresholds
+            .iter()
+            .map(|(event_type, t)| (event_type.clone(), i32::try_from(*t).unwrap_or(i32::MAX)))
+            .unzip();
+        let now = self.inner.config.clock.now();
+        let failing_since = policy.failing_since_cutoff(now);
+        let rows: Vec<(
+            uuid::Uuid,
+            i32,
+            Option<String>,
+            Option<OffsetDateTime>,
+            String,
+        )> = sqlx::query_as(
+            r"
+            WITH thresholds AS (
+                SELECT * FROM UNNEST($2::text[], $3::int4[]) AS t(event_type, threshold)
+            ),
+            exceeded AS (
+                SELECT o.id, o.failure_count, o.last_error, o.first_failed_at, o.event_type
+                FROM outbox_events AS o
+                WHERE o.failure_count > 0
+                    AND o.dlq_claimed_at IS NULL
+                    AND (
+                        o.failure_count >= COALESCE(
+                            (SELECT t.threshold FROM thresholds AS t WHERE t.event_type = o.event_type),
+                            $1
+                        )
+                        OR o.first_failed_at <= $4
+                    )
+                LIMIT $6
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE outbox_events AS o
+            SET dlq_claimed_at = $5
+            FROM exceeded AS e
+            WHERE o.id = e.id
+            RETURNING e.id, e.failure_count, e.last_error, e.first_failed_at, e.event_type
+            ",
+        )
+        .bind(threshold)
+        .bind(types)
+        .bind(type_thresholds)
+        .bind(failing_since)
+        .bind(now)
+        .bind(i64::from(policy.batch_size))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(id, failure_count, last_error, first_failed_at, event_type)| {
+                    DlqEntry::new(
+                        EventId::load(id),
+                        u32::try_from(failure_count).unwrap_or(0),
+                        last_error,
+                    )
+                    .with_first_failed_at(first_failed_at)
+                    .with_event_type(EventType::load(&event_type))
+                },
+            )
+            .collect())
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        let raw_ids: Vec<uuid::Uuid> = ids.iter().map(EventId::as_uuid).collect();
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id = ANY($1)
+            ",
+        )
+        .bind(&raw_ids)
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let Some(cutoff) = time::Duration::try_from(timeout)
+            .ok()
+            .and_then(|timeout| self.inner.config.clock.now().checked_sub(timeout))
+        else {
+            return Ok(0);
+        };
+        let result = sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET dlq_claimed_at = NULL
+            WHERE dlq_claimed_at <= $1
+            ",
+        )
+        .bind(cutoff)
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::PostgresWriter;
+    use crate::tests::pool;
+    use outbox_core::prelude::{Event, OutboxConfig, OutboxWriter, Payload};
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn drained(entries: &[DlqEntry], id: EventId) -> Option<&DlqEntry> {
+        entries.iter().find(|entry| entry.id == id)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn unacked_claim_is_drained_again_once_reclaimed() {
+        let pool = pool().await;
+        let outbox = PostgresOutbox::<serde_json::Value>::new(
+            pool.clone(),
+            Arc::new(OutboxConfig::default()),
+        );
+        let event = Event::new(
+            EventType::new("dlq-claim-test"),
+            Payload::new(serde_json::json!({ "n": 1 })),
+            None,
+        );
+        let (id, event_type) = (event.id, event.event_type.clone());
+        PostgresWriter(pool.clone())
+            .insert_event(event)
+            .await
+            .unwrap();
+        let error = OutboxError::BrokerError("nope".into());
+        for _ in 0..2 {
+            outbox
+                .record_failure(id, &event_type, &error)
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(2);
+
+        assert!(drained(&outbox.drain_exceeded(&policy).await.unwrap(), id).is_some());
+        assert!(drained(&outbox.drain_exceeded(&policy).await.unwrap(), id).is_none());
+
+        outbox.reclaim_stale(Duration::ZERO).await.unwrap();
+        let entries = outbox.drain_exceeded(&policy).await.unwrap();
+        let entry = drained(&entries, id).unwrap();
+        assert_eq!(entry.failure_count, 2);
+        assert_eq!(entry.last_error.as_deref(), Some("Broker error: nope"));
+
+        outbox.ack_drained(&[id]).await.unwrap();
+        outbox.reclaim_stale(Duration::ZERO).await.unwrap();
+        assert!(drained(&outbox.drain_exceeded(&policy).await.unwrap(), id).is_none());
+
+        sqlx::query("DELETE FROM outbox_events WHERE id = $1")
+            .bind(id.as_uuid())
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+//! Postgres-backed [`DlqStore`] implementation over `outbox_dead_letters`.
+//!
+//! Listing is ordered by `quarantined_at` newest first, which is served by
+//! `idx_outbox_dlq_quarantined_at`; the event-type filter and the per-type
+//! counts use `idx_outbox_dlq_event_type`.
+//!
+//! Requeueing is a `DELETE .. RETURNING` from the dead-letter table feeding
+//! an `INSERT` into `outbox_events`, so a dead letter is never lost or
+//! duplicated if a statement fails halfway. It moves at most 5000 rows per
+//! statement and repeats until fewer come back. Dead letters whose
+//! idempotency token is held by an event in the outbox again are left
+//! quarantined, like the reaper does when reviving. Requeued rows come back
+//! as `Pending` and unlocked, with `failure_count` and `revival_count`
+//! starting over at zero, and the insert trigger wakes the workers right
+//! away.
+
+use crate::{PostgresOutbox, map_insert_error};
+use async_trait::async_trait;
+use outbox_core::prelude::{
+    DeadLetter, DlqFilter, DlqPage, DlqStore, Event, EventId, EventType, OutboxError,
+};
+use serde::Serialize;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::types::uuid;
+use std::fmt::Debug;
+use tracing::debug;
+
+/// Upper bound on the dead letters a single requeue statement moves.
+const REQUEUE_BATCH: usize = 5000;
+
+const DEAD_LETTER_COLUMNS: &str = r"
+    id,
+    idempotency_token,
+    event_type,
+    payload,
+    original_status AS status,
+    created_at,
+    locked_until,
+    failure_count,
+    last_error,
+    first_failed_at,
+    quarantined_at,
+    revival_count
+";
+
+#[derive(sqlx::FromRow)]
+struct DeadLetterRow<P> {
+    #[sqlx(flatten)]
+    event: Event<P>,
+    failure_count: i32,
+    last_error: Option<String>,
+    first_failed_at: Option<OffsetDateTime>,
+    quarantined_at: OffsetDateTime,
+    revival_count: i32,
+}
+
+impl<P> From<DeadLetterRow<P>> for DeadLetter<P> {
+    fn from(row: DeadLetterRow<P>) -> Self {
+        DeadLetter::new(
+            row.event,
+            u32::try_from(row.failure_count).unwrap_or(0),
+            row.last_error,
+            row.quarantined_at,
+        )
+        .with_first_failed_at(row.first_failed_at)
+        .with_revival_count(u32::try_from(row.revival_count).unwrap_or(0))
+    }
+}
+
+#[async_trait]
+impl<P> DlqStore<P> for PostgresOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + for<'de> serde::Deserialize<'de> + Unpin + 'static,
+{
+    async fn list(
+        &self,
+        filter: &DlqFilter,
+        page: DlqPage,
+    ) -> Result<Vec<DeadLetter<P>>, OutboxError> {
+        let rows = sqlx::query_as::<_, DeadLetterRow<P>>(&format!(
+            r"
+            SELECT {DEAD_LETTER_COLUMNS}
+            FROM outbox_dead_letters
+            WHERE ($1::uuid[] IS NULL OR id = ANY($1))
+                AND ($2::text IS NULL OR event_type = $2)
+                AND ($3::timestamptz IS NULL OR quarantined_at >= $3)
+                AND ($4::timestamptz IS NULL OR quarantined_at < $4)
+            ORDER BY quarantined_at DESC, id
+            LIMIT $5 OFFSET $6
+            "
+        ))
+        .bind(filter_ids(filter))
+        .bind(filter.event_type.as_deref())
+        .bind(filter.quarantined_after)
+        .bind(filter.quarantined_before)
+        .bind(i64::from(page.limit))
+        .bind(i64::try_from(page.offset).unwrap_or(i64::MAX))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(rows.into_iter().map(DeadLetter::from).collect())
+    }
+
+    async fn get(&self, id: EventId) -> Result<Option<DeadLetter<P>>, OutboxError> {
+        let row = sqlx::query_as::<_, DeadLetterRow<P>>(&format!(
+            "SELECT {DEAD_LETTER_COLUMNS} FROM outbox_dead_letters WHERE id = $1"
+        ))
+        .bind(id.as_uuid())
+        .fetch_optional(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(DeadLetter::from))
+    }
+
+    async fn count_by_event_type(&self) -> Result<Vec<(EventType, u64)>, OutboxError> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            r"
+            SELECT event_type, COUNT(*)
+            FROM outbox_dead_letters
+            GROUP BY event_type
+            ORDER BY COUNT(*) DESC, event_type
+            ",
+        )
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(event_type, count)| {
+                (
+                    EventType::load(&event_type),
+                    u64::try_from(count).unwrap_or(0),
+                )
+            })
+            .collect())
+    }
+
+    async fn delete(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let raw_ids: Vec<uuid::Uuid> = ids.iter().map(EventId::as_uuid).collect();
+        let result = sqlx::query("DELETE FROM outbox_dead_letters WHERE id = ANY($1)")
+            .bind(&raw_ids)
+            .execute(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        debug!("DLQ: deleted {} dead letters", result.rows_affected());
+        Ok(result.rows_affected())
+    }
+
+    async fn requeue(&self, filter: &DlqFilter) -> Result<Vec<EventId>, OutboxError> {
+        let ids = filter_ids(filter);
+        let mut requeued = Vec::new();
+        loop {
+            let batch: Vec<uuid::Uuid> = sqlx::query_scalar(
+                r"
+                WITH moved AS (
+                    DELETE FROM outbox_dead_letters
+                    WHERE id IN (
+                        SELECT d.id FROM outbox_dead_letters AS d
+                        WHERE ($1::uuid[] IS NULL OR d.id = ANY($1))
+                            AND ($2::text IS NULL OR d.event_type = $2)
+                            AND ($3::timestamptz IS NULL OR d.quarantined_at >= $3)
+                            AND ($4::timestamptz IS NULL OR d.quarantined_at < $4)
+                            AND NOT EXISTS (
+                                SELECT 1 FROM outbox_events AS o
+                                WHERE o.idempotency_token = d.idempotency_token
+                            )
+                        ORDER BY d.quarantined_at
+                        LIMIT $5
+                        FOR UPDATE SKIP LOCKED
+                    )
+                    RETURNING id, idempotency_token, event_type, payload, created_at
+                )
+                INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+                SELECT id, idempotency_token, event_type, payload, 'Pending'::status, created_at, '-infinity'::timestamptz
+                FROM moved
+                RETURNING id
+                ",
+            )
+            .bind(ids.as_deref())
+            .bind(filter.event_type.as_deref())
+            .bind(filter.quarantined_after)
+            .bind(filter.quarantined_before)
+            .bind(i64::try_from(REQUEUE_BATCH).unwrap_or(i64::MAX))
+            .fetch_all(&self.inner.pool)
+            .await
+            .map_err(|e| map_insert_error(&e))?;
+
+            let done = batch.len() < REQUEUE_BATCH;
+            requeued.extend(batch.into_iter().map(EventId::load));
+            if done {
+                break;
+            }
+        }
+
+        debug!("DLQ: requeued {} dead letters", requeued.len());
+        Ok(requeued)
+    }
+
+    async fn requeue_with_payload(&self, id: EventId, payload: P) -> Result<bool, OutboxError> {
+        let requeued: Option<uuid::Uuid> = sqlx::query_scalar(
+            r"
+            WITH moved AS (
+                DELETE FROM outbox_dead_letters
+                WHERE id = $1
+                RETURNING id, idempotency_token, event_type, created_at
+            )
+            INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+            SELECT id, idempotency_token, event_type, $2::jsonb, 'Pending'::status, created_at, '-infinity'::timestamptz
+            FROM moved
+            RETURNING id
+            ",
+        )
+        .bind(id.as_uuid())
+        .bind(
+            serde_json::to_value(&payload)
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+        )
+        .fetch_optional(&self.inner.pool)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+        Ok(requeued.is_some())
+    }
+}
+
+fn filter_ids(filter: &DlqFilter) -> Option<Vec<uuid::Uuid>> {
+    filter
+        .ids
+        .as_ref()
+        .map(|ids| ids.iter().map(EventId::as_uuid).collect())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::PostgresWriter;
+    use crate::tests::pool;
+    use outbox_core::prelude::{
+        DlqEntry, IdempotencyToken, OutboxConfig, OutboxStorage, OutboxWriter, Payload,
+    };
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn requeue_skips_dead_letters_whose_token_is_taken_again() {
+        let pool = pool().await;
+        let outbox = PostgresOutbox::<serde_json::Value>::new(
+            pool.clone(),
+            Arc::new(OutboxConfig::default()),
+        );
+        let token = format!("requeue-test-{}", uuid::Uuid::new_v4());
+        let event = |token: Option<&str>| {
+            Event::new(
+                EventType::new("requeue-test"),
+                Payload::new(serde_json::json!({ "n": 1 })),
+                token.map(|t| IdempotencyToken::new(t.to_string())),
+            )
+        };
+        let (taken, free) = (event(Some(&token)), event(None));
+        let ids = [taken.id, free.id];
+        let writer = PostgresWriter(pool.clone());
+        writer.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        let again = event(Some(&token));
+        let again_id = again.id;
+        writer.insert_event(again).await.unwrap();
+
+        let requeued = outbox
+            .requeue(&DlqFilter::all().with_ids(ids.to_vec()))
+            .await
+            .unwrap();
+
+        assert_eq!(requeued, vec![ids[1]]);
+        assert!(outbox.get(ids[0]).await.unwrap().is_some());
+
+        sqlx::query("DELETE FROM outbox_events WHERE id = ANY($1)")
+            .bind([ids[1].as_uuid(), again_id.as_uuid()])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM outbox_dead_letters WHERE id = $1")
+            .bind(ids[0].as_uuid())
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
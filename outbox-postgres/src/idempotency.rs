@@ -0,0 +1,209 @@
+//! Postgres-backed [`IdempotencyStorageProvider`].
+//!
+//! Tokens are reserved in the `outbox_idempotency_tokens` table with an
+//! expiry. A reservation is a single `INSERT .. ON CONFLICT` statement, so
+//! concurrent producers racing for the same token are serialized by the
+//! primary key and only one of them wins. An expired row is taken over by
+//! the next reservation of its token, so expiry is correct without any
+//! cleanup; the outbox's garbage collector only reclaims the space of tokens
+//! that are never reserved again, up to 5000 per pass.
+
+use async_trait::async_trait;
+use outbox_core::prelude::{
+    Clock, IdempotencyStorageProvider, IdempotencyToken, OutboxError, SystemClock,
+};
+use sqlx::PgPool;
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Settings of a [`PostgresIdempotencyProvider`].
+#[derive(Debug)]
+pub struct PostgresTokenConfig {
+    /// How long a reserved token blocks duplicates.
+    pub ttl: Duration,
+}
+
+impl Default for PostgresTokenConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_hours(24),
+        }
+    }
+}
+
+/// Reserves idempotency tokens in the `outbox_idempotency_tokens` table.
+///
+/// The table is created by [`migrate`](crate::migrate).
+pub struct PostgresIdempotencyProvider {
+    pool: PgPool,
+    config: PostgresTokenConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostgresIdempotencyProvider {
+    /// Creates a provider that reserves tokens through `pool`.
+    pub fn new(pool: PgPool, config: PostgresTokenConfig) -> Self {
+        Self {
+            pool,
+            config,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets where reservation and expiry times come from. Pass the
+    /// [`OutboxConfig::clock`](outbox_core::prelude::OutboxConfig::clock) of
+    /// the outbox, so tests driving it with a `ManualClock` drive token
+    /// expiry too. Defaults to [`SystemClock`].
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Deletes up to 5000 expired tokens and returns how many were removed.
+    ///
+    /// The outbox's garbage collector already runs the same delete on every
+    /// pass; call this directly only to clean up between passes, e.g. after
+    /// a large import.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::DatabaseError`] if the delete fails.
+    pub async fn delete_expired_tokens(&self) -> Result<u64, OutboxError> {
+        delete_expired(&self.pool, self.clock.now()).await
+    }
+}
+
+/// Deletes up to 5000 tokens that expired at or before `now`.
+pub(crate) async fn delete_expired(pool: &PgPool, now: OffsetDateTime) -> Result<u64, OutboxError> {
+    let result = sqlx::query(
+        r"
+        DELETE
+        FROM outbox_idempotency_tokens
+        WHERE token IN (
+            SELECT token FROM outbox_idempotency_tokens
+            WHERE expires_at <= $1
+            LIMIT 5000
+        )",
+    )
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    debug!(
+        "Deleted {} expired idempotency tokens",
+        result.rows_affected()
+    );
+    Ok(result.rows_affected())
+}
+
+#[async_trait]
+impl IdempotencyStorageProvider for PostgresIdempotencyProvider {
+    async fn try_reserve(&self, token: &IdempotencyToken) -> Result<bool, OutboxError> {
+        let reserved: Option<String> = sqlx::query_scalar(
+            r"
+            INSERT INTO outbox_idempotency_tokens (token, expires_at)
+            VALUES ($1, $3 + (INTERVAL '1 second' * $2))
+            ON CONFLICT (token) DO UPDATE
+                SET expires_at = EXCLUDED.expires_at
+                WHERE outbox_idempotency_tokens.expires_at <= $3
+            RETURNING token
+            ",
+        )
+        .bind(token.as_str())
+        .bind(self.config.ttl.as_secs_f64())
+        .bind(self.clock.now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(reserved.is_some())
+    }
+
+    async fn release(&self, token: &IdempotencyToken) -> Result<(), OutboxError> {
+        sqlx::query("DELETE FROM outbox_idempotency_tokens WHERE token = $1")
+            .bind(token.as_str())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::tests::pool;
+    use outbox_core::prelude::ManualClock;
+    use rstest::rstest;
+
+    async fn provider(clock: Arc<ManualClock>) -> PostgresIdempotencyProvider {
+        PostgresIdempotencyProvider::new(
+            pool().await,
+            PostgresTokenConfig {
+                ttl: Duration::from_mins(1),
+            },
+        )
+        .with_clock(clock)
+    }
+
+    fn token() -> IdempotencyToken {
+        IdempotencyToken::new(uuid::Uuid::now_v7().to_string())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn reserved_token_is_a_duplicate_until_it_expires() {
+        let clock = Arc::new(ManualClock::new(OffsetDateTime::now_utc()));
+        let provider = provider(clock.clone()).await;
+        let token = token();
+
+        assert!(provider.try_reserve(&token).await.unwrap());
+        assert!(!provider.try_reserve(&token).await.unwrap());
+        assert!(provider.try_reserve(&self::token()).await.unwrap());
+
+        clock.advance(Duration::from_mins(1));
+        assert!(provider.try_reserve(&token).await.unwrap());
+        assert!(!provider.try_reserve(&token).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn released_token_can_be_reserved_again() {
+        let provider = provider(Arc::new(ManualClock::new(OffsetDateTime::now_utc()))).await;
+        let token = token();
+        assert!(provider.try_reserve(&token).await.unwrap());
+
+        provider.release(&token).await.unwrap();
+
+        assert!(provider.try_reserve(&token).await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn delete_expired_tokens_keeps_live_ones() {
+        let clock = Arc::new(ManualClock::new(OffsetDateTime::now_utc()));
+        let provider = provider(clock.clone()).await;
+        let (expired, live) = (token(), token());
+        assert!(provider.try_reserve(&expired).await.unwrap());
+        clock.advance(Duration::from_secs(30));
+        assert!(provider.try_reserve(&live).await.unwrap());
+        clock.advance(Duration::from_secs(30));
+
+        assert!(provider.delete_expired_tokens().await.unwrap() >= 1);
+
+        let remaining: Vec<String> =
+            sqlx::query_scalar("SELECT token FROM outbox_idempotency_tokens WHERE token = ANY($1)")
+                .bind(vec![expired.as_str(), live.as_str()])
+                .fetch_all(&provider.pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, vec![live.as_str().to_owned()]);
+    }
+}
@@ -1,11 +1,25 @@
+#[cfg(feature = "dlq")]
+mod dlq;
+#[cfg(feature = "dlq")]
+mod dlq_store;
+mod idempotency;
+mod migrate;
+
+pub use idempotency::{PostgresIdempotencyProvider, PostgresTokenConfig};
+pub use migrate::{migrate, verify_schema};
+
 use async_trait::async_trait;
 use outbox_core::prelude::*;
 use serde::Serialize;
 use sqlx::postgres::PgListener;
 use sqlx::types::uuid;
-use sqlx::{Executor, PgPool, Postgres};
+use sqlx::{Executor, PgConnection, PgPool, Postgres, Transaction};
+#[cfg(feature = "dlq")]
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+#[cfg(feature = "dlq")]
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tracing::debug;
 
@@ -51,12 +65,12 @@ where
             r"
                 UPDATE outbox_events
                 SET status = 'Processing',
-                    locked_until = NOW() + (INTERVAL '1 minute' * $2)
+                    locked_until = $3 + (INTERVAL '1 minute' * $2)
                 WHERE id IN (
                     SELECT id
                     FROM outbox_events
                     WHERE status='Pending'
-                        OR (status='Processing' AND locked_until < NOW())
+                        OR (status='Processing' AND locked_until < $3)
                     ORDER BY locked_until ASC
                     LIMIT $1
                     FOR UPDATE SKIP LOCKED
@@ -73,6 +87,7 @@ where
         )
         .bind(i64::from(limit))
         .bind(self.inner.config.lock_timeout_mins)
+        .bind(self.inner.config.clock.now())
         .fetch_all(&self.inner.pool)
         .await
         .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
@@ -93,6 +108,7 @@ where
     }
 
     async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let now = self.inner.config.clock.now();
         let result = sqlx::query(
             r"
             DELETE
@@ -100,11 +116,12 @@ where
             WHERE id IN (
                 SELECT id FROM outbox_events
                 WHERE status='Sent'
-                    AND created_at < now() - (INTERVAL '1 day' * $1)
+                    AND created_at < $2 - (INTERVAL '1 day' * $1)
                 LIMIT 5000
             )",
         )
         .bind(self.inner.config.retention_days)
+        .bind(now)
         .execute(&self.inner.pool)
         .await
         .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
@@ -112,6 +129,7 @@ where
             "Garbage collector: deleted {} old messages",
             result.rows_affected()
         );
+        idempotency::delete_expired(&self.inner.pool, now).await?;
         Ok(())
     }
 
@@ -145,9 +163,72 @@ where
     }
 
     #[cfg(feature = "dlq")]
-    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<(), OutboxError> {
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        let raw_ids: Vec<uuid::Uuid> = ids.iter().map(EventId::as_uuid).collect();
+        sqlx::query_as::<_, Event<P>>(
+            r"
+            SELECT
+                id,
+                idempotency_token,
+                event_type,
+                payload,
+                status,
+                created_at,
+                locked_until
+            FROM outbox_events
+            WHERE id = ANY($1)
+            ",
+        )
+        .bind(&raw_ids)
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let raw_ids: Vec<uuid::Uuid> = ids.iter().map(EventId::as_uuid).collect();
+        let result = sqlx::query("DELETE FROM outbox_events WHERE id = ANY($1)")
+            .bind(&raw_ids)
+            .execute(&self.inner.pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "DLQ reaper: deleted {} events published to the DLQ destination",
+            result.rows_affected()
+        );
+        Ok(result.rows_affected())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let result = sqlx::query(
+            r"
+            DELETE
+            FROM outbox_dead_letters
+            WHERE id IN (
+                SELECT id FROM outbox_dead_letters
+                WHERE quarantined_at < $2 - (INTERVAL '1 day' * $1)
+                ORDER BY quarantined_at
+                LIMIT 5000
+            )",
+        )
+        .bind(self.inner.config.dlq_retention_days)
+        .bind(self.inner.config.clock.now())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: purged {} expired dead letters",
+            result.rows_affected()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
         if entries.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
         let ids: Vec<uuid::Uuid> = entries.iter().map(|e| e.id.as_uuid()).collect();
         let failure_counts: Vec<i32> = entries
@@ -156,7 +237,9 @@ where
             .collect();
         let last_errors: Vec<Option<String>> =
             entries.iter().map(|e| e.last_error.clone()).collect();
-        let result = sqlx::query(
+        let first_failed_ats: Vec<Option<sqlx::types::time::OffsetDateTime>> =
+            entries.iter().map(|e| e.first_failed_at).collect();
+        let moved: Vec<(uuid::Uuid, String)> = sqlx::query_as(
             r"
             WITH deleted AS (
                 DELETE FROM outbox_events
@@ -168,7 +251,8 @@ where
                     payload,
                     status,
                     created_at,
-                    locked_until
+                    locked_until,
+                    revival_count
             )
             INSERT INTO outbox_dead_letters (
                 id,
@@ -179,7 +263,10 @@ where
                 created_at,
                 locked_until,
                 failure_count,
-                last_error
+                last_error,
+                first_failed_at,
+                revival_count,
+                quarantined_at
             )
             SELECT
                 d.id,
@@ -190,29 +277,106 @@ where
                 d.created_at,
                 d.locked_until,
                 f.failure_count,
-                f.last_error
+                f.last_error,
+                f.first_failed_at,
+                d.revival_count,
+                $5
             FROM deleted AS d
-            JOIN unnest($1::uuid[], $2::int[], $3::text[])
-                    AS f(id, failure_count, last_error)
+            JOIN unnest($1::uuid[], $2::int[], $3::text[], $4::timestamptz[])
+                    AS f(id, failure_count, last_error, first_failed_at)
                 ON d.id = f.id
+            RETURNING id, event_type
             ",
         )
         .bind(&ids)
         .bind(&failure_counts)
         .bind(&last_errors)
-        .execute(&self.inner.pool)
+        .bind(&first_failed_ats)
+        .bind(self.inner.config.clock.now())
+        .fetch_all(&self.inner.pool)
         .await
         .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
 
         debug!(
             "DLQ reaper: quarantined {}/{} events",
-            result.rows_affected(),
+            moved.len(),
             entries.len()
         );
-        Ok(())
+        let event_types: HashMap<uuid::Uuid, String> = moved.into_iter().collect();
+        Ok(entries
+            .iter()
+            .filter_map(|entry| {
+                event_types
+                    .get(&entry.id.as_uuid())
+                    .map(|t| entry.clone().with_event_type(EventType::load(t)))
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let revived: Vec<uuid::Uuid> = sqlx::query_scalar(
+            r"
+            WITH moved AS (
+                DELETE FROM outbox_dead_letters
+                WHERE id IN (
+                    SELECT d.id FROM outbox_dead_letters AS d
+                    WHERE d.quarantined_at <= $3 - (INTERVAL '1 second' * $1)
+                        AND d.revival_count < $2
+                        AND NOT EXISTS (
+                            SELECT 1 FROM outbox_events AS o
+                            WHERE o.idempotency_token = d.idempotency_token
+                        )
+                    ORDER BY d.quarantined_at
+                    LIMIT 5000
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, idempotency_token, event_type, payload, created_at, revival_count
+            )
+            INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until, revival_count)
+            SELECT id, idempotency_token, event_type, payload, 'Pending'::status, created_at, '-infinity'::timestamptz, revival_count + 1
+            FROM moved
+            RETURNING id
+            ",
+        )
+        .bind(i64::try_from(cooldown.as_secs()).unwrap_or(i64::MAX))
+        .bind(i32::try_from(max_revivals).unwrap_or(i32::MAX))
+        .bind(self.inner.config.clock.now())
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+        debug!("DLQ reaper: revived {} dead letters", revived.len());
+        Ok(revived.into_iter().map(EventId::load).collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        let raw_ids: Vec<uuid::Uuid> = ids.iter().map(EventId::as_uuid).collect();
+        let missing: Vec<uuid::Uuid> = sqlx::query_scalar(
+            r"
+            SELECT t.id
+            FROM UNNEST($1::uuid[]) AS t(id)
+            WHERE NOT EXISTS (SELECT 1 FROM outbox_events o WHERE o.id = t.id)
+            ",
+        )
+        .bind(&raw_ids)
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(missing.into_iter().map(EventId::load).collect())
     }
 }
 
+/// Inserts events through the executor `E`, usually a [`PgPool`].
+///
+/// `PostgresWriter<PgPool>` is also an [`OutboxTxWriter`] for [`Transaction`] and
+/// [`PgConnection`]; those inserts go through the caller's transaction and
+/// never touch the pool.
 pub struct PostgresWriter<E>(pub E);
 
 #[async_trait]
@@ -223,23 +387,295 @@ where
     P: Debug + Clone + Serialize + Send + Sync + 'static,
 {
     async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
-        sqlx::query(
-            r"
+        insert_event(&self.0, event).await
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        insert_events(&self.0, events).await
+    }
+}
+
+#[async_trait]
+impl<P> OutboxTxWriter<P, PgConnection> for PostgresWriter<PgPool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut PgConnection,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut PgConnection,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(tx, events).await
+    }
+}
+
+#[async_trait]
+impl<'t, P> OutboxTxWriter<P, Transaction<'t, Postgres>> for PostgresWriter<PgPool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut Transaction<'t, Postgres>,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(&mut **tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut Transaction<'t, Postgres>,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(&mut **tx, events).await
+    }
+}
+
+async fn insert_event<'e, X, P>(executor: X, event: Event<P>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = Postgres>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    sqlx::query(
+        r"
         INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
         VALUES ($1, $2, $3, $4, $5, $6, $7)
         ",
-        )
-            .bind(event.id.as_uuid())
-            .bind(event.idempotency_token)
-            .bind(event.event_type.as_str())
-            .bind(serde_json::to_value(&event.payload).map_err(|e| OutboxError::DatabaseError(e.to_string()))?)
-            .bind(event.status)
-            .bind(event.created_at)
-            .bind(event.locked_until)
-            .execute(&self.0)
+    )
+        .bind(event.id.as_uuid())
+        .bind(event.idempotency_token)
+        .bind(event.event_type.as_str())
+        .bind(serde_json::to_value(&event.payload).map_err(|e| OutboxError::DatabaseError(e.to_string()))?)
+        .bind(event.status)
+        .bind(event.created_at)
+        .bind(event.locked_until)
+        .execute(executor)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+async fn insert_events<'e, X, P>(executor: X, events: Vec<Event<P>>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = Postgres>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    if events.is_empty() {
+        return Ok(());
+    }
+    let len = events.len();
+    let mut ids = Vec::with_capacity(len);
+    let mut tokens = Vec::with_capacity(len);
+    let mut event_types = Vec::with_capacity(len);
+    let mut payloads = Vec::with_capacity(len);
+    let mut statuses = Vec::with_capacity(len);
+    let mut created_ats = Vec::with_capacity(len);
+    let mut locked_untils = Vec::with_capacity(len);
+    for event in events {
+        ids.push(event.id.as_uuid());
+        tokens.push(event.idempotency_token.map(|t| t.0));
+        event_types.push(event.event_type.as_str().to_owned());
+        payloads.push(
+            serde_json::to_value(&event.payload)
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+        );
+        statuses.push(event.status);
+        created_ats.push(event.created_at);
+        locked_untils.push(event.locked_until);
+    }
+
+    sqlx::query(
+        r"
+        INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+        SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::jsonb[], $5::status[], $6::timestamptz[], $7::timestamptz[])
+        ",
+    )
+        .bind(&ids)
+        .bind(&tokens)
+        .bind(&event_types)
+        .bind(&payloads)
+        .bind(&statuses)
+        .bind(&created_ats)
+        .bind(&locked_untils)
+        .execute(executor)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+/// Reports a violation of `idx_outbox_idempotency` as [`OutboxError::DuplicateEvent`].
+fn map_insert_error(e: &sqlx::Error) -> OutboxError {
+    if let Some(db) = e.as_database_error()
+        && db.is_unique_violation()
+        && db.constraint() == Some("idx_outbox_idempotency")
+    {
+        return OutboxError::DuplicateEvent;
+    }
+    OutboxError::DatabaseError(e.to_string())
+}
+
+/// The tests need a database and are ignored by default. Point
+/// `OUTBOX_POSTGRES_URL` at an empty database and run
+/// `cargo test -p outbox-postgres -- --ignored`.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+pub(crate) mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::time::Duration;
+
+    pub(crate) async fn pool() -> PgPool {
+        let url = std::env::var("OUTBOX_POSTGRES_URL")
+            .unwrap_or_else(|_| "postgres://postgres@127.0.0.1:5432/postgres".to_string());
+        let pool = PgPool::connect(&url).await.unwrap();
+        crate::migrate(&pool).await.unwrap();
+        pool
+    }
+
+    async fn notified(listener: &mut PgListener) -> bool {
+        tokio::time::timeout(Duration::from_millis(300), listener.recv())
             .await
-            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+            .is_ok()
+    }
 
-        Ok(())
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn only_rows_that_become_pending_notify_workers() {
+        let pool = pool().await;
+        let outbox = PostgresOutbox::<serde_json::Value>::new(
+            pool.clone(),
+            Arc::new(OutboxConfig::default()),
+        );
+        let mut listener = PgListener::connect_with(&pool).await.unwrap();
+        listener.listen("outbox_event").await.unwrap();
+
+        let event = Event::new(
+            EventType::new("notify-test"),
+            Payload::new(serde_json::json!({ "n": 1 })),
+            None,
+        );
+        let id = event.id;
+        PostgresWriter(pool.clone())
+            .insert_event(event)
+            .await
+            .unwrap();
+        assert!(notified(&mut listener).await);
+
+        sqlx::query("UPDATE outbox_events SET locked_until = $2 WHERE id = $1")
+            .bind(id.as_uuid())
+            .bind(time::OffsetDateTime::now_utc())
+            .execute(&pool)
+            .await
+            .unwrap();
+        outbox
+            .update_status(&[id], EventStatus::Sent)
+            .await
+            .unwrap();
+        assert!(!notified(&mut listener).await);
+
+        outbox
+            .update_status(&[id], EventStatus::Pending)
+            .await
+            .unwrap();
+        assert!(notified(&mut listener).await);
+
+        sqlx::query("DELETE FROM outbox_events WHERE id = $1")
+            .bind(id.as_uuid())
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn delete_garbage_removes_expired_tokens() {
+        let pool = pool().await;
+        let outbox = PostgresOutbox::<serde_json::Value>::new(
+            pool.clone(),
+            Arc::new(OutboxConfig::default()),
+        );
+        let (expired, live) = (
+            format!("gc-test-{}", uuid::Uuid::new_v4()),
+            format!("gc-test-{}", uuid::Uuid::new_v4()),
+        );
+        let now = time::OffsetDateTime::now_utc();
+        sqlx::query(
+            "INSERT INTO outbox_idempotency_tokens (token, expires_at) VALUES ($1, $2), ($3, $4)",
+        )
+        .bind(&expired)
+        .bind(now - time::Duration::minutes(1))
+        .bind(&live)
+        .bind(now + time::Duration::minutes(1))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        outbox.delete_garbage().await.unwrap();
+
+        let remaining: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM outbox_idempotency_tokens WHERE token = ANY($1) RETURNING token",
+        )
+        .bind([&expired, &live])
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, vec![live]);
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest]
+    #[tokio::test]
+    #[ignore = "needs a Postgres database at OUTBOX_POSTGRES_URL"]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let pool = pool().await;
+        let outbox = PostgresOutbox::<serde_json::Value>::new(
+            pool.clone(),
+            Arc::new(OutboxConfig::default()),
+        );
+        let token = format!("revive-test-{}", uuid::Uuid::new_v4());
+        let event = |token: Option<&str>| {
+            Event::new(
+                EventType::new("revive-test"),
+                Payload::new(serde_json::json!({ "n": 1 })),
+                token.map(|t| IdempotencyToken::new(t.to_string())),
+            )
+        };
+        let (taken, free) = (event(Some(&token)), event(None));
+        let ids = [taken.id, free.id];
+        let writer = PostgresWriter(pool.clone());
+        writer.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        let again = event(Some(&token));
+        let again_id = again.id;
+        writer.insert_event(again).await.unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert!(revived.contains(&ids[1]));
+        assert!(!revived.contains(&ids[0]));
+
+        sqlx::query("DELETE FROM outbox_events WHERE id = ANY($1)")
+            .bind([ids[1].as_uuid(), again_id.as_uuid()])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM outbox_dead_letters WHERE id = $1")
+            .bind(ids[0].as_uuid())
+            .execute(&pool)
+            .await
+            .unwrap();
     }
 }
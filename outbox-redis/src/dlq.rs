@@ -1,39 +1,134 @@
 //! Redis-backed [`DlqHeap`] implementation.
 //!
 //! Failure counters are stored in a single Redis sorted set
-//! (`<key_prefix>:dlq`), where:
+//! (`{<key_prefix>}:dlq`), where:
 //! - `member` = event id (UUID as string)
 //! - `score`  = current failure count
 //!
-//! - `record_failure` → `ZINCRBY` (atomic increment, creates entry if absent)
-//! - `record_success` → `ZREM`
-//! - `drain_exceeded` → atomic Lua script: read entries with `score >= threshold`,
-//!   remove them in the same pass. This guarantees the same id is not returned
-//!   to two concurrent callers.
+//! The latest error, the time of the first failure and the event type live in
+//! one hash per failing event (`{<key_prefix>}:dlq:failure:<id>`, fields
+//! `last_error`, `first_failed_at` as unix seconds and `event_type`). Every
+//! write to a hash renews its 30-day TTL, so the hash of a counter that stops
+//! failing and is never reconciled does not stay forever.
 //!
-//! `DlqEntry::last_error` is always `None` here: the [`DlqHeap`] trait does not
-//! pass an error string into `record_failure`, so there is nothing to persist.
+//! A third sorted set (`{<key_prefix>}:dlq:touched`, score = time of the last
+//! failure as unix seconds) lets idle counters be expired without scanning
+//! every hash.
+//!
+//! Drained entries are claimed rather than deleted: they move into a second
+//! sorted set (`{<key_prefix>}:dlq:pending`, score = claim time as unix
+//! seconds) and their failure count is parked in the hash under
+//! `failure_count`, until the reaper acknowledges or reclaims them.
+//!
+//! The prefix is wrapped in a hash tag, so all of these keys land in one
+//! Redis Cluster slot. The scripts receive the failure hash prefix as a key
+//! and append the ids themselves.
+//!
+//! - `record_failure` → Lua script: `ZINCRBY` (atomic increment, creates entry
+//!   if absent), `HSET last_error event_type`, `HSETNX first_failed_at`,
+//!   `EXPIRE`, `ZADD` to the touched set
+//! - `record_success` → `ZREM` from the counter and touched sets + `DEL` of
+//!   the failure hash in one `MULTI`
+//! - `drain_exceeded` → atomic Lua script: read candidate entries together
+//!   with their failure hashes, keep those whose score reached the threshold
+//!   for their `event_type` or whose `first_failed_at` is past the policy's
+//!   maximum failure age, and move them to the pending set in the same pass.
+//!   Without an age rule only scores at or above the lowest threshold are
+//!   scanned. Candidates are read in `ZRANGEBYSCORE .. LIMIT` pages and the
+//!   script stops once the policy's batch size is claimed. This guarantees the same id is not returned to two concurrent
+//!   callers.
+//! - `ack_drained` / `forget` → `ZREM` from all three sets + `DEL` of the
+//!   failure hashes in one `MULTI`
+//! - `reclaim_stale` → atomic Lua script: every pending claim older than the
+//!   timeout is added back to the counter set with its parked count (on top
+//!   of any failures recorded since) and removed from the pending set. A
+//!   claim whose hash was cleared by `record_success` is simply dropped.
+//!   Reclaimed entries count as touched at reclaim time.
+//! - `tracked_ids` → one `ZSCAN` page over the counter set
+//! - `expire_idle` → atomic Lua script: every id whose touched score is older
+//!   than the window is removed from the counter set together with its hash,
+//!   unless it is currently claimed.
 
 use crate::RedisProvider;
 use async_trait::async_trait;
-use outbox_core::prelude::{DlqEntry, DlqHeap, EventId, OutboxError};
+use outbox_core::prelude::{DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError};
+use std::time::Duration;
+use time::OffsetDateTime;
 use tracing::error;
 use uuid::Uuid;
 
-const DLQ_KEY_SUFFIX: &str = "dlq";
+/// How long a failure hash outlives its last write. Well above any sensible
+/// claim timeout, so a pending claim never loses its parked count.
+const FAILURE_TTL: Duration = Duration::from_hours(30 * 24);
+
+/// Key names of the DLQ heap, all sharing the `{<key_prefix>}` hash tag.
+#[derive(Debug, Clone)]
+struct DlqKeys {
+    tag: String,
+}
+
+impl DlqKeys {
+    fn new(key_prefix: &str) -> Self {
+        Self {
+            tag: format!("{{{key_prefix}}}"),
+        }
+    }
+
+    fn counters(&self) -> String {
+        format!("{}:dlq", self.tag)
+    }
+
+    fn pending(&self) -> String {
+        format!("{}:dlq:pending", self.tag)
+    }
+
+    fn touched(&self) -> String {
+        format!("{}:dlq:touched", self.tag)
+    }
+
+    /// The failure hash key without the id; scripts append the id themselves.
+    fn failure_prefix(&self) -> String {
+        format!("{}:dlq:failure:", self.tag)
+    }
+
+    fn failure(&self, member: &str) -> String {
+        format!("{}{member}", self.failure_prefix())
+    }
+}
 
 #[async_trait]
 impl DlqHeap for RedisProvider {
-    async fn record_failure(&self, id: EventId) -> Result<(), OutboxError> {
+    async fn record_failure(
+        &self,
+        id: EventId,
+        event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
         let mut conn = self.connection.clone();
-        let key = self.dlq_key();
-        let member = id.as_uuid().to_string();
+        let member = id.to_string();
 
-        let _: f64 = redis::cmd("ZINCRBY")
-            .arg(&key)
-            .arg(1_i64)
+        let script = redis::Script::new(
+            r"
+            redis.call('ZINCRBY', KEYS[1], 1, ARGV[1])
+            redis.call('HSET', KEYS[2], 'last_error', ARGV[2], 'event_type', ARGV[4])
+            redis.call('HSETNX', KEYS[2], 'first_failed_at', ARGV[3])
+            redis.call('EXPIRE', KEYS[2], ARGV[5])
+            redis.call('ZADD', KEYS[3], ARGV[3], ARGV[1])
+            return 1
+            ",
+        );
+
+        let keys = self.dlq_keys();
+        let _: i64 = script
+            .key(keys.counters())
+            .key(keys.failure(&member))
+            .key(keys.touched())
             .arg(&member)
-            .query_async(&mut conn)
+            .arg(error.to_string())
+            .arg(self.clock.now().unix_timestamp())
+            .arg(event_type.as_str())
+            .arg(FAILURE_TTL.as_secs())
+            .invoke_async(&mut conn)
             .await
             .map_err(|e: redis::RedisError| map_err(&e))?;
         Ok(())
@@ -41,64 +136,278 @@ impl DlqHeap for RedisProvider {
 
     async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
         let mut conn = self.connection.clone();
-        let key = self.dlq_key();
-        let member = id.as_uuid().to_string();
+        let member = id.to_string();
 
-        let _: i64 = redis::cmd("ZREM")
-            .arg(&key)
+        let keys = self.dlq_keys();
+        let _: (i64, i64, i64) = redis::pipe()
+            .atomic()
+            .cmd("ZREM")
+            .arg(keys.counters())
+            .arg(&member)
+            .cmd("ZREM")
+            .arg(keys.touched())
             .arg(&member)
+            .cmd("DEL")
+            .arg(keys.failure(&member))
             .query_async(&mut conn)
             .await
             .map_err(|e: redis::RedisError| map_err(&e))?;
         Ok(())
     }
 
-    async fn drain_exceeded(&self, threshold: u32) -> Result<Vec<DlqEntry>, OutboxError> {
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
         let mut conn = self.connection.clone();
-        let key = self.dlq_key();
+        let now = self.clock.now();
+        let cutoff = policy
+            .failing_since_cutoff(now)
+            .map(OffsetDateTime::unix_timestamp);
 
         let script = redis::Script::new(
             r"
-            local items = redis.call('ZRANGEBYSCORE', KEYS[1], ARGV[1], '+inf', 'WITHSCORES')
-            if #items > 0 then
-                redis.call('ZREMRANGEBYSCORE', KEYS[1], ARGV[1], '+inf')
+            local default = tonumber(ARGV[4])
+            local cutoff = tonumber(ARGV[5])
+            local limit = tonumber(ARGV[6])
+            local thresholds = {}
+            for i = 7, #ARGV, 2 do
+                thresholds[ARGV[i]] = tonumber(ARGV[i + 1])
+            end
+            local out = {}
+            local claimed, skipped = 0, 0
+            while claimed < limit do
+                local page = limit - claimed
+                local items = redis.call('ZRANGEBYSCORE', KEYS[1], ARGV[1], '+inf', 'WITHSCORES',
+                    'LIMIT', skipped, page)
+                for i = 1, #items, 2 do
+                    local key = KEYS[3] .. items[i]
+                    local meta = redis.call('HMGET', key, 'last_error', 'first_failed_at', 'event_type')
+                    local threshold = thresholds[meta[3]] or default
+                    local first = tonumber(meta[2])
+                    if tonumber(items[i + 1]) >= threshold or (cutoff and first and first <= cutoff) then
+                        redis.call('HSET', key, 'failure_count', items[i + 1])
+                        redis.call('EXPIRE', key, ARGV[2])
+                        redis.call('ZADD', KEYS[2], ARGV[3], items[i])
+                        redis.call('ZREM', KEYS[1], items[i])
+                        table.insert(out, items[i])
+                        table.insert(out, items[i + 1])
+                        table.insert(out, meta[1] or '')
+                        table.insert(out, meta[2] or '')
+                        table.insert(out, meta[3] or '')
+                        claimed = claimed + 1
+                    else
+                        skipped = skipped + 1
+                    end
+                end
+                if #items < 2 * page then
+                    break
+                end
             end
-            return items
+            return out
             ",
         );
 
-        let raw: Vec<String> = script
-            .key(&key)
-            .arg(threshold)
+        let keys = self.dlq_keys();
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(keys.counters())
+            .key(keys.pending())
+            .key(keys.failure_prefix())
+            .arg(if cutoff.is_some() {
+                1
+            } else {
+                policy.min_threshold()
+            })
+            .arg(FAILURE_TTL.as_secs())
+            .arg(now.unix_timestamp())
+            .arg(policy.threshold)
+            .arg(cutoff.map(|c| c.to_string()).unwrap_or_default())
+            .arg(policy.batch_size);
+        for (event_type, threshold) in &policy.type_thresholds {
+            invocation.arg(event_type).arg(threshold);
+        }
+        let raw: Vec<String> = invocation
             .invoke_async(&mut conn)
             .await
             .map_err(|e: redis::RedisError| map_err(&e))?;
 
-        let mut out = Vec::with_capacity(raw.len() / 2);
-        for pair in raw.chunks_exact(2) {
-            let uuid = Uuid::parse_str(&pair[0]).map_err(|e| {
+        let mut out = Vec::with_capacity(raw.len() / 5);
+        for item in raw.chunks_exact(5) {
+            let uuid = Uuid::parse_str(&item[0]).map_err(|e| {
                 OutboxError::InfrastructureError(format!(
                     "Invalid UUID '{}' in DLQ zset: {e}",
-                    pair[0]
+                    item[0]
                 ))
             })?;
-            let score: f64 = pair[1].parse().map_err(|e| {
+            let score: f64 = item[1].parse().map_err(|e| {
                 OutboxError::InfrastructureError(format!(
                     "Invalid score '{}' in DLQ zset: {e}",
-                    pair[1]
+                    item[1]
                 ))
             })?;
             #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
             let count = score.max(0.0) as u32;
-            out.push(DlqEntry::new(EventId::load(uuid), count, None));
+            let last_error = Some(item[2].clone()).filter(|e| !e.is_empty());
+            let first_failed_at = item[3]
+                .parse::<i64>()
+                .ok()
+                .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok());
+            let mut entry = DlqEntry::new(EventId::load(uuid), count, last_error)
+                .with_first_failed_at(first_failed_at);
+            if !item[4].is_empty() {
+                entry = entry.with_event_type(EventType::load(&item[4]));
+            }
+            out.push(entry);
         }
         Ok(out)
     }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        self.remove_entries(ids).await.map(|_| ())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let mut conn = self.connection.clone();
+        let now = self.clock.now().unix_timestamp();
+        let cutoff = now - i64::try_from(timeout.as_secs()).unwrap_or(i64::MAX);
+
+        let script = redis::Script::new(
+            r"
+            local items = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', ARGV[1])
+            local reclaimed = 0
+            for _, id in ipairs(items) do
+                local key = KEYS[4] .. id
+                local count = tonumber(redis.call('HGET', key, 'failure_count'))
+                if count then
+                    redis.call('ZINCRBY', KEYS[1], count, id)
+                    redis.call('ZADD', KEYS[3], ARGV[3], id)
+                    redis.call('HDEL', key, 'failure_count')
+                    redis.call('EXPIRE', key, ARGV[2])
+                    reclaimed = reclaimed + 1
+                end
+                redis.call('ZREM', KEYS[2], id)
+            end
+            return reclaimed
+            ",
+        );
+
+        let keys = self.dlq_keys();
+        let reclaimed: u64 = script
+            .key(keys.counters())
+            .key(keys.pending())
+            .key(keys.touched())
+            .key(keys.failure_prefix())
+            .arg(cutoff)
+            .arg(FAILURE_TTL.as_secs())
+            .arg(now)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e: redis::RedisError| map_err(&e))?;
+        Ok(reclaimed)
+    }
+
+    async fn tracked_ids(
+        &self,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, Vec<EventId>), OutboxError> {
+        let mut conn = self.connection.clone();
+        let (next, members): (u64, Vec<String>) = redis::cmd("ZSCAN")
+            .arg(self.dlq_keys().counters())
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(count)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e: redis::RedisError| map_err(&e))?;
+
+        // ZSCAN returns members and scores interleaved.
+        let ids = members
+            .iter()
+            .step_by(2)
+            .map(|member| {
+                Uuid::parse_str(member).map(EventId::load).map_err(|e| {
+                    OutboxError::InfrastructureError(format!(
+                        "Invalid UUID '{member}' in DLQ zset: {e}"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((next, ids))
+    }
+
+    async fn forget(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        self.remove_entries(ids).await
+    }
+
+    async fn expire_idle(&self, max_idle: Duration) -> Result<u64, OutboxError> {
+        let mut conn = self.connection.clone();
+        let cutoff = self.clock.now().unix_timestamp()
+            - i64::try_from(max_idle.as_secs()).unwrap_or(i64::MAX);
+
+        let script = redis::Script::new(
+            r"
+            local items = redis.call('ZRANGEBYSCORE', KEYS[3], '-inf', ARGV[1])
+            local expired = 0
+            for _, id in ipairs(items) do
+                if not redis.call('ZSCORE', KEYS[2], id) then
+                    expired = expired + redis.call('ZREM', KEYS[1], id)
+                    redis.call('DEL', KEYS[4] .. id)
+                end
+                redis.call('ZREM', KEYS[3], id)
+            end
+            return expired
+            ",
+        );
+
+        let keys = self.dlq_keys();
+        let expired: u64 = script
+            .key(keys.counters())
+            .key(keys.pending())
+            .key(keys.touched())
+            .key(keys.failure_prefix())
+            .arg(cutoff)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e: redis::RedisError| map_err(&e))?;
+        Ok(expired)
+    }
 }
 
 impl RedisProvider {
-    fn dlq_key(&self) -> String {
-        format!("{}:{}", self.config.key_prefix, DLQ_KEY_SUFFIX)
+    fn dlq_keys(&self) -> DlqKeys {
+        DlqKeys::new(&self.config.key_prefix)
+    }
+
+    /// Drops the counters, claims and failure hashes of `ids` in one `MULTI`
+    /// and returns how many counters were removed.
+    async fn remove_entries(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.connection.clone();
+        let members: Vec<String> = ids.iter().map(ToString::to_string).collect();
+
+        let keys = self.dlq_keys();
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("ZREM")
+            .arg(keys.counters())
+            .arg(&members)
+            .cmd("ZREM")
+            .arg(keys.pending())
+            .arg(&members)
+            .ignore()
+            .cmd("ZREM")
+            .arg(keys.touched())
+            .arg(&members)
+            .ignore();
+        for member in &members {
+            pipe.cmd("DEL").arg(keys.failure(member)).ignore();
+        }
+        let (removed,): (u64,) = pipe
+            .query_async(&mut conn)
+            .await
+            .map_err(|e: redis::RedisError| map_err(&e))?;
+        Ok(removed)
     }
 }
 
@@ -106,3 +415,58 @@ fn map_err(e: &redis::RedisError) -> OutboxError {
     error!("Redis DLQ command failed: {e:?}");
     OutboxError::InfrastructureError(e.to_string())
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::RedisTokenConfig;
+    use rstest::rstest;
+    use std::collections::HashSet;
+
+    #[rstest]
+    fn dlq_keys_share_one_hash_tag() {
+        let keys = DlqKeys::new("orders");
+
+        assert_eq!(keys.counters(), "{orders}:dlq");
+        assert_eq!(keys.pending(), "{orders}:dlq:pending");
+        assert_eq!(keys.touched(), "{orders}:dlq:touched");
+        assert_eq!(keys.failure("42"), "{orders}:dlq:failure:42");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn tracked_ids_pages_through_every_counter() {
+        let url = std::env::var("OUTBOX_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let heap = RedisProvider::new(
+            &url,
+            RedisTokenConfig {
+                key_prefix: format!("dlq_test_{}", Uuid::new_v4().simple()),
+                ..RedisTokenConfig::default()
+            },
+        )
+        .await
+        .unwrap();
+        let event_type = EventType::new("OrderCreated");
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        let ids: Vec<EventId> = (0..300).map(|_| EventId::default()).collect();
+        for id in &ids {
+            heap.record_failure(*id, &event_type, &error).await.unwrap();
+        }
+
+        let mut seen = HashSet::new();
+        let mut cursor = 0;
+        loop {
+            let (next, page) = heap.tracked_ids(cursor, 50).await.unwrap();
+            seen.extend(page);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        assert_eq!(seen.len(), ids.len());
+        heap.forget(&ids).await.unwrap();
+    }
+}
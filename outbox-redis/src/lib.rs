@@ -1,11 +1,21 @@
 pub mod config;
 #[cfg(feature = "dlq")]
 mod dlq;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "transport")]
+pub mod transport;
+#[cfg(feature = "storage")]
+pub mod writer;
 
 use crate::config::RedisTokenConfig;
 use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::{Clock, SystemClock};
 use outbox_core::prelude::{IdempotencyStorageProvider, IdempotencyToken, OutboxError};
 use redis::aio::MultiplexedConnection;
+#[cfg(feature = "dlq")]
+use std::sync::Arc;
 use tracing::error;
 
 pub struct RedisProvider {
@@ -13,6 +23,8 @@ pub struct RedisProvider {
     #[cfg(feature = "moka")]
     local_cache: moka::future::Cache<String, ()>,
     config: RedisTokenConfig,
+    #[cfg(feature = "dlq")]
+    clock: Arc<dyn Clock>,
 }
 
 impl RedisProvider {
@@ -40,8 +52,23 @@ impl RedisProvider {
                 .time_to_live(config.ttl)
                 .build(),
             config,
+            #[cfg(feature = "dlq")]
+            clock: Arc::new(SystemClock),
         })
     }
+
+    /// Sets where the DLQ heap takes failure, claim and idle times from.
+    /// Pass the [`OutboxConfig::clock`](outbox_core::prelude::OutboxConfig::clock)
+    /// of the manager the heap is wired into. Defaults to [`SystemClock`].
+    ///
+    /// Token reservations expire through Redis' own TTL and do not follow
+    /// this clock.
+    #[cfg(feature = "dlq")]
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 }
 
 #[async_trait]
@@ -83,4 +110,25 @@ impl IdempotencyStorageProvider for RedisProvider {
             Ok(false)
         }
     }
+
+    async fn release(&self, token: &IdempotencyToken) -> Result<(), OutboxError> {
+        let token_str = token.as_str();
+        let redis_key = format!("{}:{}", self.config.key_prefix, token_str);
+        let mut conn = self.connection.clone();
+
+        let _: () = redis::cmd("DEL")
+            .arg(&redis_key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| {
+                error!("Redis query failed: {:?}", e);
+                OutboxError::InfrastructureError(e.to_string())
+            })?;
+
+        #[cfg(feature = "moka")]
+        {
+            self.local_cache.invalidate(token_str).await;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,1128 @@
+//! Redis-backed [`OutboxStorage`] implementation.
+//!
+//! Every event is a hash (`{<key_prefix>}:event:<id>`, fields `event_type`,
+//! `payload` as JSON, `idempotency_token`, `status`, `created_at` and
+//! `locked_until` as unix microseconds). Three sorted sets index the hashes
+//! by status:
+//! - `{<key_prefix>}:pending`    — score = `created_at`, so the oldest event
+//!   is claimed first
+//! - `{<key_prefix>}:processing` — score = `locked_until`, the lease of the
+//!   worker that claimed the event
+//! - `{<key_prefix>}:sent`       — score = `created_at`, for retention
+//!
+//! A fourth hash, `{<key_prefix>}:tokens`, maps each idempotency token to the
+//! event holding it and plays the part of the unique index in the SQL
+//! backends. The prefix is wrapped in a hash tag, so every key of one outbox
+//! lands in the same Redis Cluster slot and the scripts below stay legal
+//! there.
+//!
+//! - `fetch_next_to_process` → Lua script: take `Pending` ids oldest first,
+//!   then `Processing` ids whose lease expired, move them to the processing
+//!   set with a fresh lease and return their hashes. Concurrent workers never
+//!   receive the same event.
+//! - `update_status` → Lua script: move each id to the set of its new status
+//! - `delete_garbage` → Lua script: delete `Sent` events older than
+//!   `retention_days` together with their token entries, in batches
+//! - `wait_for_notification` → `SUBSCRIBE {<key_prefix>}:<channel>`; the
+//!   insert script of [`RedisWriter`](crate::writer::RedisWriter) publishes
+//!   to `{<key_prefix>}:outbox_event`
+//!
+//! With the `dlq` feature, quarantined events move to a hash per dead letter
+//! (`{<key_prefix>}:dead_letter:<id>`, the event's fields plus the failure
+//! data) indexed by `{<key_prefix>}:dead_letters`, score = `quarantined_at`.
+//! Quarantine, revival and purging are Lua scripts as well, so a dead letter
+//! is never in both places at once.
+//!
+//! Timestamps are stored with microsecond precision, like Postgres'
+//! `timestamptz`.
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use outbox_core::prelude::*;
+use redis::aio::{MultiplexedConnection, PubSubStream};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+#[cfg(feature = "dlq")]
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+#[cfg(feature = "dlq")]
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+/// The channel the insert script publishes to, under the key prefix. It is
+/// the channel `OutboxManager` listens on.
+pub(crate) const NOTIFY_CHANNEL: &str = "outbox_event";
+
+/// Deletes at most this many `Sent` events per script call, so one garbage
+/// collection pass never blocks the server for long.
+const GC_BATCH: u32 = 1000;
+
+/// Revives at most this many dead letters per call, like the SQL backends.
+#[cfg(feature = "dlq")]
+const REVIVE_BATCH: u32 = 5000;
+
+/// Hash fields read back by [`fetch_next_to_process`](OutboxStorage::fetch_next_to_process),
+/// after the id.
+const EVENT_FIELDS: usize = 7;
+
+/// Key names of one outbox, all sharing the `{<key_prefix>}` hash tag.
+#[derive(Debug, Clone)]
+pub(crate) struct Keys {
+    tag: String,
+}
+
+impl Keys {
+    pub(crate) fn new(key_prefix: &str) -> Self {
+        Self {
+            tag: format!("{{{key_prefix}}}"),
+        }
+    }
+
+    pub(crate) fn pending(&self) -> String {
+        format!("{}:pending", self.tag)
+    }
+
+    pub(crate) fn processing(&self) -> String {
+        format!("{}:processing", self.tag)
+    }
+
+    pub(crate) fn sent(&self) -> String {
+        format!("{}:sent", self.tag)
+    }
+
+    pub(crate) fn tokens(&self) -> String {
+        format!("{}:tokens", self.tag)
+    }
+
+    #[cfg(feature = "dlq")]
+    pub(crate) fn dead_letters(&self) -> String {
+        format!("{}:dead_letters", self.tag)
+    }
+
+    /// The dead-letter hash key without the id.
+    #[cfg(feature = "dlq")]
+    pub(crate) fn dead_letter_prefix(&self) -> String {
+        format!("{}:dead_letter:", self.tag)
+    }
+
+    /// The event hash key without the id; scripts append the id themselves.
+    pub(crate) fn event_prefix(&self) -> String {
+        format!("{}:event:", self.tag)
+    }
+
+    pub(crate) fn channel(&self, channel: &str) -> String {
+        format!("{}:{channel}", self.tag)
+    }
+}
+
+/// Outbox storage kept in Redis.
+///
+/// Clones share the connection and the notification subscription.
+#[derive(Clone)]
+pub struct RedisOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    inner: Arc<RedisOutboxInner<P>>,
+    keys: Keys,
+}
+
+struct RedisOutboxInner<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    client: redis::Client,
+    connection: MultiplexedConnection,
+    config: Arc<OutboxConfig<P>>,
+    notifications: Mutex<Option<PubSubStream>>,
+}
+
+impl<P> RedisOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    /// Creates a new `RedisOutbox` and establishes a connection to Redis.
+    /// Keys are prefixed with `outbox` unless
+    /// [`with_key_prefix`](Self::with_key_prefix) says otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::InfrastructureError`] if:
+    /// - The `connection_info` string is not a valid Redis URL.
+    /// - The connection to the Redis server cannot be established.
+    pub async fn new(
+        connection_info: &str,
+        config: Arc<OutboxConfig<P>>,
+    ) -> Result<Self, OutboxError> {
+        let client = redis::Client::open(connection_info)
+            .map_err(|e| OutboxError::InfrastructureError(format!("Invalid Redis URL: {e}")))?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| {
+                error!("Redis connection failed: {:?}", e);
+                OutboxError::InfrastructureError("Redis connection failed".to_string())
+            })?;
+        Ok(Self {
+            inner: Arc::new(RedisOutboxInner {
+                client,
+                connection,
+                config,
+                notifications: Mutex::new(None),
+            }),
+            keys: Keys::new("outbox"),
+        })
+    }
+
+    /// Sets the prefix of every key this outbox uses. Writers must use the
+    /// same prefix. Defaults to `outbox`.
+    #[must_use]
+    pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+        self.keys = Keys::new(key_prefix);
+        self
+    }
+
+    /// Returns a [`RedisWriter`](crate::writer::RedisWriter) that shares this
+    /// outbox's connection and key prefix.
+    pub fn writer(&self) -> crate::writer::RedisWriter {
+        crate::writer::RedisWriter::from_parts(self.inner.connection.clone(), self.keys.clone())
+    }
+}
+
+#[async_trait]
+impl<P> OutboxStorage<P> for RedisOutbox<P>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn fetch_next_to_process(&self, limit: u32) -> Result<Vec<Event<P>>, OutboxError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let now = self.inner.config.clock.now();
+        let locked_until = now + time::Duration::minutes(self.inner.config.lock_timeout_mins);
+
+        let script = redis::Script::new(
+            r"
+            local limit = tonumber(ARGV[1])
+            local ids = redis.call('ZRANGE', KEYS[1], 0, limit - 1)
+            if #ids < limit then
+                local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', '(' .. ARGV[2],
+                    'LIMIT', 0, limit - #ids)
+                for _, id in ipairs(expired) do
+                    table.insert(ids, id)
+                end
+            end
+            local out = {}
+            for _, id in ipairs(ids) do
+                local key = ARGV[4] .. id
+                redis.call('ZREM', KEYS[1], id)
+                if redis.call('EXISTS', key) == 1 then
+                    redis.call('ZADD', KEYS[2], ARGV[3], id)
+                    redis.call('HSET', key, 'status', 'Processing', 'locked_until', ARGV[3])
+                    local fields = redis.call('HMGET', key, 'event_type', 'payload',
+                        'idempotency_token', 'status', 'created_at', 'locked_until')
+                    table.insert(out, id)
+                    for i = 1, #fields do
+                        table.insert(out, fields[i])
+                    end
+                else
+                    redis.call('ZREM', KEYS[2], id)
+                end
+            end
+            return out
+            ",
+        );
+
+        let raw: Vec<Option<String>> = script
+            .key(self.keys.pending())
+            .key(self.keys.processing())
+            .arg(limit)
+            .arg(to_unix_micros(now))
+            .arg(to_unix_micros(locked_until))
+            .arg(self.keys.event_prefix())
+            .invoke_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+
+        raw.chunks_exact(EVENT_FIELDS).map(decode_event).collect()
+    }
+
+    async fn update_status(&self, ids: &[EventId], status: EventStatus) -> Result<(), OutboxError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let script = redis::Script::new(
+            r"
+            for i = 3, #ARGV do
+                local id = ARGV[i]
+                local key = ARGV[2] .. id
+                local times = redis.call('HMGET', key, 'created_at', 'locked_until')
+                if times[1] then
+                    redis.call('ZREM', KEYS[1], id)
+                    redis.call('ZREM', KEYS[2], id)
+                    redis.call('ZREM', KEYS[3], id)
+                    redis.call('HSET', key, 'status', ARGV[1])
+                    if ARGV[1] == 'Pending' then
+                        redis.call('ZADD', KEYS[1], times[1], id)
+                    elseif ARGV[1] == 'Processing' then
+                        redis.call('ZADD', KEYS[2], times[2], id)
+                    else
+                        redis.call('ZADD', KEYS[3], times[1], id)
+                    end
+                end
+            end
+            return 1
+            ",
+        );
+
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(self.keys.pending())
+            .key(self.keys.processing())
+            .key(self.keys.sent())
+            .arg(status.as_str())
+            .arg(self.keys.event_prefix());
+        for id in ids {
+            invocation.arg(id.to_string());
+        }
+        let _: i64 = invocation
+            .invoke_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+        Ok(())
+    }
+
+    async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let cutoff =
+            self.inner.config.clock.now() - time::Duration::days(self.inner.config.retention_days);
+
+        let script = redis::Script::new(
+            r"
+            local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', '(' .. ARGV[1],
+                'LIMIT', 0, tonumber(ARGV[3]))
+            for _, id in ipairs(ids) do
+                local key = ARGV[2] .. id
+                local token = redis.call('HGET', key, 'idempotency_token')
+                if token and redis.call('HGET', KEYS[2], token) == id then
+                    redis.call('HDEL', KEYS[2], token)
+                end
+                redis.call('DEL', key)
+                redis.call('ZREM', KEYS[1], id)
+            end
+            return #ids
+            ",
+        );
+
+        let mut deleted = 0_u64;
+        loop {
+            let batch: u64 = script
+                .key(self.keys.sent())
+                .key(self.keys.tokens())
+                .arg(to_unix_micros(cutoff))
+                .arg(self.keys.event_prefix())
+                .arg(GC_BATCH)
+                .invoke_async(&mut self.inner.connection.clone())
+                .await
+                .map_err(|e| map_err(&e))?;
+            deleted += batch;
+            if batch < u64::from(GC_BATCH) {
+                break;
+            }
+        }
+        debug!("Garbage collector: deleted {} old messages", deleted);
+        Ok(())
+    }
+
+    /// Waits for the next message on `{<key_prefix>}:<channel>`.
+    ///
+    /// The subscription is opened on first use and kept between calls, so
+    /// messages published while the worker was busy are not missed; it is
+    /// reopened after the connection drops.
+    async fn wait_for_notification(&self, channel: &str) -> Result<(), OutboxError> {
+        let mut guard = self.inner.notifications.lock().await;
+
+        if guard.is_none() {
+            let mut pubsub = self
+                .inner
+                .client
+                .get_async_pubsub()
+                .await
+                .map_err(|e| map_err(&e))?;
+            pubsub
+                .subscribe(self.keys.channel(channel))
+                .await
+                .map_err(|e| map_err(&e))?;
+            *guard = Some(pubsub.into_on_message());
+        }
+        let Some(stream) = guard.as_mut() else {
+            return Ok(());
+        };
+        if stream.next().await.is_some() {
+            Ok(())
+        } else {
+            *guard = None;
+            Err(OutboxError::DatabaseError(
+                "Redis notification subscription closed".to_string(),
+            ))
+        }
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut pipe = redis::pipe();
+        for id in ids {
+            pipe.cmd("HMGET")
+                .arg(format!("{}{id}", self.keys.event_prefix()))
+                .arg(&[
+                    "event_type",
+                    "payload",
+                    "idempotency_token",
+                    "status",
+                    "created_at",
+                    "locked_until",
+                ]);
+        }
+        let rows: Vec<Vec<Option<String>>> = pipe
+            .query_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+
+        ids.iter()
+            .zip(rows)
+            .filter(|(_, fields)| fields.first().is_some_and(Option::is_some))
+            .map(|(id, fields)| {
+                let mut row = Vec::with_capacity(EVENT_FIELDS);
+                row.push(Some(id.to_string()));
+                row.extend(fields);
+                decode_event(&row)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let script = redis::Script::new(
+            r"
+            local deleted = 0
+            for i = 2, #ARGV do
+                local id = ARGV[i]
+                local key = ARGV[1] .. id
+                local token = redis.call('HGET', key, 'idempotency_token')
+                if token and redis.call('HGET', KEYS[4], token) == id then
+                    redis.call('HDEL', KEYS[4], token)
+                end
+                redis.call('ZREM', KEYS[1], id)
+                redis.call('ZREM', KEYS[2], id)
+                redis.call('ZREM', KEYS[3], id)
+                deleted = deleted + redis.call('DEL', key)
+            end
+            return deleted
+            ",
+        );
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(self.keys.pending())
+            .key(self.keys.processing())
+            .key(self.keys.sent())
+            .key(self.keys.tokens())
+            .arg(self.keys.event_prefix());
+        for id in ids {
+            invocation.arg(id.to_string());
+        }
+        let deleted: u64 = invocation
+            .invoke_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+        debug!(
+            "DLQ reaper: deleted {} events published to the DLQ destination",
+            deleted
+        );
+        Ok(deleted)
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let cutoff = self.inner.config.clock.now()
+            - time::Duration::days(self.inner.config.dlq_retention_days);
+
+        let script = redis::Script::new(
+            r"
+            local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', '(' .. ARGV[1],
+                'LIMIT', 0, tonumber(ARGV[3]))
+            for _, id in ipairs(ids) do
+                redis.call('DEL', ARGV[2] .. id)
+                redis.call('ZREM', KEYS[1], id)
+            end
+            return #ids
+            ",
+        );
+
+        let mut purged = 0_u64;
+        loop {
+            let batch: u64 = script
+                .key(self.keys.dead_letters())
+                .arg(to_unix_micros(cutoff))
+                .arg(self.keys.dead_letter_prefix())
+                .arg(GC_BATCH)
+                .invoke_async(&mut self.inner.connection.clone())
+                .await
+                .map_err(|e| map_err(&e))?;
+            purged += batch;
+            if batch < u64::from(GC_BATCH) {
+                break;
+            }
+        }
+        debug!("Garbage collector: purged {} expired dead letters", purged);
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Numbers travel as strings so cjson does not round them through a
+        // double on their way into the hash.
+        let failures = serde_json::Value::from(
+            entries
+                .iter()
+                .map(|e| {
+                    serde_json::json!({
+                        "id": e.id.to_string(),
+                        "failure_count": e.failure_count.to_string(),
+                        "last_error": e.last_error,
+                        "first_failed_at": e.first_failed_at.map(|at| to_unix_micros(at).to_string()),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let script = redis::Script::new(
+            r"
+            local out = {}
+            for _, entry in ipairs(cjson.decode(ARGV[4])) do
+                local key = ARGV[1] .. entry.id
+                local fields = redis.call('HGETALL', key)
+                if #fields > 0 then
+                    local dead = ARGV[2] .. entry.id
+                    redis.call('DEL', dead)
+                    redis.call('HSET', dead, unpack(fields))
+                    redis.call('HSET', dead, 'failure_count', entry.failure_count,
+                        'quarantined_at', ARGV[3])
+                    if entry.last_error ~= cjson.null then
+                        redis.call('HSET', dead, 'last_error', entry.last_error)
+                    end
+                    if entry.first_failed_at ~= cjson.null then
+                        redis.call('HSET', dead, 'first_failed_at', entry.first_failed_at)
+                    end
+                    redis.call('ZADD', KEYS[5], ARGV[3], entry.id)
+
+                    local token = redis.call('HGET', key, 'idempotency_token')
+                    if token and redis.call('HGET', KEYS[4], token) == entry.id then
+                        redis.call('HDEL', KEYS[4], token)
+                    end
+                    redis.call('ZREM', KEYS[1], entry.id)
+                    redis.call('ZREM', KEYS[2], entry.id)
+                    redis.call('ZREM', KEYS[3], entry.id)
+                    redis.call('DEL', key)
+                    table.insert(out, entry.id)
+                    table.insert(out, redis.call('HGET', dead, 'event_type'))
+                end
+            end
+            return out
+            ",
+        );
+
+        let moved: Vec<String> = script
+            .key(self.keys.pending())
+            .key(self.keys.processing())
+            .key(self.keys.sent())
+            .key(self.keys.tokens())
+            .key(self.keys.dead_letters())
+            .arg(self.keys.event_prefix())
+            .arg(self.keys.dead_letter_prefix())
+            .arg(to_unix_micros(self.inner.config.clock.now()))
+            .arg(failures.to_string())
+            .invoke_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+
+        debug!(
+            "DLQ reaper: quarantined {}/{} events",
+            moved.len() / 2,
+            entries.len()
+        );
+        let event_types: HashMap<&str, &str> = moved
+            .chunks_exact(2)
+            .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+            .collect();
+        Ok(entries
+            .iter()
+            .filter_map(|entry| {
+                event_types
+                    .get(entry.id.to_string().as_str())
+                    .map(|t| entry.clone().with_event_type(EventType::load(t)))
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let cutoff = self.inner.config.clock.now() - cooldown;
+
+        // Skipped dead letters stay in the set, so each page starts past the
+        // ones skipped so far.
+        let script = redis::Script::new(
+            r"
+            local max_revivals = tonumber(ARGV[2])
+            local limit = tonumber(ARGV[5])
+            local revived = {}
+            local skipped = 0
+            while #revived < limit do
+                local ids = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1],
+                    'LIMIT', skipped, limit - #revived)
+                if #ids == 0 then
+                    break
+                end
+                for _, id in ipairs(ids) do
+                    local dead = ARGV[4] .. id
+                    local revivals = tonumber(redis.call('HGET', dead, 'revival_count')) or 0
+                    local token = redis.call('HGET', dead, 'idempotency_token')
+                    if revivals >= max_revivals
+                        or (token and redis.call('HEXISTS', KEYS[3], token) == 1) then
+                        skipped = skipped + 1
+                    else
+                        local key = ARGV[3] .. id
+                        local created_at = redis.call('HGET', dead, 'created_at')
+                        redis.call('HSET', key,
+                            'event_type', redis.call('HGET', dead, 'event_type'),
+                            'payload', redis.call('HGET', dead, 'payload'),
+                            'status', 'Pending', 'created_at', created_at, 'locked_until', '0',
+                            'revival_count', revivals + 1)
+                        if token then
+                            redis.call('HSET', key, 'idempotency_token', token)
+                            redis.call('HSET', KEYS[3], token, id)
+                        end
+                        redis.call('ZADD', KEYS[2], created_at, id)
+                        redis.call('DEL', dead)
+                        redis.call('ZREM', KEYS[1], id)
+                        table.insert(revived, id)
+                    end
+                end
+            end
+            if #revived > 0 then
+                redis.call('PUBLISH', ARGV[6], #revived)
+            end
+            return revived
+            ",
+        );
+
+        let revived: Vec<String> = script
+            .key(self.keys.dead_letters())
+            .key(self.keys.pending())
+            .key(self.keys.tokens())
+            .arg(to_unix_micros(cutoff))
+            .arg(max_revivals)
+            .arg(self.keys.event_prefix())
+            .arg(self.keys.dead_letter_prefix())
+            .arg(REVIVE_BATCH)
+            .arg(self.keys.channel(NOTIFY_CHANNEL))
+            .invoke_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+
+        debug!("DLQ reaper: revived {} dead letters", revived.len());
+        revived.iter().map(|id| id.parse()).collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut pipe = redis::pipe();
+        for id in ids {
+            pipe.exists(format!("{}{id}", self.keys.event_prefix()));
+        }
+        let present: Vec<bool> = pipe
+            .query_async(&mut self.inner.connection.clone())
+            .await
+            .map_err(|e| map_err(&e))?;
+        Ok(ids
+            .iter()
+            .zip(present)
+            .filter(|(_, present)| !present)
+            .map(|(id, _)| *id)
+            .collect())
+    }
+}
+
+/// Converts one `[id, field..]` chunk returned by the fetch script back into
+/// an [`Event`].
+fn decode_event<P>(fields: &[Option<String>]) -> Result<Event<P>, OutboxError>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync,
+{
+    let field = |i: usize, name: &str| {
+        fields[i]
+            .as_deref()
+            .ok_or_else(|| OutboxError::DatabaseError(format!("Outbox event lacks `{name}`")))
+    };
+    Ok(Event {
+        id: field(0, "id")?.parse()?,
+        event_type: EventType::load(field(1, "event_type")?),
+        payload: serde_json::from_str(field(2, "payload")?)
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+        idempotency_token: fields[3].clone().map(IdempotencyToken::new),
+        status: field(4, "status")?.parse()?,
+        created_at: from_micros(field(5, "created_at")?)?,
+        locked_until: from_micros(field(6, "locked_until")?)?,
+    })
+}
+
+fn from_micros(value: &str) -> Result<OffsetDateTime, OutboxError> {
+    let micros = value
+        .parse()
+        .map_err(|_| OutboxError::DatabaseError(format!("Invalid timestamp '{value}'")))?;
+    from_unix_micros(micros)
+}
+
+pub(crate) fn map_err(e: &redis::RedisError) -> OutboxError {
+    error!("Redis outbox command failed: {e:?}");
+    OutboxError::DatabaseError(e.to_string())
+}
+
+/// The tests that talk to Redis are ignored by default. Point
+/// `OUTBOX_REDIS_URL` at a server and run
+/// `cargo test -p outbox-redis --features storage -- --ignored`. Every test
+/// uses its own key prefix and deletes its keys when it passes.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload(u32);
+
+    fn url() -> String {
+        std::env::var("OUTBOX_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string())
+    }
+
+    async fn outbox(clock: &ManualClock) -> RedisOutbox<TestPayload> {
+        let config = Arc::new(OutboxConfig::default().with_clock(Arc::new(clock.clone())));
+        RedisOutbox::new(&url(), config)
+            .await
+            .unwrap()
+            .with_key_prefix(&format!("outbox_test_{}", Uuid::new_v4().simple()))
+    }
+
+    async fn cleanup(outbox: &RedisOutbox<TestPayload>) {
+        let mut conn = outbox.inner.connection.clone();
+        let keys: Vec<String> = redis::cmd("KEYS")
+            .arg(format!("{}*", outbox.keys.tag))
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        if !keys.is_empty() {
+            let _: i64 = redis::cmd("DEL")
+                .arg(keys)
+                .query_async(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn count(outbox: &RedisOutbox<TestPayload>, key: String) -> u64 {
+        redis::cmd("ZCARD")
+            .arg(key)
+            .query_async(&mut outbox.inner.connection.clone())
+            .await
+            .unwrap()
+    }
+
+    fn event(token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    #[rstest]
+    fn keys_share_one_hash_tag() {
+        let keys = Keys::new("orders");
+
+        assert_eq!(keys.pending(), "{orders}:pending");
+        assert_eq!(keys.event_prefix(), "{orders}:event:");
+        assert_eq!(keys.channel(NOTIFY_CHANNEL), "{orders}:outbox_event");
+        #[cfg(feature = "dlq")]
+        assert_eq!(keys.dead_letter_prefix(), "{orders}:dead_letter:");
+    }
+
+    #[rstest]
+    fn decode_event_reads_the_fetch_script_reply() {
+        let id = Uuid::new_v4();
+        let created_at = OffsetDateTime::now_utc();
+        let fields = vec![
+            Some(id.to_string()),
+            Some("OrderCreated".to_string()),
+            Some("{\"order\":7}".to_string()),
+            None,
+            Some("Processing".to_string()),
+            Some(to_unix_micros(created_at).to_string()),
+            Some("0".to_string()),
+        ];
+
+        let event: Event<serde_json::Value> = decode_event(&fields).unwrap();
+
+        assert_eq!(event.id, EventId::load(id));
+        assert_eq!(event.event_type.as_str(), "OrderCreated");
+        assert_eq!(event.payload.as_value()["order"], 7);
+        assert!(event.idempotency_token.is_none());
+        assert_eq!(event.status, EventStatus::Processing);
+        assert_eq!(to_unix_micros(event.created_at), to_unix_micros(created_at));
+        assert_eq!(event.locked_until, OffsetDateTime::UNIX_EPOCH);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn fetch_claims_pending_events_and_locks_them() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let writer = outbox.writer();
+        let inserted = event(Some("token-1"));
+        writer.insert_event(inserted.clone()).await.unwrap();
+        writer
+            .insert_events(vec![event(None), event(None)])
+            .await
+            .unwrap();
+
+        let claimed = outbox.fetch_next_to_process(2).await.unwrap();
+
+        assert_eq!(claimed.len(), 2);
+        assert!(claimed.iter().all(|e| e.status == EventStatus::Processing));
+        assert!(claimed.iter().all(|e| e.locked_until > clock.now()));
+        let rest = outbox.fetch_next_to_process(10).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        let round_trip = claimed
+            .iter()
+            .chain(&rest)
+            .find(|e| e.id == inserted.id)
+            .unwrap();
+        assert_eq!(round_trip.payload.as_value(), &TestPayload(1));
+        assert_eq!(
+            round_trip
+                .idempotency_token
+                .as_ref()
+                .map(IdempotencyToken::as_str),
+            Some("token-1")
+        );
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn locks_expire_on_the_configured_clock() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        outbox.writer().insert_event(event(None)).await.unwrap();
+
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_mins(6));
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn update_status_moves_events_between_sets() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let e = event(None);
+        outbox.writer().insert_event(e.clone()).await.unwrap();
+        outbox.fetch_next_to_process(10).await.unwrap();
+
+        outbox
+            .update_status(&[e.id], EventStatus::Pending)
+            .await
+            .unwrap();
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 1);
+        assert_eq!(count(&outbox, outbox.keys.processing()).await, 0);
+
+        outbox
+            .update_status(&[e.id], EventStatus::Sent)
+            .await
+            .unwrap();
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 0);
+        assert_eq!(count(&outbox, outbox.keys.sent()).await, 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn insert_rejects_duplicate_idempotency_token() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let writer = outbox.writer();
+        writer.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let single = writer.insert_event(event(Some("token-1"))).await;
+        let batch = writer
+            .insert_events(vec![event(Some("token-2")), event(Some("token-1"))])
+            .await;
+        let within_batch = writer
+            .insert_events(vec![event(Some("token-3")), event(Some("token-3"))])
+            .await;
+
+        assert!(matches!(single, Err(OutboxError::DuplicateEvent)));
+        assert!(matches!(batch, Err(OutboxError::DuplicateEvent)));
+        assert!(matches!(within_batch, Err(OutboxError::DuplicateEvent)));
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 1);
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn writer_appends_to_the_callers_pipeline() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let writer = outbox.writer();
+        let order_key = format!("{}:order", outbox.keys.tag);
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("SET")
+            .arg(&order_key)
+            .arg("paid")
+            .ignore();
+        writer
+            .insert_events_in(&mut pipe, vec![event(None), event(None)])
+            .await
+            .unwrap();
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 0);
+
+        let (): () = pipe
+            .query_async(&mut outbox.inner.connection.clone())
+            .await
+            .unwrap();
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 2);
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn insert_wakes_waiting_worker() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        // Subscribe before inserting, as the manager does.
+        let waiter = {
+            let outbox = outbox.clone();
+            tokio::spawn(async move { outbox.wait_for_notification(NOTIFY_CHANNEL).await })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        outbox.writer().insert_event(event(None)).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        cleanup(&outbox).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn delete_garbage_removes_only_old_sent_events() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let old = Event::new_with_clock(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            Some(IdempotencyToken::new("token-1".to_string())),
+            &clock,
+        );
+        outbox
+            .writer()
+            .insert_events(vec![old.clone(), event(None)])
+            .await
+            .unwrap();
+        outbox
+            .update_status(&[old.id], EventStatus::Sent)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_hours(24 * 8));
+        outbox.delete_garbage().await.unwrap();
+
+        assert_eq!(count(&outbox, outbox.keys.sent()).await, 0);
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 1);
+        // The token is free again once its event is gone.
+        outbox
+            .writer()
+            .insert_event(event(Some("token-1")))
+            .await
+            .unwrap();
+        cleanup(&outbox).await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn exceeded_events_are_quarantined_and_revived() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let failing = event(Some("token-1"));
+        outbox
+            .writer()
+            .insert_events(vec![failing.clone(), event(None)])
+            .await
+            .unwrap();
+
+        let entry = DlqEntry::new(failing.id, 3, Some("unreachable".to_string()))
+            .with_first_failed_at(Some(clock.now()));
+        let moved = outbox.quarantine_events(&[entry]).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(
+            moved[0].event_type.as_ref().unwrap().as_str(),
+            "OrderCreated"
+        );
+        assert_eq!(
+            outbox.missing_events(&[failing.id]).await.unwrap(),
+            vec![failing.id]
+        );
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 1);
+        assert_eq!(count(&outbox, outbox.keys.dead_letters()).await, 1);
+
+        let cooldown = Duration::from_mins(10);
+        assert!(
+            outbox
+                .revive_dead_letters(cooldown, 3)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        clock.advance(cooldown);
+        assert_eq!(
+            outbox.revive_dead_letters(cooldown, 3).await.unwrap(),
+            vec![failing.id]
+        );
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 2);
+        assert_eq!(count(&outbox, outbox.keys.dead_letters()).await, 0);
+        // The revived event holds its token again.
+        assert!(matches!(
+            outbox.writer().insert_event(event(Some("token-1"))).await,
+            Err(OutboxError::DuplicateEvent)
+        ));
+        cleanup(&outbox).await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let (taken, free) = (event(Some("token-1")), event(None));
+        let ids = [taken.id, free.id];
+        outbox
+            .writer()
+            .insert_events(vec![taken, free])
+            .await
+            .unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        outbox
+            .writer()
+            .insert_event(event(Some("token-1")))
+            .await
+            .unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert_eq!(revived, vec![ids[1]]);
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 2);
+        assert_eq!(count(&outbox, outbox.keys.dead_letters()).await, 1);
+        cleanup(&outbox).await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn purge_dead_letters_removes_only_expired_ones() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let (old, recent) = (event(None), event(None));
+        outbox
+            .writer()
+            .insert_events(vec![old.clone(), recent.clone()])
+            .await
+            .unwrap();
+        outbox
+            .quarantine_events(&[DlqEntry::new(old.id, 3, None)])
+            .await
+            .unwrap();
+        clock.advance(Duration::from_hours(24 * 31));
+        outbox
+            .quarantine_events(&[DlqEntry::new(recent.id, 3, None)])
+            .await
+            .unwrap();
+
+        outbox.purge_dead_letters().await.unwrap();
+
+        assert_eq!(count(&outbox, outbox.keys.dead_letters()).await, 1);
+        cleanup(&outbox).await;
+    }
+
+    #[cfg(feature = "dlq")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn fetch_and_delete_events_for_a_dlq_transport() {
+        let clock = ManualClock::default();
+        let outbox = outbox(&clock).await;
+        let drained = event(Some("token-1"));
+        outbox
+            .writer()
+            .insert_events(vec![drained.clone(), event(None)])
+            .await
+            .unwrap();
+
+        let fetched = outbox
+            .fetch_events(&[drained.id, EventId::default()])
+            .await
+            .unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].id, drained.id);
+
+        assert_eq!(outbox.delete_events(&[drained.id]).await.unwrap(), 1);
+        assert_eq!(count(&outbox, outbox.keys.pending()).await, 1);
+        outbox
+            .writer()
+            .insert_event(event(Some("token-1")))
+            .await
+            .unwrap();
+        cleanup(&outbox).await;
+    }
+}
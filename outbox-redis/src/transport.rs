@@ -0,0 +1,341 @@
+//! Redis Streams [`Transport`] implementation.
+//!
+//! Every event becomes one stream entry, appended with `XADD` to a stream
+//! chosen by its event type. An entry carries the fields:
+//! - `id`      — event id (UUID as string)
+//! - `type`    — event type
+//! - `token`   — idempotency token, only present when the event has one
+//! - `headers` — JSON object of string metadata (`created_at`, and the
+//!   `dlq_*` failure data for dead letters)
+//! - `payload` — the payload as JSON
+//!
+//! With [`with_max_len`](RedisStreamTransport::with_max_len) every `XADD`
+//! also trims its stream with `MAXLEN ~`, which lets Redis cut whole
+//! macro-nodes and keeps trimming cheap.
+
+use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
+use outbox_core::prelude::{Event, EventType, OutboxError, Transport};
+use redis::aio::MultiplexedConnection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use tracing::error;
+
+pub struct RedisStreamTransport {
+    connection: MultiplexedConnection,
+    routing: StreamRouting,
+}
+
+/// Where events go and how their `XADD` looks; kept apart from the
+/// connection so it can be tested without a server.
+struct StreamRouting {
+    stream_prefix: String,
+    streams: HashMap<String, String>,
+    max_len: Option<usize>,
+}
+
+impl RedisStreamTransport {
+    /// Creates a new `RedisStreamTransport` and establishes a connection to
+    /// Redis. Events go to `outbox:<event_type>` unless
+    /// [`with_stream_prefix`](Self::with_stream_prefix) or
+    /// [`with_stream`](Self::with_stream) say otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::InfrastructureError`] if:
+    /// - The `connection_info` string is not a valid Redis URL.
+    /// - The connection to the Redis server cannot be established.
+    pub async fn new(connection_info: &str) -> Result<Self, OutboxError> {
+        let client = redis::Client::open(connection_info)
+            .map_err(|e| OutboxError::InfrastructureError(format!("Invalid Redis URL: {e}")))?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| {
+                error!("Redis connection failed: {:?}", e);
+                OutboxError::InfrastructureError("Redis connection failed".to_string())
+            })?;
+        Ok(Self {
+            connection,
+            routing: StreamRouting {
+                stream_prefix: "outbox".to_owned(),
+                streams: HashMap::new(),
+                max_len: None,
+            },
+        })
+    }
+
+    /// Sets the prefix of the default stream names, `<prefix>:<event_type>`.
+    /// Defaults to `outbox`.
+    #[must_use]
+    pub fn with_stream_prefix(mut self, stream_prefix: &str) -> Self {
+        stream_prefix.clone_into(&mut self.routing.stream_prefix);
+        self
+    }
+
+    /// Sends events of `event_type` to `stream` instead of the default
+    /// `<prefix>:<event_type>`.
+    #[must_use]
+    pub fn with_stream(mut self, event_type: &str, stream: &str) -> Self {
+        self.routing
+            .streams
+            .insert(event_type.to_owned(), stream.to_owned());
+        self
+    }
+
+    /// Caps every stream at roughly `max_len` entries with `MAXLEN ~`.
+    /// Unbounded by default.
+    #[must_use]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.routing.max_len = Some(max_len);
+        self
+    }
+
+    /// Publishes all `events` in one pipelined round trip.
+    ///
+    /// The pipeline is not a transaction: if Redis rejects one `XADD`, the
+    /// entries before and after it may still have been appended.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::BrokerError`] if a command fails, or
+    /// [`OutboxError::InfrastructureError`] if a payload cannot be
+    /// serialized.
+    pub async fn publish_batch<P>(&self, events: &[Event<P>]) -> Result<(), OutboxError>
+    where
+        P: Debug + Clone + Send + Sync + Serialize,
+    {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for event in events {
+            pipe.add_command(self.routing.xadd(event, HashMap::new())?)
+                .ignore();
+        }
+        self.send(&pipe).await
+    }
+
+    /// The stream events of `event_type` are appended to.
+    pub fn stream_for(&self, event_type: &EventType) -> String {
+        self.routing.stream_for(event_type)
+    }
+
+    async fn send(&self, pipe: &redis::Pipeline) -> Result<(), OutboxError> {
+        let () = pipe
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(|e| {
+                error!("Redis XADD failed: {:?}", e);
+                OutboxError::BrokerError(e.to_string())
+            })?;
+        Ok(())
+    }
+}
+
+impl StreamRouting {
+    /// The stream events of `event_type` are appended to.
+    fn stream_for(&self, event_type: &EventType) -> String {
+        self.streams
+            .get(event_type.as_str())
+            .cloned()
+            .unwrap_or_else(|| format!("{}:{}", self.stream_prefix, event_type.as_str()))
+    }
+
+    fn xadd<P>(
+        &self,
+        event: &Event<P>,
+        mut headers: HashMap<&'static str, String>,
+    ) -> Result<redis::Cmd, OutboxError>
+    where
+        P: Serialize,
+    {
+        headers.insert("created_at", event.created_at.to_string());
+        let headers = serde_json::to_string(&headers)
+            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
+        let payload = serde_json::to_string(&event.payload)
+            .map_err(|e| OutboxError::InfrastructureError(e.to_string()))?;
+
+        let mut cmd = redis::cmd("XADD");
+        cmd.arg(self.stream_for(&event.event_type));
+        if let Some(max_len) = self.max_len {
+            cmd.arg("MAXLEN").arg("~").arg(max_len);
+        }
+        cmd.arg("*")
+            .arg("id")
+            .arg(event.id.as_uuid().to_string())
+            .arg("type")
+            .arg(event.event_type.as_str());
+        if let Some(token) = &event.idempotency_token {
+            cmd.arg("token").arg(token.as_str());
+        }
+        cmd.arg("headers").arg(headers).arg("payload").arg(payload);
+        Ok(cmd)
+    }
+}
+
+#[async_trait]
+impl<P> Transport<P> for RedisStreamTransport
+where
+    P: Debug + Clone + Send + Sync + Serialize + 'static,
+{
+    async fn publish(&self, event: Event<P>) -> Result<(), OutboxError> {
+        let mut pipe = redis::pipe();
+        pipe.add_command(self.routing.xadd(&event, HashMap::new())?)
+            .ignore();
+        self.send(&pipe).await
+    }
+
+    /// The `dlq_*` headers are added to the entry's `headers` field.
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<P>) -> Result<(), OutboxError> {
+        let mut headers = HashMap::from([
+            ("dlq_failure_count", letter.failure_count.to_string()),
+            ("dlq_quarantined_at", letter.quarantined_at.to_string()),
+        ]);
+        if let Some(last_error) = letter.last_error {
+            headers.insert("dlq_last_error", last_error);
+        }
+        if let Some(first_failed_at) = letter.first_failed_at {
+            headers.insert("dlq_first_failed_at", first_failed_at.to_string());
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.add_command(self.routing.xadd(&letter.event, headers)?)
+            .ignore();
+        self.send(&pipe).await
+    }
+}
+
+/// The tests that talk to Redis are ignored by default. Point
+/// `OUTBOX_REDIS_URL` at a server and run
+/// `cargo test -p outbox-redis --features transport -- --ignored`. The test
+/// deletes the streams it wrote when it passes.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use outbox_core::prelude::{IdempotencyToken, Payload};
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, Serialize)]
+    struct TestPayload {
+        order: u32,
+    }
+
+    fn url() -> String {
+        std::env::var("OUTBOX_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string())
+    }
+
+    fn event(event_type: &str, token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new(event_type),
+            Payload::new(TestPayload { order: 7 }),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    fn args(cmd: &redis::Cmd) -> Vec<String> {
+        cmd.args_iter()
+            .map(|arg| match arg {
+                redis::Arg::Simple(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    /// Reads the `(field, value)` pairs of the newest entry of `stream`.
+    async fn last_entry(transport: &RedisStreamTransport, stream: &str) -> HashMap<String, String> {
+        let entries: Vec<(String, HashMap<String, String>)> = redis::cmd("XREVRANGE")
+            .arg(stream)
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            .arg(1)
+            .query_async(&mut transport.connection.clone())
+            .await
+            .unwrap();
+        entries.into_iter().next().unwrap().1
+    }
+
+    #[rstest]
+    fn xadd_carries_id_type_token_headers_and_payload() {
+        let transport_args = |max_len: Option<usize>, e: &Event<TestPayload>| {
+            let mut streams = HashMap::new();
+            streams.insert("OrderPaid".to_owned(), "payments".to_owned());
+            let routing = StreamRouting {
+                stream_prefix: "outbox".to_owned(),
+                streams,
+                max_len,
+            };
+            args(&routing.xadd(e, HashMap::new()).unwrap())
+        };
+        let created = event("OrderCreated", Some("token-1"));
+        let paid = event("OrderPaid", None);
+
+        let created_args = transport_args(None, &created);
+        let paid_args = transport_args(Some(1000), &paid);
+
+        assert_eq!(created_args[..2], ["XADD", "outbox:OrderCreated"]);
+        assert_eq!(created_args[2], "*");
+        assert_eq!(
+            created_args[3..9],
+            [
+                "id".to_string(),
+                created.id.as_uuid().to_string(),
+                "type".to_string(),
+                "OrderCreated".to_string(),
+                "token".to_string(),
+                "token-1".to_string(),
+            ]
+        );
+        assert_eq!(created_args[9], "headers");
+        assert!(created_args[10].contains("created_at"));
+        assert_eq!(created_args[11..], ["payload", "{\"order\":7}"]);
+
+        assert_eq!(
+            paid_args[..6],
+            ["XADD", "payments", "MAXLEN", "~", "1000", "*"]
+        );
+        assert!(!paid_args.contains(&"token".to_string()));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Redis server at OUTBOX_REDIS_URL"]
+    async fn publish_batch_appends_one_entry_per_event() {
+        let prefix = format!("outbox_test_{}", std::process::id());
+        let transport = RedisStreamTransport::new(&url())
+            .await
+            .unwrap()
+            .with_stream_prefix(&prefix)
+            .with_max_len(100);
+        let events = vec![
+            event("OrderCreated", Some("token-1")),
+            event("OrderCreated", None),
+            event("OrderPaid", None),
+        ];
+
+        transport.publish_batch(&events).await.unwrap();
+
+        let mut conn = transport.connection.clone();
+        let created: u64 = redis::cmd("XLEN")
+            .arg(format!("{prefix}:OrderCreated"))
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(created, 2);
+        let paid = last_entry(&transport, &format!("{prefix}:OrderPaid")).await;
+        assert_eq!(paid["id"], events[2].id.as_uuid().to_string());
+        assert_eq!(paid["type"], "OrderPaid");
+        assert_eq!(paid["payload"], "{\"order\":7}");
+
+        let _: i64 = redis::cmd("DEL")
+            .arg(format!("{prefix}:OrderCreated"))
+            .arg(format!("{prefix}:OrderPaid"))
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+    }
+}
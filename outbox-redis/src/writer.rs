@@ -0,0 +1,218 @@
+//! Producer side of the Redis outbox.
+//!
+//! Every insert is one Lua script that checks the batch's idempotency tokens
+//! against `{<key_prefix>}:tokens`, writes the event hashes, indexes them in
+//! the set of their status and publishes a wake-up on
+//! `{<key_prefix>}:outbox_event`. A token that is already taken, or that
+//! appears twice in the batch, fails the whole script before anything is
+//! written.
+
+use crate::storage::{Keys, NOTIFY_CHANNEL, map_err};
+use async_trait::async_trait;
+use outbox_core::prelude::*;
+use redis::aio::MultiplexedConnection;
+use serde::Serialize;
+use std::fmt::Debug;
+use tracing::error;
+
+/// Error code the insert script replies with when an idempotency token is
+/// already taken.
+const DUPLICATE_CODE: &str = "OUTBOX_DUPLICATE";
+
+const INSERT_SCRIPT: &str = r"
+local fields = 7
+local count = (#ARGV - 2) / fields
+local seen = {}
+for i = 0, count - 1 do
+    local token = ARGV[3 + i * fields + 3]
+    if token ~= '' then
+        if seen[token] or redis.call('HEXISTS', KEYS[4], token) == 1 then
+            return redis.error_reply('OUTBOX_DUPLICATE idempotency token ' .. token .. ' is taken')
+        end
+        seen[token] = true
+    end
+end
+for i = 0, count - 1 do
+    local base = 3 + i * fields
+    local id = ARGV[base]
+    local key = ARGV[1] .. id
+    redis.call('HSET', key, 'event_type', ARGV[base + 1], 'payload', ARGV[base + 2],
+        'status', ARGV[base + 4], 'created_at', ARGV[base + 5], 'locked_until', ARGV[base + 6])
+    if ARGV[base + 3] ~= '' then
+        redis.call('HSET', key, 'idempotency_token', ARGV[base + 3])
+        redis.call('HSET', KEYS[4], ARGV[base + 3], id)
+    end
+    if ARGV[base + 4] == 'Pending' then
+        redis.call('ZADD', KEYS[1], ARGV[base + 5], id)
+    elseif ARGV[base + 4] == 'Processing' then
+        redis.call('ZADD', KEYS[2], ARGV[base + 6], id)
+    else
+        redis.call('ZADD', KEYS[3], ARGV[base + 5], id)
+    end
+end
+redis.call('PUBLISH', ARGV[2], count)
+return count
+";
+
+/// Inserts events into a [`RedisOutbox`](crate::storage::RedisOutbox).
+///
+/// As an [`OutboxWriter`] it runs the insert script directly. As an
+/// [`OutboxTxWriter`] over [`redis::Pipeline`] it appends the script to the
+/// caller's pipeline instead; make the pipeline
+/// [`atomic`](redis::Pipeline::atomic) to get a `MULTI`/`EXEC` block that
+/// applies the events together with your own commands.
+///
+/// Redis does not roll back a `MULTI` block. If a token is already taken, the
+/// pipeline fails with a server error whose code is `OUTBOX_DUPLICATE`; none
+/// of the events are written, but the block's other commands are.
+#[derive(Clone)]
+pub struct RedisWriter {
+    connection: MultiplexedConnection,
+    keys: Keys,
+}
+
+impl RedisWriter {
+    /// Creates a new `RedisWriter` and establishes a connection to Redis.
+    /// Keys are prefixed with `outbox` unless
+    /// [`with_key_prefix`](Self::with_key_prefix) says otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboxError::InfrastructureError`] if:
+    /// - The `connection_info` string is not a valid Redis URL.
+    /// - The connection to the Redis server cannot be established.
+    pub async fn new(connection_info: &str) -> Result<Self, OutboxError> {
+        let client = redis::Client::open(connection_info)
+            .map_err(|e| OutboxError::InfrastructureError(format!("Invalid Redis URL: {e}")))?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| {
+                error!("Redis connection failed: {:?}", e);
+                OutboxError::InfrastructureError("Redis connection failed".to_string())
+            })?;
+        Ok(Self::from_parts(connection, Keys::new("outbox")))
+    }
+
+    pub(crate) fn from_parts(connection: MultiplexedConnection, keys: Keys) -> Self {
+        Self { connection, keys }
+    }
+
+    /// Sets the prefix of every key this writer uses. It must match the
+    /// prefix of the [`RedisOutbox`](crate::storage::RedisOutbox). Defaults
+    /// to `outbox`.
+    #[must_use]
+    pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+        self.keys = Keys::new(key_prefix);
+        self
+    }
+
+    fn script_keys(&self) -> [String; 4] {
+        [
+            self.keys.pending(),
+            self.keys.processing(),
+            self.keys.sent(),
+            self.keys.tokens(),
+        ]
+    }
+
+    fn script_args<P>(&self, events: &[Event<P>]) -> Result<Vec<String>, OutboxError>
+    where
+        P: Debug + Clone + Serialize + Send + Sync,
+    {
+        let mut args = Vec::with_capacity(2 + events.len() * 7);
+        args.push(self.keys.event_prefix());
+        args.push(self.keys.channel(NOTIFY_CHANNEL));
+        for event in events {
+            args.push(event.id.to_string());
+            args.push(event.event_type.as_str().to_string());
+            args.push(
+                serde_json::to_string(&event.payload)
+                    .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+            );
+            args.push(
+                event
+                    .idempotency_token
+                    .as_ref()
+                    .map(|t| t.as_str().to_string())
+                    .unwrap_or_default(),
+            );
+            args.push(event.status.as_str().to_string());
+            args.push(to_unix_micros(event.created_at).to_string());
+            args.push(to_unix_micros(event.locked_until).to_string());
+        }
+        Ok(args)
+    }
+}
+
+#[async_trait]
+impl<P> OutboxWriter<P> for RedisWriter
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        self.insert_events(vec![event]).await
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let script = redis::Script::new(INSERT_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        for key in self.script_keys() {
+            invocation.key(key);
+        }
+        for arg in self.script_args(&events)? {
+            invocation.arg(arg);
+        }
+        let _: i64 = invocation
+            .invoke_async(&mut self.connection.clone())
+            .await
+            .map_err(|e| map_insert_error(&e))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P> OutboxTxWriter<P, redis::Pipeline> for RedisWriter
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut redis::Pipeline,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        self.insert_events_in(tx, vec![event]).await
+    }
+
+    /// Appends one `EVAL` of the insert script to `tx`. Its reply is ignored,
+    /// so it does not change the pipeline's result type.
+    async fn insert_events_in(
+        &self,
+        tx: &mut redis::Pipeline,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let keys = self.script_keys();
+        tx.cmd("EVAL")
+            .arg(INSERT_SCRIPT)
+            .arg(keys.len())
+            .arg(&keys)
+            .arg(self.script_args(&events)?)
+            .ignore();
+        Ok(())
+    }
+}
+
+/// Reports a token the insert script found taken as [`OutboxError::DuplicateEvent`].
+fn map_insert_error(e: &redis::RedisError) -> OutboxError {
+    if e.code() == Some(DUPLICATE_CODE) {
+        OutboxError::DuplicateEvent
+    } else {
+        map_err(e)
+    }
+}
@@ -0,0 +1,25 @@
+-- Outbox table for the SQLite backend.
+--
+-- SQLite has no uuid, enum or timestamptz types, so ids are stored as
+-- hyphenated UUID text, the status as text guarded by a CHECK constraint and
+-- every timestamp as microseconds since the Unix epoch. A fresh row is
+-- unlocked with `locked_until = 0`.
+
+create table outbox_events
+(
+    id                text    primary key not null,
+    idempotency_token text             default null,
+    event_type        text    not null,
+    payload           text    not null,
+    status            text    not null default 'Pending'
+        check (status in ('Pending', 'Processing', 'Sent')),
+    created_at        integer not null,
+    locked_until      integer not null default 0
+);
+
+create index idx_outbox_processing_queue
+    on outbox_events (locked_until asc, status)
+    where status in ('Pending', 'Processing');
+
+create unique index idx_outbox_idempotency
+    on outbox_events (idempotency_token);
@@ -0,0 +1,38 @@
+-- Dead-letter table and failure bookkeeping for the SQLite backend.
+--
+-- `failure_count`, `last_error` and `first_failed_at` on `outbox_events` back
+-- the SQLite `DlqHeap`; `revival_count` counts automatic revivals. The DLQ
+-- reaper moves rows that crossed the threshold into `outbox_dead_letters`
+-- in one transaction. Timestamps are microseconds since the Unix epoch, as
+-- in `outbox_events`.
+
+alter table outbox_events add column failure_count integer not null default 0;
+alter table outbox_events add column last_error text default null;
+alter table outbox_events add column first_failed_at integer default null;
+alter table outbox_events add column revival_count integer not null default 0;
+
+create index idx_outbox_failure_count
+    on outbox_events (failure_count)
+    where failure_count > 0;
+
+create table outbox_dead_letters
+(
+    id                text    primary key not null,
+    idempotency_token text             default null,
+    event_type        text    not null,
+    payload           text    not null,
+    original_status   text    not null,
+    created_at        integer not null,
+    locked_until      integer not null,
+    failure_count     integer not null,
+    last_error        text             default null,
+    first_failed_at   integer          default null,
+    revival_count     integer not null default 0,
+    quarantined_at    integer not null
+);
+
+create index idx_outbox_dlq_quarantined_at
+    on outbox_dead_letters (quarantined_at desc);
+
+create index idx_outbox_dlq_event_type
+    on outbox_dead_letters (event_type);
@@ -0,0 +1,11 @@
+-- Claims taken by `drain_exceeded`, in microseconds since the Unix epoch.
+--
+-- A drained row keeps its failure counters until `ack_drained` resets them
+-- after the quarantine committed; `reclaim_stale` clears claims that were
+-- never acknowledged so the row is drained again.
+
+alter table outbox_events add column dlq_claimed_at integer default null;
+
+create index idx_outbox_dlq_claimed_at
+    on outbox_events (dlq_claimed_at)
+    where dlq_claimed_at is not null;
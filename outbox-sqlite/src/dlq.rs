@@ -0,0 +1,166 @@
+//! SQLite-backed [`DlqHeap`] implementation.
+//!
+//! Failure counters live on the outbox row itself, in the `failure_count`,
+//! `last_error` and `first_failed_at` columns added by the `dlq` migrations,
+//! just as in the Postgres backend:
+//! - `record_failure` → `UPDATE .. SET failure_count = failure_count + 1`,
+//!   overwriting `last_error` and setting `first_failed_at` if unset
+//! - `record_success` → reset the counter, error, timestamp and claim
+//! - `drain_exceeded` → one `UPDATE .. RETURNING` that claims up to the
+//!   policy's batch size of unclaimed rows whose `failure_count` reached the
+//!   threshold for their `event_type`, or whose `first_failed_at` is older
+//!   than the policy's maximum failure age, by setting `dlq_claimed_at`. Per-type thresholds are passed as one JSON
+//!   object. SQLite runs the statement under its write lock, so concurrent
+//!   callers never drain the same row twice.
+//! - `ack_drained` → reset the counter, error, timestamp and claim of rows
+//!   still in the outbox
+//! - `reclaim_stale` → clear `dlq_claimed_at` on claims older than the
+//!   timeout, so the next drain returns those rows again
+
+use crate::SqliteOutbox;
+use async_trait::async_trait;
+use outbox_core::prelude::{
+    DlqEntry, DlqHeap, DlqPolicy, EventId, EventType, OutboxError, from_unix_micros, to_unix_micros,
+};
+use serde::Serialize;
+use std::fmt::Debug;
+use std::time::Duration;
+
+#[async_trait]
+impl<P> DlqHeap for SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    async fn record_failure(
+        &self,
+        id: EventId,
+        _event_type: &EventType,
+        error: &OutboxError,
+    ) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = failure_count + 1,
+                last_error = ?2,
+                first_failed_at = COALESCE(first_failed_at, ?3)
+            WHERE id = ?1
+            ",
+        )
+        .bind(id.to_string())
+        .bind(error.to_string())
+        .bind(self.now())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn record_success(&self, id: EventId) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id = ?1
+                AND failure_count > 0
+            ",
+        )
+        .bind(id.to_string())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn drain_exceeded(&self, policy: &DlqPolicy) -> Result<Vec<DlqEntry>, OutboxError> {
+        let thresholds = serde_json::to_string(&policy.type_thresholds)
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        let now = self.inner.config.clock.now();
+        let failing_since = policy.failing_since_cutoff(now).map(to_unix_micros);
+
+        let rows: Vec<(String, i64, Option<String>, Option<i64>, String)> = sqlx::query_as(
+            r"
+            UPDATE outbox_events
+            SET dlq_claimed_at = ?4
+            WHERE id IN (
+                SELECT o.id FROM outbox_events AS o
+                WHERE o.failure_count > 0
+                    AND o.dlq_claimed_at IS NULL
+                    AND (
+                        o.failure_count >= COALESCE(
+                            (SELECT t.value FROM json_each(?2) AS t WHERE t.key = o.event_type),
+                            ?1
+                        )
+                        OR o.first_failed_at <= ?3
+                    )
+                LIMIT ?5
+            )
+            RETURNING id, failure_count, last_error, first_failed_at, event_type
+            ",
+        )
+        .bind(i64::from(policy.threshold))
+        .bind(thresholds)
+        .bind(failing_since)
+        .bind(to_unix_micros(now))
+        .bind(i64::from(policy.batch_size))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(
+                |(id, failure_count, last_error, first_failed_at, event_type)| {
+                    Ok(DlqEntry::new(
+                        id.parse()?,
+                        u32::try_from(failure_count).unwrap_or(0),
+                        last_error,
+                    )
+                    .with_first_failed_at(first_failed_at.map(from_unix_micros).transpose()?)
+                    .with_event_type(EventType::load(&event_type)))
+                },
+            )
+            .collect()
+    }
+
+    async fn ack_drained(&self, ids: &[EventId]) -> Result<(), OutboxError> {
+        let ids = serde_json::Value::from(ids.iter().map(ToString::to_string).collect::<Vec<_>>());
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET failure_count = 0,
+                last_error = NULL,
+                first_failed_at = NULL,
+                dlq_claimed_at = NULL
+            WHERE id IN (SELECT value FROM json_each(?1))
+            ",
+        )
+        .bind(ids.to_string())
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn reclaim_stale(&self, timeout: Duration) -> Result<u64, OutboxError> {
+        let Some(cutoff) = time::Duration::try_from(timeout)
+            .ok()
+            .and_then(|timeout| self.inner.config.clock.now().checked_sub(timeout))
+        else {
+            return Ok(0);
+        };
+        let result = sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET dlq_claimed_at = NULL
+            WHERE dlq_claimed_at <= ?1
+            ",
+        )
+        .bind(to_unix_micros(cutoff))
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,899 @@
+//! SQLite storage backend for `outbox-core`, built on sqlx's SQLite driver.
+//!
+//! [`SqliteOutbox`] implements [`OutboxStorage`] (and, with the `dlq`
+//! feature, the quarantine methods and [`DlqHeap`]) over an `outbox_events`
+//! table created by [`migrate`]. [`SqliteWriter`] inserts events through a
+//! pool, a connection or a transaction, so an event can be committed
+//! together with the business data it describes.
+//!
+//! SQLite serializes writers on the whole database, so a claim is a single
+//! `UPDATE .. RETURNING` statement and two workers never receive the same
+//! row; multi-statement moves run in `BEGIN IMMEDIATE` transactions. There
+//! is no `LISTEN`/`NOTIFY`: `wait_for_notification` returns after the poll
+//! interval, or earlier when an insert made through the [`SqliteOutbox`]
+//! itself (or a call to [`SqliteOutbox::wake`]) wakes it in process.
+
+#[cfg(feature = "dlq")]
+mod dlq;
+mod migrate;
+mod row;
+
+pub use migrate::{migrate, verify_schema};
+
+use crate::row::{EVENT_COLUMNS, EventRow, encode_payload, event_from_row, id_list};
+use async_trait::async_trait;
+use outbox_core::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool, Transaction};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::debug;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    inner: Arc<SqliteOutboxInner<P>>,
+    poll_interval: Duration,
+}
+
+impl<P> SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new(pool: SqlitePool, config: Arc<OutboxConfig<P>>) -> Self {
+        Self {
+            inner: Arc::new(SqliteOutboxInner {
+                pool,
+                config,
+                notify: Notify::new(),
+            }),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets how long `wait_for_notification` waits for an in-process wake-up
+    /// before returning anyway, so that events written by other processes or
+    /// through [`SqliteWriter`] are picked up. Defaults to one second.
+    #[must_use]
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Wakes a worker waiting in `wait_for_notification` of this process.
+    ///
+    /// Inserts made through the [`SqliteOutbox`] itself do this already;
+    /// call it after committing a transaction written through
+    /// [`SqliteWriter`] to skip the wait for the next poll.
+    pub fn wake(&self) {
+        self.inner.notify.notify_one();
+    }
+
+    #[cfg(feature = "dlq")]
+    fn now(&self) -> i64 {
+        to_unix_micros(self.inner.config.clock.now())
+    }
+}
+
+struct SqliteOutboxInner<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pool: SqlitePool,
+    config: Arc<OutboxConfig<P>>,
+    notify: Notify,
+}
+
+#[async_trait]
+impl<P> OutboxStorage<P> for SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn fetch_next_to_process(&self, limit: u32) -> Result<Vec<Event<P>>, OutboxError> {
+        let now = self.inner.config.clock.now();
+        let locked_until = now + time::Duration::minutes(self.inner.config.lock_timeout_mins);
+        let rows: Vec<EventRow> = sqlx::query_as(&format!(
+            r"
+            UPDATE outbox_events
+            SET status = 'Processing',
+                locked_until = ?2
+            WHERE id IN (
+                SELECT id
+                FROM outbox_events
+                WHERE status = 'Pending'
+                    OR (status = 'Processing' AND locked_until < ?3)
+                ORDER BY locked_until ASC
+                LIMIT ?1
+            )
+            RETURNING {EVENT_COLUMNS}
+            "
+        ))
+        .bind(i64::from(limit))
+        .bind(to_unix_micros(locked_until))
+        .bind(to_unix_micros(now))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        rows.into_iter().map(event_from_row).collect()
+    }
+
+    async fn update_status(&self, ids: &[EventId], status: EventStatus) -> Result<(), OutboxError> {
+        sqlx::query(
+            r"
+            UPDATE outbox_events
+            SET status = ?1
+            WHERE id IN (SELECT value FROM json_each(?2))
+            ",
+        )
+        .bind(status.as_str())
+        .bind(id_list(ids))
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_garbage(&self) -> Result<(), OutboxError> {
+        let cutoff =
+            self.inner.config.clock.now() - time::Duration::days(self.inner.config.retention_days);
+        let result = sqlx::query(
+            r"
+            DELETE
+            FROM outbox_events
+            WHERE id IN (
+                SELECT id FROM outbox_events
+                WHERE status = 'Sent'
+                    AND created_at < ?1
+                LIMIT 5000
+            )",
+        )
+        .bind(to_unix_micros(cutoff))
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: deleted {} old messages",
+            result.rows_affected()
+        );
+        Ok(())
+    }
+
+    async fn wait_for_notification(&self, _channel: &str) -> Result<(), OutboxError> {
+        tokio::select! {
+            () = self.inner.notify.notified() => {}
+            () = tokio::time::sleep(self.poll_interval) => {}
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn fetch_events(&self, ids: &[EventId]) -> Result<Vec<Event<P>>, OutboxError> {
+        let rows: Vec<EventRow> = sqlx::query_as(&format!(
+            r"
+            SELECT {EVENT_COLUMNS}
+            FROM outbox_events
+            WHERE id IN (SELECT value FROM json_each(?1))
+            "
+        ))
+        .bind(id_list(ids))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        rows.into_iter().map(event_from_row).collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn delete_events(&self, ids: &[EventId]) -> Result<u64, OutboxError> {
+        let result =
+            sqlx::query("DELETE FROM outbox_events WHERE id IN (SELECT value FROM json_each(?1))")
+                .bind(id_list(ids))
+                .execute(&self.inner.pool)
+                .await
+                .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "DLQ reaper: deleted {} events published to the DLQ destination",
+            result.rows_affected()
+        );
+        Ok(result.rows_affected())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn purge_dead_letters(&self) -> Result<(), OutboxError> {
+        let cutoff = self.inner.config.clock.now()
+            - time::Duration::days(self.inner.config.dlq_retention_days);
+        let result = sqlx::query(
+            r"
+            DELETE
+            FROM outbox_dead_letters
+            WHERE id IN (
+                SELECT id FROM outbox_dead_letters
+                WHERE quarantined_at < ?1
+                ORDER BY quarantined_at
+                LIMIT 5000
+            )",
+        )
+        .bind(to_unix_micros(cutoff))
+        .execute(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        debug!(
+            "Garbage collector: purged {} expired dead letters",
+            result.rows_affected()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn quarantine_events(&self, entries: &[DlqEntry]) -> Result<Vec<DlqEntry>, OutboxError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let failures = serde_json::Value::from(
+            entries
+                .iter()
+                .map(|e| {
+                    serde_json::json!({
+                        "id": e.id.to_string(),
+                        "failure_count": e.failure_count,
+                        "last_error": e.last_error,
+                        "first_failed_at": e.first_failed_at.map(to_unix_micros),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let mut tx = self.begin_immediate().await?;
+        let moved: Vec<(String, String)> = sqlx::query_as(
+            r"
+            INSERT INTO outbox_dead_letters (
+                id,
+                idempotency_token,
+                event_type,
+                payload,
+                original_status,
+                created_at,
+                locked_until,
+                failure_count,
+                last_error,
+                first_failed_at,
+                revival_count,
+                quarantined_at
+            )
+            SELECT
+                o.id,
+                o.idempotency_token,
+                o.event_type,
+                o.payload,
+                o.status,
+                o.created_at,
+                o.locked_until,
+                f.value ->> '$.failure_count',
+                f.value ->> '$.last_error',
+                f.value ->> '$.first_failed_at',
+                o.revival_count,
+                ?2
+            FROM outbox_events AS o
+            JOIN json_each(?1) AS f ON o.id = f.value ->> '$.id'
+            RETURNING id, event_type
+            ",
+        )
+        .bind(failures.to_string())
+        .bind(self.now())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        let moved_ids =
+            serde_json::Value::from(moved.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>());
+        sqlx::query("DELETE FROM outbox_events WHERE id IN (SELECT value FROM json_each(?1))")
+            .bind(moved_ids.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        debug!(
+            "DLQ reaper: quarantined {}/{} events",
+            moved.len(),
+            entries.len()
+        );
+        let event_types: std::collections::HashMap<String, String> = moved.into_iter().collect();
+        Ok(entries
+            .iter()
+            .filter_map(|entry| {
+                event_types
+                    .get(&entry.id.to_string())
+                    .map(|t| entry.clone().with_event_type(EventType::load(t)))
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn revive_dead_letters(
+        &self,
+        cooldown: Duration,
+        max_revivals: u32,
+    ) -> Result<Vec<EventId>, OutboxError> {
+        let cutoff = self.inner.config.clock.now() - cooldown;
+
+        let mut tx = self.begin_immediate().await?;
+        let revived: Vec<String> = sqlx::query_scalar(
+            r"
+            INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until, revival_count)
+            SELECT d.id, d.idempotency_token, d.event_type, d.payload, 'Pending', d.created_at, 0, d.revival_count + 1
+            FROM outbox_dead_letters AS d
+            WHERE d.quarantined_at <= ?1
+                AND d.revival_count < ?2
+                AND NOT EXISTS (
+                    SELECT 1 FROM outbox_events AS o
+                    WHERE o.idempotency_token = d.idempotency_token
+                )
+            ORDER BY d.quarantined_at
+            LIMIT 5000
+            RETURNING id
+            ",
+        )
+        .bind(to_unix_micros(cutoff))
+        .bind(i64::from(max_revivals))
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| map_insert_error(&e))?;
+
+        sqlx::query(
+            "DELETE FROM outbox_dead_letters WHERE id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(serde_json::Value::from(revived.clone()).to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+
+        if !revived.is_empty() {
+            self.wake();
+        }
+        debug!("DLQ reaper: revived {} dead letters", revived.len());
+        revived.iter().map(|id| id.parse()).collect()
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn missing_events(&self, ids: &[EventId]) -> Result<Vec<EventId>, OutboxError> {
+        let missing: Vec<String> = sqlx::query_scalar(
+            r"
+            SELECT t.value
+            FROM json_each(?1) AS t
+            WHERE NOT EXISTS (SELECT 1 FROM outbox_events o WHERE o.id = t.value)
+            ",
+        )
+        .bind(id_list(ids))
+        .fetch_all(&self.inner.pool)
+        .await
+        .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+        missing.iter().map(|id| id.parse()).collect()
+    }
+}
+
+impl<P> SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    /// Starts a transaction that takes the database write lock right away,
+    /// so a read followed by a write inside it cannot fail with `SQLITE_BUSY`
+    /// halfway.
+    #[cfg(feature = "dlq")]
+    async fn begin_immediate(&self) -> Result<Transaction<'static, Sqlite>, OutboxError> {
+        self.inner
+            .pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))
+    }
+}
+
+/// Writes through the [`SqliteOutbox`]'s own pool and wakes this process'
+/// workers right away.
+#[async_trait]
+impl<P> OutboxWriter<P> for SqliteOutbox<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        insert_event(&self.inner.pool, event).await?;
+        self.wake();
+        Ok(())
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        insert_events(&self.inner.pool, events).await?;
+        self.wake();
+        Ok(())
+    }
+}
+
+/// Inserts events through the executor `E`, usually a [`SqlitePool`].
+///
+/// `SqliteWriter<SqlitePool>` is also an [`OutboxTxWriter`] for [`Transaction`] and
+/// [`SqliteConnection`]; those inserts go through the caller's transaction and
+/// never touch the pool.
+pub struct SqliteWriter<E>(pub E);
+
+#[async_trait]
+impl<E, P> OutboxWriter<P> for SqliteWriter<E>
+where
+    for<'c> &'c E: Executor<'c, Database = Sqlite>,
+    E: Send + Sync,
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event(&self, event: Event<P>) -> Result<(), OutboxError> {
+        insert_event(&self.0, event).await
+    }
+
+    async fn insert_events(&self, events: Vec<Event<P>>) -> Result<(), OutboxError> {
+        insert_events(&self.0, events).await
+    }
+}
+
+#[async_trait]
+impl<P> OutboxTxWriter<P, SqliteConnection> for SqliteWriter<SqlitePool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut SqliteConnection,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut SqliteConnection,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(tx, events).await
+    }
+}
+
+#[async_trait]
+impl<'t, P> OutboxTxWriter<P, Transaction<'t, Sqlite>> for SqliteWriter<SqlitePool>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn insert_event_in(
+        &self,
+        tx: &mut Transaction<'t, Sqlite>,
+        event: Event<P>,
+    ) -> Result<(), OutboxError> {
+        insert_event(&mut **tx, event).await
+    }
+
+    async fn insert_events_in(
+        &self,
+        tx: &mut Transaction<'t, Sqlite>,
+        events: Vec<Event<P>>,
+    ) -> Result<(), OutboxError> {
+        insert_events(&mut **tx, events).await
+    }
+}
+
+async fn insert_event<'e, X, P>(executor: X, event: Event<P>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = Sqlite>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    sqlx::query(
+        r"
+        INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ",
+    )
+    .bind(event.id.to_string())
+    .bind(event.idempotency_token.as_ref().map(IdempotencyToken::as_str))
+    .bind(event.event_type.as_str())
+    .bind(encode_payload(&event)?)
+    .bind(event.status.as_str())
+    .bind(to_unix_micros(event.created_at))
+    .bind(to_unix_micros(event.locked_until))
+    .execute(executor)
+    .await
+    .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+/// Inserts the whole batch with one statement, reading the rows from a JSON
+/// array with `json_each`, so the insert is all-or-nothing without needing
+/// a transaction of its own.
+async fn insert_events<'e, X, P>(executor: X, events: Vec<Event<P>>) -> Result<(), OutboxError>
+where
+    X: Executor<'e, Database = Sqlite>,
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    if events.is_empty() {
+        return Ok(());
+    }
+    let rows = events
+        .iter()
+        .map(|event| {
+            Ok(serde_json::json!({
+                "id": event.id.to_string(),
+                "idempotency_token": event.idempotency_token.as_ref().map(IdempotencyToken::as_str),
+                "event_type": event.event_type.as_str(),
+                "payload": encode_payload(event)?,
+                "status": event.status.as_str(),
+                "created_at": to_unix_micros(event.created_at),
+                "locked_until": to_unix_micros(event.locked_until),
+            }))
+        })
+        .collect::<Result<Vec<_>, OutboxError>>()?;
+
+    sqlx::query(
+        r"
+        INSERT INTO outbox_events (id, idempotency_token, event_type, payload, status, created_at, locked_until)
+        SELECT
+            value ->> '$.id',
+            value ->> '$.idempotency_token',
+            value ->> '$.event_type',
+            value ->> '$.payload',
+            value ->> '$.status',
+            value ->> '$.created_at',
+            value ->> '$.locked_until'
+        FROM json_each(?1)
+        ",
+    )
+    .bind(serde_json::Value::from(rows).to_string())
+    .execute(executor)
+    .await
+    .map_err(|e| map_insert_error(&e))?;
+
+    Ok(())
+}
+
+/// Reports a violation of `idx_outbox_idempotency` as [`OutboxError::DuplicateEvent`].
+/// SQLite does not report the index name, only the offending column.
+fn map_insert_error(e: &sqlx::Error) -> OutboxError {
+    if let Some(db) = e.as_database_error()
+        && db.is_unique_violation()
+        && db.message().contains("outbox_events.idempotency_token")
+    {
+        return OutboxError::DuplicateEvent;
+    }
+    OutboxError::DatabaseError(e.to_string())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use time::OffsetDateTime;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    struct TestPayload(u32);
+
+    async fn pool() -> SqlitePool {
+        // One connection that never closes: every `sqlite::memory:`
+        // connection opens its own, empty database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::migrate(&pool).await.unwrap();
+        pool
+    }
+
+    async fn outbox(config: OutboxConfig<TestPayload>) -> SqliteOutbox<TestPayload> {
+        SqliteOutbox::new(pool().await, Arc::new(config))
+    }
+
+    fn event(token: Option<&str>) -> Event<TestPayload> {
+        Event::new(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            token.map(|t| IdempotencyToken::new(t.to_string())),
+        )
+    }
+
+    async fn count(outbox: &SqliteOutbox<TestPayload>, status: &EventStatus) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events WHERE status = ?1")
+            .bind(status.as_str())
+            .fetch_one(&outbox.inner.pool)
+            .await
+            .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn fetch_claims_pending_events_and_locks_them() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        let inserted = event(Some("token-1"));
+        outbox.insert_event(inserted.clone()).await.unwrap();
+        outbox
+            .insert_events(vec![event(None), event(None)])
+            .await
+            .unwrap();
+
+        let claimed = outbox.fetch_next_to_process(2).await.unwrap();
+
+        assert_eq!(claimed.len(), 2);
+        assert!(claimed.iter().all(|e| e.status == EventStatus::Processing));
+        assert!(
+            claimed
+                .iter()
+                .all(|e| e.locked_until > OffsetDateTime::now_utc())
+        );
+        assert_eq!(count(&outbox, &EventStatus::Processing).await, 2);
+
+        let rest = outbox.fetch_next_to_process(10).await.unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        let round_trip = claimed
+            .iter()
+            .chain(&rest)
+            .find(|e| e.id == inserted.id)
+            .unwrap();
+        assert_eq!(
+            round_trip
+                .idempotency_token
+                .as_ref()
+                .map(IdempotencyToken::as_str),
+            Some("token-1")
+        );
+        assert_eq!(round_trip.payload.as_value(), &TestPayload(1));
+        // Stored at microsecond precision.
+        assert_eq!(
+            to_unix_micros(round_trip.created_at),
+            to_unix_micros(inserted.created_at)
+        );
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn locks_expire_on_the_configured_clock() {
+        let clock = ManualClock::default();
+        let outbox = outbox(OutboxConfig::default().with_clock(Arc::new(clock.clone()))).await;
+        outbox.insert_event(event(None)).await.unwrap();
+
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+        assert!(outbox.fetch_next_to_process(10).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_mins(6));
+        assert_eq!(outbox.fetch_next_to_process(10).await.unwrap().len(), 1);
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn update_status_marks_events_sent() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        outbox
+            .insert_events(vec![event(None), event(None)])
+            .await
+            .unwrap();
+        let ids: Vec<EventId> = outbox
+            .fetch_next_to_process(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+
+        outbox.update_status(&ids, EventStatus::Sent).await.unwrap();
+
+        assert_eq!(count(&outbox, &EventStatus::Sent).await, 2);
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn insert_rejects_duplicate_idempotency_token() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let err = outbox
+            .insert_event(event(Some("token-1")))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, OutboxError::DuplicateEvent));
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn insert_events_is_all_or_nothing() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let err = outbox
+            .insert_events(vec![event(Some("token-2")), event(Some("token-1"))])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, OutboxError::DuplicateEvent));
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 1);
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn writer_inserts_within_the_callers_transaction() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        let writer = SqliteWriter(outbox.inner.pool.clone());
+
+        let mut tx = outbox.inner.pool.begin().await.unwrap();
+        writer
+            .insert_events_in(&mut tx, vec![event(None), event(None)])
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 0);
+
+        let mut tx = outbox.inner.pool.begin().await.unwrap();
+        writer.insert_event_in(&mut tx, event(None)).await.unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 1);
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn delete_garbage_removes_only_old_sent_events() {
+        let clock = ManualClock::default();
+        let config = OutboxConfig::default().with_clock(Arc::new(clock.clone()));
+        let outbox = outbox(config).await;
+        let old = Event::new_with_clock(
+            EventType::new("OrderCreated"),
+            Payload::new(TestPayload(1)),
+            None,
+            &clock,
+        );
+        outbox
+            .insert_events(vec![old.clone(), event(None)])
+            .await
+            .unwrap();
+        outbox
+            .update_status(&[old.id], EventStatus::Sent)
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_hours(24 * 8));
+        outbox.delete_garbage().await.unwrap();
+
+        assert_eq!(count(&outbox, &EventStatus::Sent).await, 0);
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 1);
+    }
+
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn verify_schema_requires_migrations() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        let err = crate::verify_schema(&pool).await.unwrap_err();
+        assert!(matches!(err, OutboxError::ConfigError(_)));
+
+        crate::migrate(&pool).await.unwrap();
+        crate::verify_schema(&pool).await.unwrap();
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn exceeded_events_are_quarantined_and_revived() {
+        let clock = ManualClock::default();
+        let outbox = outbox(OutboxConfig::default().with_clock(Arc::new(clock.clone()))).await;
+        let failing = event(Some("token-1"));
+        outbox
+            .insert_events(vec![failing.clone(), event(None)])
+            .await
+            .unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..3 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+
+        let drained = outbox.drain_exceeded(&DlqPolicy::new(3)).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 3);
+        assert!(drained[0].first_failed_at.is_some());
+        assert!(
+            outbox
+                .drain_exceeded(&DlqPolicy::new(3))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let moved = outbox.quarantine_events(&drained).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(
+            moved[0].event_type.as_ref().unwrap().as_str(),
+            "OrderCreated"
+        );
+        assert_eq!(
+            outbox.missing_events(&[failing.id]).await.unwrap(),
+            vec![failing.id]
+        );
+
+        let cooldown = Duration::from_mins(10);
+        assert!(
+            outbox
+                .revive_dead_letters(cooldown, 3)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        clock.advance(cooldown);
+        assert_eq!(
+            outbox.revive_dead_letters(cooldown, 3).await.unwrap(),
+            vec![failing.id]
+        );
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 2);
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn unacked_claim_is_drained_again_once_reclaimed() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        let failing = event(None);
+        outbox.insert_events(vec![failing.clone()]).await.unwrap();
+        let error = OutboxError::BrokerError("unreachable".to_string());
+        for _ in 0..2 {
+            outbox
+                .record_failure(failing.id, &failing.event_type, &error)
+                .await
+                .unwrap();
+        }
+        let policy = DlqPolicy::new(2);
+
+        assert_eq!(outbox.drain_exceeded(&policy).await.unwrap().len(), 1);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 1);
+        let drained = outbox.drain_exceeded(&policy).await.unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].failure_count, 2);
+
+        outbox.ack_drained(&[failing.id]).await.unwrap();
+        assert_eq!(outbox.reclaim_stale(Duration::ZERO).await.unwrap(), 0);
+        assert!(outbox.drain_exceeded(&policy).await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "dlq")]
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn revive_skips_dead_letters_whose_token_is_taken_again() {
+        let outbox = outbox(OutboxConfig::default()).await;
+        let (taken, free) = (event(Some("token-1")), event(None));
+        let ids = [taken.id, free.id];
+        outbox.insert_events(vec![taken, free]).await.unwrap();
+        let entries: Vec<DlqEntry> = ids.iter().map(|id| DlqEntry::new(*id, 5, None)).collect();
+        outbox.quarantine_events(&entries).await.unwrap();
+        outbox.insert_event(event(Some("token-1"))).await.unwrap();
+
+        let revived = outbox.revive_dead_letters(Duration::ZERO, 3).await.unwrap();
+
+        assert_eq!(revived, vec![ids[1]]);
+        assert_eq!(count(&outbox, &EventStatus::Pending).await, 2);
+    }
+}
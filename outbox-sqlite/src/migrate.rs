@@ -0,0 +1,101 @@
+//! Embedded schema migrations for the SQLite outbox.
+//!
+//! The SQL files shipped in `migrations/` are compiled into the crate with
+//! [`sqlx::migrate!`]. The base outbox schema lives in the top-level folder;
+//! migrations that only matter for the dead-letter queue live in
+//! `migrations/dlq/` and are applied only when the crate is built with its
+//! `dlq` feature.
+//!
+//! Both sets share sqlx's `_sqlx_migrations` bookkeeping table, so each
+//! migrator is run with `ignore_missing` enabled — otherwise the base set
+//! would reject the versions recorded by the DLQ set and vice versa.
+
+use outbox_core::prelude::OutboxError;
+use sqlx::SqlitePool;
+use sqlx::migrate::Migrator;
+use std::collections::HashSet;
+
+fn migrators() -> Vec<Migrator> {
+    let mut base = sqlx::migrate!("./migrations");
+    base.set_ignore_missing(true);
+
+    #[cfg(feature = "dlq")]
+    {
+        let mut dlq = sqlx::migrate!("./migrations/dlq");
+        dlq.set_ignore_missing(true);
+        vec![base, dlq]
+    }
+    #[cfg(not(feature = "dlq"))]
+    vec![base]
+}
+
+/// Applies every embedded outbox migration that has not been applied yet.
+///
+/// The DLQ migrations are included only when the `dlq` feature is
+/// enabled. Safe to call on every startup — already applied versions are
+/// skipped.
+///
+/// # Errors
+///
+/// Returns [`OutboxError::DatabaseError`] if a migration fails to apply
+/// or an already applied migration has been modified.
+pub async fn migrate(pool: &SqlitePool) -> Result<(), OutboxError> {
+    for migrator in migrators() {
+        migrator
+            .run(pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Checks that the database schema matches what this build of the crate
+/// expects.
+///
+/// Meant to be called once at startup, before the storage is handed to
+/// the manager, so a missing or outdated schema fails fast instead of
+/// surfacing as query errors on the first tick. The check relies on the
+/// `_sqlx_migrations` table written by [`migrate`].
+///
+/// # Errors
+///
+/// Returns [`OutboxError::ConfigError`] if the migrations table does not
+/// exist or any embedded migration has not been applied successfully.
+/// Returns [`OutboxError::DatabaseError`] if the check itself fails.
+pub async fn verify_schema(pool: &SqlitePool) -> Result<(), OutboxError> {
+    let initialised: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| OutboxError::DatabaseError(e.to_string()))?;
+    if !initialised {
+        return Err(OutboxError::ConfigError(
+            "Outbox schema is missing: run outbox_sqlite::migrate first".to_string(),
+        ));
+    }
+
+    let applied: HashSet<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .collect();
+
+    let missing: Vec<String> = migrators()
+        .iter()
+        .flat_map(Migrator::iter)
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(|m| format!("{} ({})", m.version, m.description))
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(OutboxError::ConfigError(format!(
+            "Outbox schema is outdated, pending migrations: {}",
+            missing.join(", ")
+        )))
+    }
+}
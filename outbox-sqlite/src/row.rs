@@ -0,0 +1,52 @@
+//! Conversions between outbox types and their SQLite column representation.
+//!
+//! SQLite has no native uuid, enum or timestamp types: ids are stored as
+//! hyphenated UUID text, the status by
+//! [`EventStatus::as_str`](outbox_core::prelude::EventStatus::as_str) and
+//! timestamps as microseconds since the Unix epoch
+//! ([`to_unix_micros`](outbox_core::prelude::to_unix_micros)), which keeps
+//! them ordered and comparable in SQL. Lists of ids are bound as a single JSON array and read
+//! back with `json_each`, the SQLite stand-in for Postgres' `= ANY($1)`.
+
+use outbox_core::prelude::{
+    Event, EventId, EventType, IdempotencyToken, OutboxError, from_unix_micros,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+
+/// Columns selected for an [`Event`], in this order:
+/// `id, idempotency_token, event_type, payload, status, created_at, locked_until`.
+pub(crate) type EventRow = (String, Option<String>, String, String, String, i64, i64);
+
+pub(crate) const EVENT_COLUMNS: &str =
+    "id, idempotency_token, event_type, payload, status, created_at, locked_until";
+
+/// Serializes `ids` as a JSON array of hyphenated UUIDs for `json_each`.
+pub(crate) fn id_list(ids: &[EventId]) -> String {
+    serde_json::Value::from(ids.iter().map(ToString::to_string).collect::<Vec<_>>()).to_string()
+}
+
+pub(crate) fn encode_payload<P>(event: &Event<P>) -> Result<String, OutboxError>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    serde_json::to_string(&event.payload).map_err(|e| OutboxError::DatabaseError(e.to_string()))
+}
+
+pub(crate) fn event_from_row<P>(row: EventRow) -> Result<Event<P>, OutboxError>
+where
+    P: Debug + Clone + Serialize + DeserializeOwned + Send + Sync,
+{
+    let (id, idempotency_token, event_type, payload, status, created_at, locked_until) = row;
+    Ok(Event {
+        id: id.parse()?,
+        idempotency_token: idempotency_token.map(IdempotencyToken::new),
+        event_type: EventType::load(&event_type),
+        payload: serde_json::from_str(&payload)
+            .map_err(|e| OutboxError::DatabaseError(e.to_string()))?,
+        status: status.parse()?,
+        created_at: from_unix_micros(created_at)?,
+        locked_until: from_unix_micros(locked_until)?,
+    })
+}
@@ -0,0 +1,189 @@
+//! Running an [`OutboxManager`] until the outbox is empty.
+
+use outbox_core::prelude::{EventStatus, OutboxError, OutboxManager, Transport};
+use outbox_memory::InMemoryOutbox;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::time::Duration;
+use tokio::sync::watch;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Whether `storage` holds no event that still has to be published — every
+/// event is either [`Sent`](EventStatus::Sent) or gone.
+pub fn is_drained<P>(storage: &InMemoryOutbox<P>) -> bool
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    storage.count_with_status(&EventStatus::Pending) == 0
+        && storage.count_with_status(&EventStatus::Processing) == 0
+}
+
+/// Runs `manager` until [`is_drained`] holds for `storage`, then sends the
+/// shutdown signal on `shutdown_tx` and waits for the manager to stop.
+///
+/// `manager` must have been built over `storage` (or a clone of it) and with
+/// the receiver of `shutdown_tx`. A failed publish leaves its event
+/// `Processing` until its lock expires, so tests that expect retries should
+/// set `lock_timeout_mins` to `0`; with the `dlq` feature, quarantined events
+/// leave the outbox and count as drained.
+///
+/// # Errors
+///
+/// Returns the manager's own error if it stopped on its own, or an
+/// [`OutboxError::InfrastructureError`] if the outbox was not drained within
+/// `timeout` (the manager is shut down in that case too).
+pub async fn run_until_drained<T, P>(
+    manager: OutboxManager<InMemoryOutbox<P>, T, P>,
+    storage: &InMemoryOutbox<P>,
+    shutdown_tx: &watch::Sender<bool>,
+    timeout: Duration,
+) -> Result<(), OutboxError>
+where
+    T: Transport<P> + Send + Sync + 'static,
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    let handle = tokio::spawn(manager.run());
+
+    let drained = tokio::time::timeout(timeout, async {
+        while !is_drained(storage) && !handle.is_finished() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .is_ok();
+
+    // The receiver is gone if the manager already stopped; nothing to signal.
+    let _ = shutdown_tx.send(true);
+    handle
+        .await
+        .map_err(|e| OutboxError::InfrastructureError(format!("outbox manager panicked: {e}")))??;
+
+    if drained && is_drained(storage) {
+        return Ok(());
+    }
+    let left = storage.count_with_status(&EventStatus::Pending)
+        + storage.count_with_status(&EventStatus::Processing);
+    Err(OutboxError::InfrastructureError(format!(
+        "outbox not drained within {timeout:?}: {left} events left"
+    )))
+}
+
+/// These tests build a real manager, which needs a DLQ heap whenever
+/// `outbox-core/dlq` is on; other workspace members turn that on, so the
+/// tests only run with this crate's `dlq` feature, where the heap is wired.
+#[cfg(all(test, feature = "dlq"))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::tests::{TestPayload, event};
+    use crate::{FaultyTransport, RecordingTransport};
+    use outbox_core::prelude::{OutboxConfig, OutboxManagerBuilder, OutboxWriter};
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn config() -> OutboxConfig<TestPayload> {
+        OutboxConfig::default().with_lock_timeout_mins(0)
+    }
+
+    async fn run<T>(
+        config: OutboxConfig<TestPayload>,
+        storage: &InMemoryOutbox<TestPayload>,
+        transport: T,
+        timeout: Duration,
+    ) -> Result<(), OutboxError>
+    where
+        T: Transport<TestPayload> + Send + Sync + 'static,
+    {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let manager = OutboxManagerBuilder::new()
+            .storage(Arc::new(storage.clone()))
+            .publisher(Arc::new(transport))
+            .config(Arc::new(config))
+            .shutdown_rx(shutdown_rx)
+            .dlq_heap(Arc::new(storage.clone()))
+            .build()
+            .unwrap();
+        run_until_drained(manager, storage, &shutdown_tx, timeout).await
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn publishes_everything_then_stops() {
+        let storage = InMemoryOutbox::new(Arc::new(config()));
+        storage
+            .insert_events(vec![event("OrderCreated", 1), event("OrderPaid", 2)])
+            .await
+            .unwrap();
+        let recorder = RecordingTransport::new();
+
+        run(config(), &storage, recorder.clone(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert!(is_drained(&storage));
+        recorder.assert_published("OrderCreated", |p| *p == TestPayload(1));
+        recorder.assert_published_times("OrderPaid", 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn retries_failed_publishes_until_they_succeed() {
+        let storage = InMemoryOutbox::new(Arc::new(config()));
+        storage
+            .insert_event(event("OrderCreated", 1))
+            .await
+            .unwrap();
+        let recorder = RecordingTransport::new();
+
+        run(
+            config(),
+            &storage,
+            FaultyTransport::new(recorder.clone()).fail_times(3),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        recorder.assert_published_times("OrderCreated", 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn reports_events_left_after_timeout() {
+        let storage = InMemoryOutbox::new(Arc::new(config()));
+        storage.insert_event(event("PoisonPill", 1)).await.unwrap();
+
+        // Never quarantined, so it stays in the outbox with or without `dlq`.
+        let config = config().with_dlq_threshold(u32::MAX);
+        let err = run(
+            config,
+            &storage,
+            FaultyTransport::new(RecordingTransport::new()).fail_event_type("PoisonPill"),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().contains("1 events left"), "{err}");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn quarantined_events_count_as_drained() {
+        let storage = InMemoryOutbox::new(Arc::new(config()));
+        storage.insert_event(event("PoisonPill", 1)).await.unwrap();
+        let config = config().with_dlq_threshold(3).with_dlq_interval_secs(1);
+
+        run(
+            config,
+            &storage,
+            FaultyTransport::new(RecordingTransport::new()).fail_event_type("PoisonPill"),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        assert!(storage.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! A [`Transport`] wrapper that injects failures and latency.
+
+use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
+use outbox_core::prelude::{Event, EventType, OutboxError, Transport};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Wraps a transport and makes some publishes fail.
+///
+/// Every publish first sleeps for the configured latency, then fails with
+/// [`OutboxError::BrokerError`] if one of the configured faults applies, and
+/// otherwise forwards the event to the wrapped transport. Faults are checked
+/// in order: events of a failing type always fail, then the first
+/// [`fail_times`](Self::fail_times) remaining publishes fail.
+///
+/// ```ignore
+/// let recorder = RecordingTransport::new();
+/// let transport = FaultyTransport::new(recorder.clone())
+///     .fail_times(2)
+///     .fail_event_type("PoisonPill")
+///     .with_latency(Duration::from_millis(5));
+/// ```
+pub struct FaultyTransport<T> {
+    inner: T,
+    remaining_failures: AtomicU32,
+    failing_types: HashSet<String>,
+    latency: Option<Duration>,
+    attempts: AtomicUsize,
+    failures: AtomicUsize,
+}
+
+impl<T> FaultyTransport<T> {
+    /// Wraps `inner` without any fault; add them with the builder methods.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            remaining_failures: AtomicU32::new(0),
+            failing_types: HashSet::new(),
+            latency: None,
+            attempts: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+        }
+    }
+
+    /// Fails the next `times` publishes, whatever their event type.
+    #[must_use]
+    pub fn fail_times(self, times: u32) -> Self {
+        self.remaining_failures.store(times, Ordering::SeqCst);
+        self
+    }
+
+    /// Fails every publish of `event_type`.
+    #[must_use]
+    pub fn fail_event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.failing_types.insert(event_type.into());
+        self
+    }
+
+    /// Delays every publish, failed or not, by `latency`.
+    #[must_use]
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// How many publishes were attempted so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// How many publishes were failed on purpose so far.
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::SeqCst)
+    }
+
+    /// The wrapped transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    async fn inject(&self, event_type: &EventType) -> Result<(), OutboxError> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+        let fail = self.failing_types.contains(event_type.as_str())
+            || self
+                .remaining_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+        if fail {
+            self.failures.fetch_add(1, Ordering::SeqCst);
+            return Err(OutboxError::BrokerError(format!(
+                "injected failure for `{event_type}`"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P, T> Transport<P> for FaultyTransport<T>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+    T: Transport<P> + Send + Sync,
+{
+    async fn publish(&self, event: Event<P>) -> Result<(), OutboxError> {
+        self.inject(&event.event_type).await?;
+        self.inner.publish(event).await
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<P>) -> Result<(), OutboxError> {
+        self.inject(&letter.event.event_type).await?;
+        self.inner.publish_dead_letter(letter).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::RecordingTransport;
+    use crate::tests::{TestPayload, event};
+    use rstest::rstest;
+
+    #[rstest]
+    #[tokio::test]
+    async fn fails_the_first_n_publishes_then_forwards() {
+        let recorder = RecordingTransport::<TestPayload>::new();
+        let transport = FaultyTransport::new(recorder.clone()).fail_times(2);
+
+        assert!(transport.publish(event("OrderCreated", 1)).await.is_err());
+        assert!(transport.publish(event("OrderCreated", 1)).await.is_err());
+        transport.publish(event("OrderCreated", 1)).await.unwrap();
+
+        assert_eq!(transport.attempts(), 3);
+        assert_eq!(transport.failures(), 2);
+        recorder.assert_published_times("OrderCreated", 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fails_every_event_of_a_failing_type() {
+        let recorder = RecordingTransport::<TestPayload>::new();
+        let transport = FaultyTransport::new(recorder.clone()).fail_event_type("PoisonPill");
+
+        for _ in 0..3 {
+            let err = transport.publish(event("PoisonPill", 1)).await.unwrap_err();
+            assert!(matches!(err, OutboxError::BrokerError(_)));
+        }
+        transport.publish(event("OrderCreated", 1)).await.unwrap();
+
+        recorder.assert_not_published("PoisonPill");
+        recorder.assert_published_times("OrderCreated", 1);
+    }
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn delays_publishes_by_the_configured_latency() {
+        let transport = FaultyTransport::new(RecordingTransport::<TestPayload>::new())
+            .with_latency(Duration::from_secs(2));
+        let started = tokio::time::Instant::now();
+
+        transport.publish(event("OrderCreated", 1)).await.unwrap();
+
+        assert!(started.elapsed() >= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,40 @@
+//! Test helpers for code built on `outbox-core`.
+//!
+//! The mocks `outbox-core` uses internally are `cfg(test)`-only, so this
+//! crate ships the pieces an application needs to test its own outbox
+//! wiring end to end:
+//!
+//! - [`RecordingTransport`] — a [`Transport`](outbox_core::prelude::Transport)
+//!   that keeps every published event and offers assertions such as
+//!   [`assert_published`](RecordingTransport::assert_published).
+//! - [`FaultyTransport`] — wraps another transport and injects failures
+//!   (the first N publishes, every event of a given type) and latency.
+//! - [`run_until_drained`] — runs an
+//!   [`OutboxManager`](outbox_core::prelude::OutboxManager) over an
+//!   [`InMemoryOutbox`](outbox_memory::InMemoryOutbox) until no event is
+//!   left to publish, then shuts it down.
+
+mod drain;
+mod faulty;
+mod recording;
+
+pub use drain::{is_drained, run_until_drained};
+pub use faulty::FaultyTransport;
+pub use recording::RecordingTransport;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use outbox_core::prelude::{Event, EventType, Payload};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    pub(crate) struct TestPayload(pub(crate) u32);
+
+    pub(crate) fn event(event_type: &str, value: u32) -> Event<TestPayload> {
+        Event::new(
+            EventType::new(event_type),
+            Payload::new(TestPayload(value)),
+            None,
+        )
+    }
+}
@@ -0,0 +1,218 @@
+//! A [`Transport`] that records what it publishes.
+
+use async_trait::async_trait;
+#[cfg(feature = "dlq")]
+use outbox_core::prelude::DeadLetter;
+use outbox_core::prelude::{Event, OutboxError, Transport};
+use serde::Serialize;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Transport that accepts every event and keeps it in memory for later
+/// assertions.
+///
+/// Clones share the recorded events, so keep one clone in the test and hand
+/// another (wrapped in an `Arc`) to the manager. The `assert_*` methods panic
+/// with a message listing what was actually published.
+pub struct RecordingTransport<P> {
+    published: Arc<Mutex<Vec<Event<P>>>>,
+    #[cfg(feature = "dlq")]
+    dead_letters: Arc<Mutex<Vec<DeadLetter<P>>>>,
+}
+
+impl<P> Clone for RecordingTransport<P> {
+    fn clone(&self) -> Self {
+        Self {
+            published: self.published.clone(),
+            #[cfg(feature = "dlq")]
+            dead_letters: self.dead_letters.clone(),
+        }
+    }
+}
+
+impl<P> Default for RecordingTransport<P> {
+    fn default() -> Self {
+        Self {
+            published: Arc::new(Mutex::new(Vec::new())),
+            #[cfg(feature = "dlq")]
+            dead_letters: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<P> RecordingTransport<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every event published so far, in publish order.
+    pub fn published(&self) -> Vec<Event<P>> {
+        lock(&self.published).clone()
+    }
+
+    /// The events of `event_type` published so far, in publish order.
+    pub fn published_of_type(&self, event_type: &str) -> Vec<Event<P>> {
+        lock(&self.published)
+            .iter()
+            .filter(|e| e.event_type.as_str() == event_type)
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets every recorded event.
+    pub fn clear(&self) {
+        lock(&self.published).clear();
+        #[cfg(feature = "dlq")]
+        lock(&self.dead_letters).clear();
+    }
+
+    /// Asserts that at least one event of `event_type` whose payload
+    /// satisfies `matches` was published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no such event was published.
+    #[track_caller]
+    pub fn assert_published(&self, event_type: &str, matches: impl Fn(&P) -> bool) {
+        let published = lock(&self.published);
+        assert!(
+            published
+                .iter()
+                .any(|e| e.event_type.as_str() == event_type && matches(e.payload.as_value())),
+            "no matching `{event_type}` event was published; published: {:?}",
+            summary(&published)
+        );
+    }
+
+    /// Asserts that exactly `times` events of `event_type` were published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of published `event_type` events differs.
+    #[track_caller]
+    pub fn assert_published_times(&self, event_type: &str, times: usize) {
+        let published = lock(&self.published);
+        let count = published
+            .iter()
+            .filter(|e| e.event_type.as_str() == event_type)
+            .count();
+        assert!(
+            count == times,
+            "expected {times} `{event_type}` events, got {count}; published: {:?}",
+            summary(&published)
+        );
+    }
+
+    /// Asserts that no event of `event_type` was published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an `event_type` event was published.
+    #[track_caller]
+    pub fn assert_not_published(&self, event_type: &str) {
+        self.assert_published_times(event_type, 0);
+    }
+
+    /// Asserts that nothing at all was published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any event was published.
+    #[track_caller]
+    pub fn assert_nothing_published(&self) {
+        let published = lock(&self.published);
+        assert!(
+            published.is_empty(),
+            "expected no events; published: {:?}",
+            summary(&published)
+        );
+    }
+
+    /// Every dead letter sent to this transport as a DLQ destination, in
+    /// publish order. Dead letters are not part of
+    /// [`published`](Self::published).
+    #[cfg(feature = "dlq")]
+    pub fn dead_letters(&self) -> Vec<DeadLetter<P>> {
+        lock(&self.dead_letters).clone()
+    }
+}
+
+#[async_trait]
+impl<P> Transport<P> for RecordingTransport<P>
+where
+    P: Debug + Clone + Serialize + Send + Sync + 'static,
+{
+    async fn publish(&self, event: Event<P>) -> Result<(), OutboxError> {
+        lock(&self.published).push(event);
+        Ok(())
+    }
+
+    #[cfg(feature = "dlq")]
+    async fn publish_dead_letter(&self, letter: DeadLetter<P>) -> Result<(), OutboxError> {
+        lock(&self.dead_letters).push(letter);
+        Ok(())
+    }
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn summary<P>(events: &[Event<P>]) -> Vec<String>
+where
+    P: Debug + Clone + Serialize + Send + Sync,
+{
+    events
+        .iter()
+        .map(|e| format!("{}({:?})", e.event_type, e.payload.as_value()))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::tests::{TestPayload, event};
+    use rstest::rstest;
+
+    #[rstest]
+    #[tokio::test]
+    async fn records_events_and_asserts_on_them() {
+        let transport = RecordingTransport::new();
+        transport.publish(event("OrderCreated", 1)).await.unwrap();
+        transport.publish(event("OrderCreated", 2)).await.unwrap();
+        transport.publish(event("OrderPaid", 1)).await.unwrap();
+
+        transport.assert_published("OrderCreated", |p| *p == TestPayload(2));
+        transport.assert_published_times("OrderCreated", 2);
+        transport.assert_not_published("OrderShipped");
+        assert_eq!(transport.published().len(), 3);
+        assert_eq!(transport.published_of_type("OrderPaid").len(), 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn clones_share_recorded_events() {
+        let transport = RecordingTransport::new();
+        let handed_out = transport.clone();
+
+        handed_out.publish(event("OrderCreated", 1)).await.unwrap();
+        transport.assert_published_times("OrderCreated", 1);
+
+        transport.clear();
+        handed_out.assert_nothing_published();
+    }
+
+    #[rstest]
+    #[should_panic(expected = "no matching `OrderCreated` event was published")]
+    #[tokio::test]
+    async fn assert_published_panics_without_matching_payload() {
+        let transport = RecordingTransport::new();
+        transport.publish(event("OrderCreated", 1)).await.unwrap();
+
+        transport.assert_published("OrderCreated", |p| *p == TestPayload(2));
+    }
+}